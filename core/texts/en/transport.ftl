@@ -1,2 +1,5 @@
 transport-group_solid_name = Solid
 transport-method_manual_name = Manual
+transport-group_liquid_name = Liquid
+transport-group_gas_name = Gas
+transport-method_manual_description = Carried by people
@@ -2,3 +2,23 @@ ui_load_load =
     Load {$save_name}
     Date: {$saved_date}
     Time played: {$play_time}
+ui_load_changed-components = Components changed since {$save_name} was saved, parts of the game referring to missing configs will be dropped:
+ui_load_component-added = Added: {$component} {$version}
+ui_load_component-removed = Removed: {$component} {$version}
+ui_load_component-changed = Changed: {$component} {$saved_version} → {$version}
+ui_load_continue = Continue
+ui_load_summary = Tick: {$tick}, population: {$population}
+ui_load_components = Components: {$components}
+ui_load_rename = Rename
+ui_load_duplicate = Duplicate
+ui_load_export = Export
+ui_load_delete = Delete
+ui_load_import = Import
+ui_load_import-hint = Path to a save file
+ui_load_rename-prompt = Rename {$save_name} to:
+ui_load_duplicate-prompt = Duplicate {$save_name} as:
+ui_load_export-prompt = Export {$save_name} to the file:
+ui_load_delete-prompt = Delete {$save_name}? This can't be undone.
+ui_load_name-hint = Letters, digits and underscores
+ui_load_path-hint = Path to the file
+ui_load_confirm = Confirm
@@ -1,2 +1,7 @@
 ui_main_debug_header = Debug
 ui_main_debug_spawn-resources = Spawn Resources
+ui_main_debug_components = Components
+ui_main_debug_unload = Unload
+ui_main_debug_audit = Audit Texts and Textures
+ui_main_debug_audit-clean = No problems found
+ui_main_debug_export-replay = Export Replay
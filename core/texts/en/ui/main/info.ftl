@@ -4,3 +4,5 @@ ui_main_info_nutrition = Nutrition: { $nutrition }
 ui_main_info_save = Save
 ui_main_info_stats = Stats
 ui_main_info_resources = Resources
+ui_main_info_save-as = Save as
+ui_main_info_save-as-hint = New save name
@@ -4,3 +4,24 @@ ui_main_productions_builder_name = Name
 ui_main_productions_builder_window-title = Production Builder
 ui_main_productions_builder_add-production-method = Add Production Method
 ui_main_productions_builder_finish = Create
+ui_main_productions_builder_invalid = Invalid production
+ui_main_productions_open-planner = Open Planner
+ui_main_productions_planner_window-title = Production Planner
+ui_main_productions_planner_targets = Target output per tick
+ui_main_productions_planner_add-target = Add Target
+ui_main_productions_planner_recipes = Recipes
+ui_main_productions_planner_depot = From depot
+ui_main_productions_planner_units = Units
+ui_main_productions_planner_external-input = Supplied from depot
+ui_main_productions_planner_surplus = Surplus
+ui_main_productions_planner_transport = Transport weight per tick
+ui_main_productions_planner_invalid = Invalid plan
+ui_main_productions_duplicate = Duplicate
+ui_main_productions_templates = Templates
+ui_main_productions_templates_units = Units
+ui_main_productions_templates_create = Create
+ui_main_productions_templates_export = Export
+ui_main_productions_templates_import = Import
+ui_main_productions_builder_save-template = Save as Template
+ui_main_productions_builder_load-template = Load Template
+ui_main_productions_locked = Locked
@@ -1,3 +1,6 @@
 ui_main_research_header = Research
 ui_main_research_current = Currently researching
-
+ui_main_research_history = History
+ui_main_research_history-entry = { $name }: researched on tick { $tick }, { $invested } points invested
+ui_main_research_history-obsolete = obsolete since tick { $tick }
+ui_main_research_history-lost = lost on tick { $tick }
@@ -1,6 +1,7 @@
 ui_menu_title = Plemeshko
 ui_menu_new-game = New game
 ui_menu_continue = Continue
+ui_menu_mods = Mods
 ui_menu_language = Language
 ui_menu_exit = Exit
 ui_main_language-select = Select language
@@ -0,0 +1,9 @@
+ui_mods_title = Mods
+ui_mods_none = No mods found in the "mods" directory
+ui_mods_authors = Authors: { $authors }
+ui_mods_depends-on = Depends on: { $dependencies }
+ui_mods_move-up = Up
+ui_mods_move-down = Down
+ui_mods_apply = Apply
+ui_mods_applied = Mods were applied
+ui_mods_game-running = Mods can't be changed while a game is running
@@ -3,3 +3,5 @@ ui_new-game_name-hint = Letters, digits and underscores
 ui_new-game_start = Start
 ui_new-game_err-empty = Name can't be empty!
 ui_new-game_err-invalid = Name contains invalid characters!
+ui_new-game_compact-save = Compact save
+ui_new-game_compact-save-hint = Store the save compressed, smaller and faster to write but not readable by hand
@@ -1,2 +1,5 @@
 transport-group_solid_name = Твердые
 transport-method_manual_name = Вручную
+transport-group_liquid_name = Жидкие
+transport-group_gas_name = Газообразные
+transport-method_manual_description = Переносится людьми
@@ -2,3 +2,23 @@ ui_load_load =
     Загрузить {$save_name}
     Дата: {$saved_date}
     Время игры: {$play_time}
+ui_load_changed-components = Компоненты изменились с сохранения {$save_name}, части игры, ссылающиеся на отсутствующие конфигурации, будут отброшены:
+ui_load_component-added = Добавлен: {$component} {$version}
+ui_load_component-removed = Удалён: {$component} {$version}
+ui_load_component-changed = Изменён: {$component} {$saved_version} → {$version}
+ui_load_continue = Продолжить
+ui_load_summary = Ход: {$tick}, население: {$population}
+ui_load_components = Компоненты: {$components}
+ui_load_rename = Переименовать
+ui_load_duplicate = Копировать
+ui_load_export = Экспорт
+ui_load_delete = Удалить
+ui_load_import = Импорт
+ui_load_import-hint = Путь к файлу сохранения
+ui_load_rename-prompt = Переименовать {$save_name} в:
+ui_load_duplicate-prompt = Копировать {$save_name} как:
+ui_load_export-prompt = Экспортировать {$save_name} в файл:
+ui_load_delete-prompt = Удалить {$save_name}? Это нельзя отменить.
+ui_load_name-hint = Латинские буквы, цифры и '_'
+ui_load_path-hint = Путь к файлу
+ui_load_confirm = Подтвердить
@@ -1,2 +1,7 @@
 ui_main_debug_header = Дебаг
 ui_main_debug_spawn-resources = Создать ресурсы
+ui_main_debug_components = Компоненты
+ui_main_debug_unload = Выгрузить
+ui_main_debug_audit = Проверить тексты и текстуры
+ui_main_debug_audit-clean = Проблем не найдено
+ui_main_debug_export-replay = Экспортировать реплей
@@ -4,3 +4,5 @@ ui_main_info_nutrition = Питание: { $nutrition }
 ui_main_info_save = Сохранить
 ui_main_info_stats = Параметры
 ui_main_info_resources = Ресурсы
+ui_main_info_save-as = Сохранить как
+ui_main_info_save-as-hint = Название нового сохранения
@@ -4,3 +4,24 @@ ui_main_productions_builder_name = Имя
 ui_main_productions_builder_window-title = Конструктор Производств
 ui_main_productions_builder_add-production-method = Добавить метод производства
 ui_main_productions_builder_finish = Создать
+ui_main_productions_builder_invalid = Некорректное производство
+ui_main_productions_open-planner = Открыть планировщик
+ui_main_productions_planner_window-title = Планировщик производства
+ui_main_productions_planner_targets = Целевой выход за ход
+ui_main_productions_planner_add-target = Добавить цель
+ui_main_productions_planner_recipes = Способы получения
+ui_main_productions_planner_depot = Со склада
+ui_main_productions_planner_units = Количество
+ui_main_productions_planner_external-input = Поставляется со склада
+ui_main_productions_planner_surplus = Излишек
+ui_main_productions_planner_transport = Транспортируемый вес за ход
+ui_main_productions_planner_invalid = Некорректный план
+ui_main_productions_duplicate = Дублировать
+ui_main_productions_templates = Шаблоны
+ui_main_productions_templates_units = Количество
+ui_main_productions_templates_create = Создать
+ui_main_productions_templates_export = Экспортировать
+ui_main_productions_templates_import = Импортировать
+ui_main_productions_builder_save-template = Сохранить как шаблон
+ui_main_productions_builder_load-template = Загрузить шаблон
+ui_main_productions_locked = Заблокировано
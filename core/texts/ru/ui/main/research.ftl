@@ -1,2 +1,6 @@
 ui_main_research_header = Исследования
 ui_main_research_current = Сейчас исследуется
+ui_main_research_history = История
+ui_main_research_history-entry = { $name }: исследовано на ходу { $tick }, вложено { $invested } очков
+ui_main_research_history-obsolete = устарело с хода { $tick }
+ui_main_research_history-lost = утрачено на ходу { $tick }
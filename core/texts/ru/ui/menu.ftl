@@ -1,6 +1,7 @@
 ui_menu_title = Племеншко
 ui_menu_new-game = Новая игра
 ui_menu_continue = Продолжить
+ui_menu_mods = Моды
 ui_menu_language = Язык
 ui_menu_exit = Выход
 ui_main_language-select = Выберите язык
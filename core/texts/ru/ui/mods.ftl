@@ -0,0 +1,9 @@
+ui_mods_title = Моды
+ui_mods_none = В папке "mods" не найдено модов
+ui_mods_authors = Авторы: { $authors }
+ui_mods_depends-on = Зависит от: { $dependencies }
+ui_mods_move-up = Выше
+ui_mods_move-down = Ниже
+ui_mods_apply = Применить
+ui_mods_applied = Моды применены
+ui_mods_game-running = Моды нельзя менять во время игры
@@ -3,3 +3,5 @@ ui_new-game_name-hint = Латинские буквы, цифры и '_'
 ui_new-game_start = Начать
 ui_new-game_err-empty = Название не должно быть пустым!
 ui_new-game_err-invalid = Название содержит неверные символы!
+ui_new-game_compact-save = Сжатое сохранение
+ui_new-game_compact-save-hint = Хранить сохранение сжатым: оно меньше и быстрее записывается, но его нельзя прочитать вручную
@@ -33,4 +33,8 @@ impl<T> SetEvent<T> {
     pub fn get_mut(&mut self) -> Option<&mut T> {
         self.0.get_mut().as_mut()
     }
+
+    pub fn into_inner(self) -> Option<T> {
+        self.0.into_inner()
+    }
 }
@@ -1,23 +1,208 @@
+use std::path::Path;
+
 use anyhow::{Ok, Result};
-use egui::{vec2, Button};
+use egui::{vec2, Button, Color32, ScrollArea, TextEdit};
 use fluent::FluentArgs;
 
 use crate::{
     app::{env::Env, widgets::Widget},
-    state::{has::HasTexts, save::SaveMetadata},
+    state::{
+        has::HasTexts,
+        save::{
+            self, compare_components, component_fingerprints, is_valid_save_name, ComponentChange,
+            SaveMetadata,
+        },
+    },
 };
 
 use super::{AppLoadEvent, AppScreen, AppScreenTransitionEvent};
 
 struct LoadScreenId;
 
+#[derive(Clone, Copy)]
+enum SaveAction {
+    Rename,
+    Duplicate,
+    Export,
+    Delete,
+}
+
+/// Action chosen for a save, until it's confirmed along with the entered name or path.
+struct PendingAction {
+    action: SaveAction,
+    save_name: String,
+    input: String,
+}
+
 pub struct LoadScreen {
     saves: Option<Vec<(String, SaveMetadata)>>,
+    /// Save selected to load along with components changed since it was made, until loading is confirmed.
+    changes: Option<(String, Vec<ComponentChange>)>,
+    pending: Option<PendingAction>,
+    import_path: String,
+    /// Error of the last failed action, shown until another one is chosen.
+    error: Option<String>,
 }
 
 impl LoadScreen {
     pub fn new() -> Self {
-        LoadScreen { saves: None }
+        LoadScreen {
+            saves: None,
+            changes: None,
+            pending: None,
+            import_path: String::new(),
+            error: None,
+        }
+    }
+
+    fn action_ui(&mut self, env: &mut Env<'_>, ui: &mut egui::Ui) -> Result<()> {
+        let app_st = env.app_state();
+        let pending = self.pending.as_mut().unwrap();
+        let mut args = FluentArgs::new();
+        args.set("save_name", pending.save_name.clone());
+        let (prompt, hint) = match pending.action {
+            SaveAction::Rename => ("ui_load_rename-prompt", Some("ui_load_name-hint")),
+            SaveAction::Duplicate => ("ui_load_duplicate-prompt", Some("ui_load_name-hint")),
+            SaveAction::Export => ("ui_load_export-prompt", Some("ui_load_path-hint")),
+            SaveAction::Delete => ("ui_load_delete-prompt", None),
+        };
+        ui.heading(app_st.text_core_fmt(prompt, &args)?);
+        if let Some(hint) = hint {
+            ui.add(TextEdit::singleline(&mut pending.input).hint_text(app_st.text_core(hint)?));
+        }
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::RED, error);
+        }
+        let valid_input = match pending.action {
+            SaveAction::Rename | SaveAction::Duplicate => is_valid_save_name(&pending.input),
+            SaveAction::Export => !pending.input.is_empty(),
+            SaveAction::Delete => true,
+        };
+        let mut done = false;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    valid_input,
+                    Button::new(app_st.text_core("ui_load_confirm")?),
+                )
+                .clicked()
+            {
+                let name = &pending.save_name;
+                let result = match pending.action {
+                    SaveAction::Rename => save::rename(name, &pending.input),
+                    SaveAction::Duplicate => save::duplicate(name, &pending.input),
+                    SaveAction::Export => save::export(name, Path::new(&pending.input)),
+                    SaveAction::Delete => save::delete(name),
+                };
+                match result {
+                    Err(e) => self.error = Some(format!("{e:#}")),
+                    _ => done = true,
+                }
+            }
+            if ui.button(app_st.text_core("ui_generic_return")?).clicked() {
+                done = true;
+            }
+            Ok(())
+        })
+        .inner?;
+        if done {
+            self.pending = None;
+            self.error = None;
+            self.saves = None;
+        }
+        Ok(())
+    }
+
+    /// Loads the save, unless components changed since it was made, which are returned to be shown first.
+    fn select(
+        env: &Env<'_>,
+        name: &str,
+        metadata: &SaveMetadata,
+    ) -> Result<Option<Vec<ComponentChange>>> {
+        let app_st = env.app_state();
+        let changes = match &metadata.components {
+            Some(saved) => compare_components(
+                saved,
+                &component_fingerprints(app_st.component_loader.indexer(), &app_st.components)?,
+            ),
+            None => Vec::new(),
+        };
+        if !changes.is_empty() {
+            return Ok(Some(changes));
+        }
+        env.get::<AppLoadEvent>()
+            .unwrap()
+            .emit(name.to_owned(), false);
+        env.get::<AppScreenTransitionEvent>()
+            .unwrap()
+            .emit(AppScreen::Main);
+        Ok(None)
+    }
+
+    fn save_text(env: &Env<'_>, name: &str, meta: &SaveMetadata) -> Result<String> {
+        let app_st = env.app_state();
+        let mut args = FluentArgs::new();
+        args.set("save_name", name.to_owned());
+        args.set("saved_date", meta.saved_date.to_string());
+        args.set("play_time", meta.play_time.as_secs());
+        let mut text = app_st.text_core_fmt("ui_load_load", &args)?.into_owned();
+        if let Some(summary) = &meta.summary {
+            let mut args = FluentArgs::new();
+            args.set("tick", summary.tick.0);
+            args.set("population", summary.population.0);
+            text.push('\n');
+            text.push_str(&app_st.text_core_fmt("ui_load_summary", &args)?);
+        }
+        if let Some(components) = &meta.components {
+            let mut args = FluentArgs::new();
+            let names: Vec<&str> = components.iter().map(|c| c.name.as_str()).collect();
+            args.set("components", names.join(", "));
+            text.push('\n');
+            text.push_str(&app_st.text_core_fmt("ui_load_components", &args)?);
+        }
+        Ok(text)
+    }
+
+    fn changes_ui(&mut self, env: &mut Env<'_>, ui: &mut egui::Ui) -> Result<()> {
+        let app_st = env.app_state();
+        let (name, changes) = self.changes.as_ref().unwrap();
+        let mut args = FluentArgs::new();
+        args.set("save_name", name.to_owned());
+        ui.heading(app_st.text_core_fmt("ui_load_changed-components", &args)?);
+        for change in changes {
+            let mut args = FluentArgs::new();
+            let text = match change {
+                ComponentChange::Added(loaded) => {
+                    args.set("component", loaded.name.clone());
+                    args.set("version", loaded.version.to_string());
+                    "ui_load_component-added"
+                }
+                ComponentChange::Removed(saved) => {
+                    args.set("component", saved.name.clone());
+                    args.set("version", saved.version.to_string());
+                    "ui_load_component-removed"
+                }
+                ComponentChange::Changed { saved, loaded } => {
+                    args.set("component", loaded.name.clone());
+                    args.set("saved_version", saved.version.to_string());
+                    args.set("version", loaded.version.to_string());
+                    "ui_load_component-changed"
+                }
+            };
+            ui.label(app_st.text_core_fmt(text, &args)?);
+        }
+        ui.add_space(ui.spacing().item_spacing.y * 4.);
+        if ui.button(app_st.text_core("ui_load_continue")?).clicked() {
+            let (name, _) = self.changes.take().unwrap();
+            env.get::<AppLoadEvent>().unwrap().emit(name, true);
+            env.get::<AppScreenTransitionEvent>()
+                .unwrap()
+                .emit(AppScreen::Main);
+            self.saves = None;
+        } else if ui.button(app_st.text_core("ui_generic_return")?).clicked() {
+            self.changes = None;
+        }
+        Ok(())
     }
 }
 
@@ -25,16 +210,17 @@ impl Widget for LoadScreen {
     type Response = ();
 
     fn ui(&mut self, env: &mut Env<'_>, ui: &mut egui::Ui) -> Result<Self::Response> {
+        if self.changes.is_some() {
+            return self.changes_ui(env, ui);
+        }
+        if self.pending.is_some() {
+            return self.action_ui(env, ui);
+        }
         if self.saves.is_none() {
-            let saves = crate::state::save::saves()?;
-            if saves.len() == 1 {
-                env.get::<AppLoadEvent>().unwrap().emit(saves[0].0.clone());
-                env.get::<AppScreenTransitionEvent>()
-                    .unwrap()
-                    .emit(AppScreen::Main);
-                return Ok(());
-            }
-            self.saves = Some(saves);
+            self.saves = Some(save::saves().unwrap_or_else(|e| {
+                self.error = Some(format!("{e:#}"));
+                Vec::new()
+            }));
         }
         let app_st = env.app_state();
         let mut ev_refresh = false;
@@ -43,26 +229,6 @@ impl Widget for LoadScreen {
             (ui.available_width() / 4.).min(300.),
             (ui.available_height() / 6.).min(200.),
         );
-        for (name, meta) in saves {
-            let mut args = FluentArgs::new();
-            args.set("save_name", name.to_owned());
-            args.set("saved_date", meta.saved_date.to_string());
-            args.set("play_time", meta.play_time.as_secs());
-            if ui
-                .add_sized(
-                    btnsz,
-                    Button::new(app_st.text_core_fmt("ui_load_load", &args)?),
-                )
-                .clicked()
-            {
-                env.get::<AppLoadEvent>().unwrap().emit(name.clone());
-                env.get::<AppScreenTransitionEvent>()
-                    .unwrap()
-                    .emit(AppScreen::Main);
-                ev_refresh = true;
-            }
-        }
-        ui.add_space(btnsz.y * 0.5);
         if ui
             .add_sized(
                 vec2(btnsz.x, btnsz.y * 0.5),
@@ -75,6 +241,83 @@ impl Widget for LoadScreen {
                 .emit(AppScreen::Menu);
             ev_refresh = true;
         }
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut self.import_path)
+                    .hint_text(app_st.text_core("ui_load_import-hint")?),
+            );
+            if ui
+                .add_enabled(
+                    !self.import_path.is_empty(),
+                    Button::new(app_st.text_core("ui_load_import")?),
+                )
+                .clicked()
+            {
+                match save::import(Path::new(&self.import_path)) {
+                    Err(e) => self.error = Some(format!("{e:#}")),
+                    _ => {
+                        self.import_path.clear();
+                        self.error = None;
+                        ev_refresh = true;
+                    }
+                }
+            }
+            Ok(())
+        })
+        .inner?;
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::RED, error);
+        }
+        ui.add_space(btnsz.y * 0.5);
+        let selected = ScrollArea::vertical()
+            .show_rows(ui, btnsz.y, saves.len(), |ui, rows| {
+                for (name, meta) in &saves[rows] {
+                    let action = ui
+                        .horizontal(|ui| {
+                            if ui
+                                .add_sized(btnsz, Button::new(Self::save_text(env, name, meta)?))
+                                .clicked()
+                            {
+                                return Ok(Some(None));
+                            }
+                            ui.vertical(|ui| {
+                                for (action, text) in [
+                                    (SaveAction::Rename, "ui_load_rename"),
+                                    (SaveAction::Duplicate, "ui_load_duplicate"),
+                                    (SaveAction::Export, "ui_load_export"),
+                                    (SaveAction::Delete, "ui_load_delete"),
+                                ] {
+                                    if ui.button(app_st.text_core(text)?).clicked() {
+                                        return Ok(Some(Some(action)));
+                                    }
+                                }
+                                Ok(None)
+                            })
+                            .inner
+                        })
+                        .inner?;
+                    if let Some(action) = action {
+                        return Ok(Some((name, meta, action)));
+                    }
+                }
+                Ok(None)
+            })
+            .inner?;
+        match selected {
+            Some((name, meta, None)) => match Self::select(env, name, meta)? {
+                Some(changes) => self.changes = Some((name.clone(), changes)),
+                None => ev_refresh = true,
+            },
+            Some((name, _, Some(action))) => {
+                self.pending = Some(PendingAction {
+                    action,
+                    save_name: name.clone(),
+                    input: String::new(),
+                });
+                self.error = None;
+            }
+            None => (),
+        }
         if ev_refresh {
             self.saves = None;
         }
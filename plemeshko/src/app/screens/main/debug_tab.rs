@@ -1,21 +1,30 @@
+use std::path::PathBuf;
+
 use anyhow::Result;
-use egui::WidgetText;
+use egui::{Color32, ScrollArea, WidgetText};
 
 use crate::{
     app::{
         env::Env,
+        screens::AppUnloadComponentEvent,
         widgets::{Tab, Widget},
     },
+    sim::command::SimCommand,
     state::{
+        audit::audit_assets,
+        components::ComponentId,
         has::{HasSimMutex, HasTexts},
-        AppState,
+        replay, AppState,
     },
-    util::cor::Cor,
 };
 
 pub struct MainScreenDebugTab {
     spawn_resource_name: String,
     spawn_resource_value: String,
+    /// Problems found by the last audit of texts and textures.
+    audit: Option<Vec<String>>,
+    /// Path of the last exported replay, or the error exporting it.
+    replay: Option<Result<PathBuf>>,
 }
 
 impl MainScreenDebugTab {
@@ -23,6 +32,8 @@ impl MainScreenDebugTab {
         MainScreenDebugTab {
             spawn_resource_name: "human".to_string(),
             spawn_resource_value: "10".to_string(),
+            audit: None,
+            replay: None,
         }
     }
 }
@@ -48,15 +59,83 @@ impl Widget for MainScreenDebugTab {
             .button(app_st.text_core("ui_main_debug_spawn-resources")?)
             .clicked()
         {
-            sim.depot.cor_put(
-                &shared_comps
-                    .core()?
-                    .configs
-                    .id_from_raw(self.spawn_resource_name.as_str())
-                    .unwrap()
-                    .in_core(),
-                self.spawn_resource_value.parse().unwrap(),
-            );
+            sim.apply(
+                shared_comps,
+                SimCommand::SpawnResource {
+                    resource: shared_comps
+                        .core()?
+                        .configs
+                        .id_from_raw(self.spawn_resource_name.as_str())
+                        .unwrap()
+                        .in_core(),
+                    amount: self.spawn_resource_value.parse().unwrap(),
+                },
+            )?;
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .button(app_st.text_core("ui_main_debug_export-replay")?)
+                .clicked()
+            {
+                self.replay = Some(replay::record(app_st, shared_comps, sim));
+            }
+            match &self.replay {
+                Some(Err(e)) => {
+                    ui.colored_label(Color32::RED, format!("{e:#}"));
+                }
+                Some(Ok(path)) => {
+                    ui.label(path.display().to_string());
+                }
+                None => (),
+            }
+            anyhow::Ok(())
+        })
+        .inner?;
+        ui.separator();
+        ui.label(app_st.text_core("ui_main_debug_components")?);
+        for (id, component) in app_st.components.iter_components() {
+            ui.horizontal(|ui| {
+                let label = app_st.component_loader.indexer().label(id)?;
+                ui.label(format!(
+                    "{} {} ({label})",
+                    component.manifest.name, component.manifest.version
+                ));
+                if ui
+                    .add_enabled(
+                        id != ComponentId::core(),
+                        egui::Button::new(app_st.text_core("ui_main_debug_unload")?),
+                    )
+                    .clicked()
+                {
+                    env.get::<AppUnloadComponentEvent>().unwrap().emit(id);
+                }
+                anyhow::Ok(())
+            })
+            .inner?;
+        }
+        ui.separator();
+        if ui
+            .button(app_st.text_core("ui_main_debug_audit")?)
+            .clicked()
+        {
+            self.audit = Some(audit_assets(
+                &app_st.component_loader,
+                shared_comps,
+                &app_st.components,
+            )?);
+        }
+        match &self.audit {
+            Some(problems) if problems.is_empty() => {
+                ui.label(app_st.text_core("ui_main_debug_audit-clean")?);
+            }
+            Some(problems) => {
+                ScrollArea::vertical().show(ui, |ui| {
+                    for problem in problems {
+                        ui.label(problem);
+                    }
+                });
+            }
+            None => (),
         }
         Ok(())
     }
@@ -4,7 +4,7 @@ use std::{
 };
 
 use anyhow::{Ok, Result};
-use egui::{vec2, Color32};
+use egui::{vec2, Button, Color32, TextEdit};
 use fluent::FluentArgs;
 
 use crate::{
@@ -17,6 +17,7 @@ use crate::{
     sim::{config::resource::ResourceId, units::ResourceAmount},
     state::{
         has::{HasSimMutex, HasTexts},
+        save::{self, is_valid_save_name},
         AppState,
     },
 };
@@ -26,6 +27,7 @@ pub struct MainScreenInfoTab {
     depot_change: HashMap<ResourceId, ResourceAmount>,
     previous_nutrition: Option<i64>,
     nutrition_change: i64,
+    save_as_name: String,
 }
 
 impl MainScreenInfoTab {
@@ -35,6 +37,7 @@ impl MainScreenInfoTab {
             depot_change: HashMap::new(),
             previous_nutrition: None,
             nutrition_change: 0,
+            save_as_name: String::new(),
         }
     }
 }
@@ -45,11 +48,29 @@ impl Widget for MainScreenInfoTab {
     fn ui(&mut self, env: &mut Env<'_>, ui: &mut egui::Ui) -> Result<Self::Response> {
         let app_st = env.app_state();
         let shared_comps = env.shared_components();
+        let human_id = shared_comps.human_id()?;
         ui.horizontal(|ui| {
-            ui.label(app_st.session.as_ref().unwrap());
+            ui.label(&app_st.session.as_ref().unwrap().name);
             if ui.button(app_st.text_core("ui_main_info_save")?).clicked() {
                 env.get::<AppSaveEvent>().unwrap().emit();
             }
+            ui.add(
+                TextEdit::singleline(&mut self.save_as_name)
+                    .hint_text(app_st.text_core("ui_main_info_save-as-hint")?),
+            );
+            let can_save_as =
+                is_valid_save_name(&self.save_as_name) && !save::save_exists(&self.save_as_name)?;
+            if ui
+                .add_enabled(
+                    can_save_as,
+                    Button::new(app_st.text_core("ui_main_info_save-as")?),
+                )
+                .clicked()
+            {
+                env.get::<AppSaveEvent>()
+                    .unwrap()
+                    .emit_as(std::mem::take(&mut self.save_as_name));
+            }
             Ok(())
         })
         .inner?;
@@ -61,11 +82,11 @@ impl Widget for MainScreenInfoTab {
                 let mut args = FluentArgs::new();
                 let mut population_count_text = sim
                     .depot
-                    .get(&app_st.shared.human_id)
+                    .get(&human_id)
                     .copied()
                     .unwrap_or_default()
                     .to_string();
-                if let Some(change) = self.depot_change.get(&app_st.shared.human_id) {
+                if let Some(change) = self.depot_change.get(&human_id) {
                     population_count_text += " (";
                     if change.0 > 0 {
                         population_count_text.push('+');
@@ -95,7 +116,7 @@ impl Widget for MainScreenInfoTab {
         ui.label(app_st.text_core("ui_main_info_resources")?);
         ui.indent("resources", |ui| {
             for (&id, &value) in sim.depot.iter() {
-                if id != app_st.shared.human_id {
+                if id != human_id {
                     let res = shared_comps.config(id)?;
                     let change = self.depot_change.get(&id);
                     ui.horizontal(|ui| {
@@ -1,7 +1,7 @@
 use std::borrow::Cow;
 
 use anyhow::{Ok, Result};
-use egui::{vec2, Button, Color32, RichText};
+use egui::{vec2, Button, Color32, DragValue, RichText};
 use tap::Conv;
 
 use crate::{
@@ -13,44 +13,157 @@ use crate::{
         },
         widgets::{PersistentWindow, Tab, Widget},
     },
-    sim::{config::resource::ResourceMap, production::Production},
+    sim::{command::SimCommand, production::Production, Sim},
     state::{
-        components::SharedComponents,
+        components::{ComponentsRef, SharedComponents},
         has::{HasSimMutex, HasTexts},
-        AppState,
+        template, AppState,
     },
     util::cor::Cor,
 };
 
-use self::production_menu::ProductionBuilder;
+use self::{planner::ProductionPlanner, production_menu::ProductionBuilder};
 
+mod planner;
 mod production_menu;
 
 pub struct MainScreenProductionsTab {
     production_menu: PersistentWindow<ProductionBuilder, ProductionBuilder>,
+    planner: PersistentWindow<ProductionPlanner, ProductionPlanner>,
+    template_count: u32,
+    template_error: Option<String>,
 }
 
 impl MainScreenProductionsTab {
     pub fn new() -> Self {
         MainScreenProductionsTab {
             production_menu: PersistentWindow::new(ProductionBuilder::new()),
+            planner: PersistentWindow::new(ProductionPlanner::new()),
+            template_count: 1,
+            template_error: None,
         }
     }
+
+    fn ui_templates(
+        &mut self,
+        app_st: &AppState,
+        shared_comps: &SharedComponents,
+        ui: &mut egui::Ui,
+        sim: &mut Sim,
+    ) -> Result<()> {
+        let components = ComponentsRef {
+            indexer: app_st.component_loader.indexer(),
+            app: &app_st.components,
+            shared: shared_comps,
+            dropped: None,
+        };
+        ui.horizontal(|ui| {
+            ui.label(app_st.text_core("ui_main_productions_templates_units")?);
+            ui.add(DragValue::new(&mut self.template_count).clamp_range(1..=u32::MAX));
+            Ok(())
+        })
+        .inner?;
+        let mut template_index = 0;
+        while template_index < sim.templates.len() {
+            let mut command = None;
+            let template = &sim.templates[template_index];
+            let production = Production::from_template(
+                shared_comps,
+                &sim.research,
+                template.clone(),
+                self.template_count,
+            );
+            let removed = ui
+                .horizontal(|ui| {
+                    ui.strong(&template.name);
+                    let can_create = production.as_ref().is_ok_and(|production| {
+                        sim.depot
+                            .cor_has_all_times(production.cost(), self.template_count as i64)
+                            >= self.template_count as i64
+                    });
+                    let create_response = ui.add_enabled(
+                        can_create,
+                        Button::new(app_st.text_core("ui_main_productions_templates_create")?),
+                    );
+                    if let Err(e) = &production {
+                        create_response.on_disabled_hover_text(format!("{e:#}"));
+                    } else if create_response.clicked() {
+                        command = Some(SimCommand::CreateProduction {
+                            template: template.clone(),
+                            count: self.template_count,
+                            index: sim.productions.len(),
+                        });
+                    }
+                    if ui
+                        .button(app_st.text_core("ui_main_productions_templates_export")?)
+                        .clicked()
+                    {
+                        self.template_error = template::export(template.clone(), components)
+                            .err()
+                            .map(|e| format!("{e:#}"));
+                    }
+                    Ok(ui.button(app_st.text_core("ui_generic_delete")?).clicked())
+                })
+                .inner?;
+            if removed {
+                command = Some(SimCommand::RemoveTemplate {
+                    index: template_index,
+                });
+            }
+            if let Some(command) = command {
+                sim.apply(shared_comps, command)?;
+            }
+            if !removed {
+                template_index += 1;
+            }
+        }
+        let imported = ui
+            .menu_button(
+                app_st.text_core("ui_main_productions_templates_import")?,
+                |ui| {
+                    for name in template::templates()? {
+                        if ui.button(&name).clicked() {
+                            ui.close_menu();
+                            match template::import(&name, components) {
+                                Result::Ok(template) => {
+                                    self.template_error = None;
+                                    return Ok(Some(template));
+                                }
+                                Err(e) => self.template_error = Some(format!("{e:#}")),
+                            }
+                        }
+                    }
+                    Ok(None)
+                },
+            )
+            .inner
+            .transpose()?
+            .flatten();
+        if let Some(template) = imported {
+            sim.apply(shared_comps, SimCommand::SaveTemplate(template))?;
+        }
+        if let Some(error) = &self.template_error {
+            ui.colored_label(Color32::from_rgb(240, 160, 160), error);
+        }
+        Ok(())
+    }
 }
 
+/// Draws the production, returning the command issued for it.
 fn ui_production(
     app_st: &AppState,
     shared_comps: &SharedComponents,
     ctx: &egui::Context,
     ui: &mut egui::Ui,
     production_index: usize,
-    productions: &mut Vec<Production>,
-    depot: &mut ResourceMap,
-) -> Result<bool> {
+    sim: &Sim,
+) -> Result<Option<SimCommand>> {
     ui.separator();
-    let removed = ui
+    let depot = &sim.depot;
+    let production = &sim.productions[production_index];
+    let command = ui
         .horizontal(|ui| {
-            let production = &mut productions[production_index];
+            let mut command = None;
 
             let prodname_response = ui.strong(format!("{}:", production.name()));
             let prodname_tt_shift = prodname_response.ctx.input().modifiers.shift_only();
@@ -72,6 +185,14 @@ fn ui_production(
                 Cow::Borrowed(production.last_io())
             });
 
+            let locked = production.is_locked(shared_comps, &sim.research);
+            if locked {
+                ui.colored_label(
+                    Color32::from_rgb(240, 160, 160),
+                    app_st.text_core("ui_main_productions_locked")?,
+                );
+            }
+
             let inactive = production.count() - production.active();
             let can_grow = depot.cor_has_all_times(
                 production.cost(),
@@ -89,7 +210,7 @@ fn ui_production(
                 (false, true) => 10,
                 _ => 1,
             };
-            let (color, enabled) = if can_grow >= grow as i64 {
+            let (color, enabled) = if !locked && can_grow >= grow as i64 {
                 (Color32::WHITE, true)
             } else {
                 (Color32::from_rgb(255, 200, 200), false)
@@ -97,14 +218,10 @@ fn ui_production(
             let grow_response =
                 ui.add(Button::new(RichText::new("+").color(color)).min_size(vec2(16.0, 16.0)));
             if enabled && grow_response.clicked() {
-                let new_active = production.active() + grow;
-                if new_active > production.count() {
-                    production.set_count(new_active);
-                }
-                production.set_active(new_active);
-                if inactive < grow {
-                    depot.cor_sub_all_times_unchecked(production.cost(), (grow - inactive) as i64);
-                }
+                command = Some(SimCommand::ActivateProduction {
+                    index: production_index,
+                    delta: grow,
+                });
             }
             if !production.cost().is_empty() {
                 grow_response.on_hover_ui(|ui| {
@@ -135,22 +252,33 @@ fn ui_production(
                 &ui.add(Button::new("-").min_size(vec2(16.0, 16.0))),
                 egui::Response::clicked,
                 |m| {
-                    let delta = m.elim(1, 10, 100);
-                    let new_active = if production.active() > delta {
-                        production.active() - delta
-                    } else {
-                        0
-                    };
-                    production.set_active(new_active);
+                    command = Some(SimCommand::DeactivateProduction {
+                        index: production_index,
+                        delta: m.elim(1, 10, 100),
+                    });
                 },
             );
 
-            if ui.button(app_st.text_core("ui_generic_delete")?).clicked() {
-                productions.remove(production_index);
-                return Ok(true);
+            let can_duplicate = !locked && depot.cor_has_all(production.cost());
+            if ui
+                .add_enabled(
+                    can_duplicate,
+                    Button::new(app_st.text_core("ui_main_productions_duplicate")?),
+                )
+                .clicked()
+            {
+                command = Some(SimCommand::CreateProduction {
+                    template: production.template(),
+                    count: 1,
+                    index: production_index + 1,
+                });
             }
 
-            let production = &mut productions[production_index];
+            if ui.button(app_st.text_core("ui_generic_delete")?).clicked() {
+                return Ok(Some(SimCommand::RemoveProduction {
+                    index: production_index,
+                }));
+            }
 
             for transport in production.transport().values().configs(shared_comps) {
                 let transport_group = shared_comps.config(transport.group)?;
@@ -163,15 +291,15 @@ fn ui_production(
                         transport.capacity
                     ));
             }
-            Ok(false)
+            Ok(command)
         })
         .inner?;
 
-    if removed {
-        return Ok(true);
+    if let Some(SimCommand::RemoveProduction { .. }) = command {
+        return Ok(command);
     }
 
-    for selected_method in productions[production_index].methods() {
+    for selected_method in production.methods() {
         let method = shared_comps.config(selected_method.id)?;
         ui.horizontal(|ui| {
             ui.label(app_st.text(&method.info.name)?);
@@ -183,39 +311,53 @@ fn ui_production(
         })
         .inner?;
     }
-    Ok(false)
+    Ok(command)
 }
 
 impl Widget for MainScreenProductionsTab {
     type Response = ();
 
     fn ui(&mut self, env: &mut Env<'_>, ui: &mut egui::Ui) -> Result<Self::Response> {
-        if ui
-            .button(
-                env.app_state()
-                    .text_core("ui_main_productions_open-builder")?,
-            )
-            .clicked()
-        {
-            self.production_menu.is_open = true;
-        }
+        ui.horizontal(|ui| {
+            if ui
+                .button(
+                    env.app_state()
+                        .text_core("ui_main_productions_open-builder")?,
+                )
+                .clicked()
+            {
+                self.production_menu.is_open = true;
+            }
+            if ui
+                .button(
+                    env.app_state()
+                        .text_core("ui_main_productions_open-planner")?,
+                )
+                .clicked()
+            {
+                self.planner.is_open = true;
+            }
+            Ok(())
+        })
+        .inner?;
         self.production_menu.ui(env, ui)?;
+        self.planner.ui(env, ui)?;
         let app_st = env.app_state();
         let ctx = env.get::<egui::Context>().unwrap();
         let shared_comps = env.shared_components();
         let mut sim_guard = app_st.lock_sim();
         let sim = sim_guard.as_mut().unwrap();
+        egui::CollapsingHeader::new(app_st.text_core("ui_main_productions_templates")?)
+            .show(ui, |ui| self.ui_templates(app_st, shared_comps, ui, sim))
+            .body_returned
+            .transpose()?;
         let mut production_index = 0;
         while production_index < sim.productions.len() {
-            let removed = ui_production(
-                app_st,
-                shared_comps,
-                ctx,
-                ui,
-                production_index,
-                &mut sim.productions,
-                &mut sim.depot,
-            )?;
+            let command = ui_production(app_st, shared_comps, ctx, ui, production_index, sim)?;
+            let removed = matches!(command, Some(SimCommand::RemoveProduction { .. }));
+            if let Some(command) = command {
+                sim.apply(shared_comps, command)?;
+            }
             if !removed {
                 production_index += 1;
             }
@@ -0,0 +1,253 @@
+use std::{collections::HashMap, fmt::Write};
+
+use anyhow::{Ok, Result};
+use egui::{vec2, Color32, ComboBox, DragValue, SelectableLabel};
+
+use crate::{
+    app::{
+        env::Env,
+        util::{draw_icon_with_tooltip, draw_resource_io, draw_resource_map_labeled},
+        widgets::{PersistentWindowContent, Widget},
+    },
+    sim::{
+        config::{
+            production_method::FixedProductionMethod,
+            resource::{Resource, ResourceId, ResourceIo, ResourceMap},
+        },
+        planner::{plan, recipe_candidates, RecipeChoice},
+        units::ResourceAmount,
+    },
+    state::{
+        components::SharedComponents,
+        has::{HasSimMutex, HasTexts},
+        AppState,
+    },
+    util::cor::Cor,
+};
+
+/// Calculates production units needed for the target output, without creating any productions.
+#[derive(Default)]
+pub struct ProductionPlanner {
+    targets: Vec<(ResourceId, i64)>,
+    choices: HashMap<ResourceId, RecipeChoice>,
+}
+
+impl ProductionPlanner {
+    pub fn new() -> Self {
+        ProductionPlanner::default()
+    }
+}
+
+fn recipe_name(
+    app_st: &AppState,
+    shared_comps: &SharedComponents,
+    method: &FixedProductionMethod,
+) -> Result<String> {
+    let mut name = app_st
+        .text(&shared_comps.config(method.id)?.info.name)?
+        .into_owned();
+    for (index, &setting_id) in method.settings.iter().enumerate() {
+        let separator = if index == 0 { " (" } else { ", " };
+        write!(
+            name,
+            "{separator}{}",
+            app_st.text(&shared_comps.config(setting_id)?.name)?
+        )?;
+    }
+    if !method.settings.is_empty() {
+        name.push(')');
+    }
+    Ok(name)
+}
+
+impl Widget for ProductionPlanner {
+    type Response = ();
+
+    fn ui(&mut self, env: &mut Env<'_>, ui: &mut egui::Ui) -> Result<Self::Response> {
+        let app_st = env.app_state();
+        let shared_comps = env.shared_components();
+        let ctx = env.get::<egui::Context>().unwrap();
+        let sim_guard = app_st.lock_sim();
+        let sim = sim_guard.as_ref().unwrap();
+
+        ui.strong(app_st.text_core("ui_main_productions_planner_targets")?);
+        let mut removed_target = None;
+        for (index, (resource_id, amount)) in self.targets.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                let info = &shared_comps.config(*resource_id)?.info;
+                draw_icon_with_tooltip(app_st, ctx, ui, info, vec2(24., 24.), |i| i, |_| ())?;
+                ui.label(app_st.text(&info.name)?);
+                ui.add(DragValue::new(amount).clamp_range(0..=i64::MAX));
+                if ui.button("X").clicked() {
+                    removed_target = Some(index);
+                }
+                Ok(())
+            })
+            .inner?;
+        }
+        if let Some(index) = removed_target {
+            self.targets.remove(index);
+        }
+        ui.menu_button(
+            app_st.text_core("ui_main_productions_planner_add-target")?,
+            |ui| {
+                for resource in shared_comps.iter_configs::<Resource>() {
+                    let (resource_id, resource) = resource?;
+                    if self.targets.iter().any(|(id, _)| *id == resource_id) {
+                        continue;
+                    }
+                    if ui.button(app_st.text(&resource.info.name)?).clicked() {
+                        self.targets.push((resource_id, 10));
+                        ui.close_menu();
+                    }
+                }
+                Ok(())
+            },
+        )
+        .inner
+        .transpose()?;
+
+        let targets: ResourceMap = self
+            .targets
+            .iter()
+            .map(|&(id, amount)| (id, ResourceAmount(amount)))
+            .collect();
+        let plan = match plan(shared_comps, &sim.research, &targets, &self.choices) {
+            Result::Ok(plan) => plan,
+            Err(e) => {
+                ui.colored_label(
+                    Color32::from_rgb(240, 160, 160),
+                    format!(
+                        "{}: {e:#}",
+                        app_st.text_core("ui_main_productions_planner_invalid")?
+                    ),
+                );
+                return Ok(());
+            }
+        };
+
+        if !plan.resolved.is_empty() {
+            ui.separator();
+            ui.strong(app_st.text_core("ui_main_productions_planner_recipes")?);
+        }
+        let depot_text = app_st.text_core("ui_main_productions_planner_depot")?;
+        for &(resource_id, step_index) in &plan.resolved {
+            ui.horizontal(|ui| {
+                let info = &shared_comps.config(resource_id)?.info;
+                draw_icon_with_tooltip(app_st, ctx, ui, info, vec2(24., 24.), |i| i, |_| ())?;
+                let selected_text = match step_index {
+                    Some(step_index) => {
+                        recipe_name(app_st, shared_comps, &plan.steps[step_index].method)?
+                    }
+                    None => depot_text.clone().into_owned(),
+                };
+                ComboBox::from_id_source(("planner recipe", resource_id))
+                    .width(300.0)
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        if ui
+                            .add(SelectableLabel::new(
+                                step_index.is_none(),
+                                depot_text.as_ref(),
+                            ))
+                            .clicked()
+                        {
+                            self.choices.insert(resource_id, RecipeChoice::Depot);
+                        }
+                        for candidate in
+                            recipe_candidates(shared_comps, &sim.research, resource_id)?
+                        {
+                            let selected = step_index
+                                .is_some_and(|index| plan.steps[index].method == candidate);
+                            let name = recipe_name(app_st, shared_comps, &candidate)?;
+                            if ui.add(SelectableLabel::new(selected, name)).clicked() {
+                                self.choices
+                                    .insert(resource_id, RecipeChoice::Method(candidate));
+                            }
+                        }
+                        Ok(())
+                    })
+                    .inner
+                    .transpose()?;
+                Ok(())
+            })
+            .inner?;
+        }
+
+        if !plan.steps.is_empty() {
+            ui.separator();
+            ui.strong(app_st.text_core("ui_main_productions_planner_units")?);
+        }
+        for step in &plan.steps {
+            let name = recipe_name(app_st, shared_comps, &step.method)?;
+            let mut hover_result = Ok(());
+            ui.label(format!("{} × {name}", step.units))
+                .on_hover_ui(|ui| {
+                    let mut io = ResourceIo::new();
+                    io.input
+                        .cor_put_all_times(&step.single_io.input, step.units);
+                    io.output
+                        .cor_put_all_times(&step.single_io.output, step.units);
+                    hover_result = draw_resource_io(app_st, shared_comps, ctx, ui, &io);
+                });
+            hover_result?;
+        }
+
+        ui.separator();
+        draw_resource_io(app_st, shared_comps, ctx, ui, &plan.total_io)?;
+        draw_resource_map_labeled(
+            app_st,
+            shared_comps,
+            ctx,
+            ui,
+            &plan.external_input,
+            app_st.text_core("ui_main_productions_planner_external-input")?,
+            true,
+        )?;
+        draw_resource_map_labeled(
+            app_st,
+            shared_comps,
+            ctx,
+            ui,
+            &plan.surplus,
+            app_st.text_core("ui_main_productions_planner_surplus")?,
+            true,
+        )?;
+        draw_resource_map_labeled(
+            app_st,
+            shared_comps,
+            ctx,
+            ui,
+            &plan.cost,
+            app_st.text_core("ui_generic_cost")?,
+            true,
+        )?;
+        if !plan.transport_weight.is_empty() {
+            ui.strong(app_st.text_core("ui_main_productions_planner_transport")?);
+            let mut transport_weight: Vec<_> = plan.transport_weight.into_iter().collect();
+            transport_weight.sort_by_key(|(id, _)| *id);
+            ui.indent("transport weight", |ui| {
+                for (transport_group_id, weight) in transport_weight {
+                    let transport_group = shared_comps.config(transport_group_id)?;
+                    ui.label(format!("{}: {weight}", app_st.text(&transport_group.name)?));
+                }
+                Ok(())
+            })
+            .inner?;
+        }
+
+        ui.separator();
+        if ui.button(app_st.text_core("ui_generic_clear")?).clicked() {
+            *self = Default::default();
+        }
+        Ok(())
+    }
+}
+
+impl PersistentWindowContent for ProductionPlanner {
+    fn title(&self, env: &Env<'_>) -> Result<egui::WidgetText> {
+        env.app_state()
+            .text_core("ui_main_productions_planner_window-title")
+            .map(Into::into)
+    }
+}
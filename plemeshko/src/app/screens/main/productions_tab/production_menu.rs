@@ -1,7 +1,7 @@
 use std::{collections::HashMap, convert::identity};
 
 use anyhow::{Ok, Result};
-use egui::{vec2, Color32, ComboBox, Ui};
+use egui::{vec2, Color32, ComboBox, SelectableLabel, Ui};
 
 use crate::{
     app::{
@@ -13,6 +13,7 @@ use crate::{
         widgets::{PersistentWindowContent, Widget, WindowCloseEvent},
     },
     sim::{
+        command::SimCommand,
         config::{
             production_method::{FixedProductionMethod, ProductionMethodId},
             production_method_group::ProductionMethodGroup,
@@ -20,15 +21,15 @@ use crate::{
             transport_group::TransportGroupId,
             transport_method::TransportMethodId,
         },
-        production::Production,
+        production::{Production, ProductionTemplate},
         Sim,
     },
     state::{
         components::SharedComponents,
         has::{HasSimMutex, HasTexts},
+        research::Research,
         AppState,
     },
-    util::cor::Cor,
 };
 
 #[derive(Default, Clone)]
@@ -47,16 +48,34 @@ impl ProductionBuilder {
         !self.name.is_empty() && !self.production_methods.is_empty()
     }
 
-    pub fn finish(&self, shared_comps: &SharedComponents) -> Result<Production> {
-        Production::new(
-            shared_comps,
-            self.name.clone(),
-            self.production_methods.clone(),
-            self.transport_methods
+    pub fn template(&self) -> ProductionTemplate {
+        ProductionTemplate {
+            name: self.name.clone(),
+            selected_methods: self.production_methods.clone(),
+            transport: self
+                .transport_methods
                 .iter()
                 .map(|(&key, &(value, _))| (key, value))
                 .collect(),
-        )
+        }
+    }
+
+    pub fn load_template(&mut self, template: &ProductionTemplate) {
+        self.name = template.name.clone();
+        self.production_methods = template.selected_methods.clone();
+        self.transport_methods = template
+            .transport
+            .iter()
+            .map(|(&key, &value)| (key, (value, true)))
+            .collect();
+    }
+
+    pub fn finish(
+        &self,
+        shared_comps: &SharedComponents,
+        research: &Research,
+    ) -> Result<Production> {
+        Production::from_template(shared_comps, research, self.template(), 1)
     }
 
     fn draw_method_select(
@@ -81,6 +100,14 @@ impl ProductionBuilder {
                     if !sim.research.is_production_unlocked(method_id) {
                         continue;
                     }
+                    let Some(selected_method) = FixedProductionMethod::new_unlocked(
+                        shared_comps,
+                        &sim.research,
+                        method_id,
+                    )?
+                    else {
+                        continue;
+                    };
                     let method = shared_comps.config(method_id)?;
                     draw_icon_btn_with_tooltip(
                         app_st,
@@ -91,8 +118,6 @@ impl ProductionBuilder {
                         |i| i,
                         |_| (),
                         || {
-                            let selected_method =
-                                FixedProductionMethod::new(shared_comps, method_id, None)?;
                             self.production_methods.push(selected_method);
                             Ok(())
                         },
@@ -214,8 +239,10 @@ impl Widget for ProductionBuilder {
                         .show_ui(ui, |ui| {
                             for &setting_id in &setting_group.settings {
                                 let setting = shared_comps.config(setting_id)?;
-                                let response =
-                                    ui.selectable_label(false, app_st.text(&setting.name)?);
+                                let response = ui.add_enabled(
+                                    sim.research.is_setting_unlocked(setting_id),
+                                    SelectableLabel::new(false, app_st.text(&setting.name)?),
+                                );
                                 if response.clicked() {
                                     *selected_setting_id = setting_id;
                                 }
@@ -411,16 +438,41 @@ impl Widget for ProductionBuilder {
         })
         .inner?;
 
+        let production = if self.ready() {
+            match self.finish(shared_comps, &sim.research) {
+                Result::Ok(production) => Some(production),
+                Err(e) => {
+                    ui.colored_label(
+                        Color32::from_rgb(240, 160, 160),
+                        format!(
+                            "{}: {e:#}",
+                            app_st.text_core("ui_main_productions_builder_invalid")?
+                        ),
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let valid = production.is_some();
         ui.horizontal(|ui| {
             if ui
                 .add_enabled(
-                    enough_resources && self.ready(),
+                    enough_resources && valid,
                     egui::Button::new(app_st.text_core("ui_main_productions_builder_finish")?),
                 )
                 .clicked()
             {
-                sim.depot.cor_sub_all_unchecked(&total_cost);
-                sim.productions.push(self.finish(shared_comps)?);
+                sim.apply(
+                    shared_comps,
+                    SimCommand::CreateProduction {
+                        template: self.template(),
+                        count: 1,
+                        index: sim.productions.len(),
+                    },
+                )?;
                 env.get::<WindowCloseEvent<ProductionBuilder>>()
                     .map(WindowCloseEvent::emit);
                 *self = Default::default();
@@ -428,6 +480,32 @@ impl Widget for ProductionBuilder {
             if ui.button(app_st.text_core("ui_generic_clear")?).clicked() {
                 self.production_methods.clear();
             }
+            if ui
+                .add_enabled(
+                    valid,
+                    egui::Button::new(
+                        app_st.text_core("ui_main_productions_builder_save-template")?,
+                    ),
+                )
+                .clicked()
+            {
+                sim.apply(shared_comps, SimCommand::SaveTemplate(self.template()))?;
+            }
+            ui.add_enabled_ui(!sim.templates.is_empty(), |ui| {
+                ui.menu_button(
+                    app_st.text_core("ui_main_productions_builder_load-template")?,
+                    |ui| {
+                        for template in &sim.templates {
+                            if ui.button(&template.name).clicked() {
+                                self.load_template(template);
+                                ui.close_menu();
+                            }
+                        }
+                    },
+                );
+                Ok(())
+            })
+            .inner?;
             Ok(())
         })
         .inner?;
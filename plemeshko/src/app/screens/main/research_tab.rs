@@ -5,6 +5,7 @@ use std::{
 
 use anyhow::{Ok, Result};
 use egui::{Color32, Pos2, ProgressBar, Ui, WidgetText};
+use fluent::FluentArgs;
 
 use crate::{
     app::{
@@ -13,11 +14,14 @@ use crate::{
         widgets::{Tab, Widget},
     },
     sim::{
+        command::SimCommand,
         config::technology::{Technology, TechnologyBonus, TechnologyId},
         Sim,
     },
     state::{
+        components::SharedComponents,
         has::{HasSimMutex, HasTexts},
+        research::TechnologyRevocation,
         AppState,
     },
 };
@@ -103,6 +107,7 @@ impl Widget for MainScreenResearchTab {
                     let (id, technology) = technology?;
                     draw_technology_icon_tip(
                         app_st,
+                        shared_comps,
                         ctx,
                         ui,
                         sim,
@@ -121,6 +126,36 @@ impl Widget for MainScreenResearchTab {
             }
         }
 
+        ui.separator();
+        egui::CollapsingHeader::new(app_st.text_core("ui_main_research_history")?)
+            .show(ui, |ui| {
+                for record in sim.research.history().iter().rev() {
+                    let mut args = FluentArgs::new();
+                    args.set(
+                        "name",
+                        app_st.text(&shared_comps.config(record.technology)?.info.name)?,
+                    );
+                    args.set("tick", record.completed.0);
+                    args.set("invested", record.invested);
+                    let mut entry = app_st
+                        .text_core_fmt("ui_main_research_history-entry", &args)?
+                        .into_owned();
+                    if let Some((tick, revocation)) = record.revoked {
+                        let mut args = FluentArgs::new();
+                        args.set("tick", tick.0);
+                        let id = match revocation {
+                            TechnologyRevocation::Obsolete => "ui_main_research_history-obsolete",
+                            TechnologyRevocation::Lost => "ui_main_research_history-lost",
+                        };
+                        write!(entry, " ({})", app_st.text_core_fmt(id, &args)?)?;
+                    }
+                    ui.label(entry);
+                }
+                Ok(())
+            })
+            .body_returned
+            .transpose()?;
+
         if let Some((id, pos)) = self.technology_tooltip {
             let technology = shared_comps.config(id)?;
             let win_resp = egui::Window::new("tech tooltip")
@@ -139,6 +174,10 @@ impl Widget for MainScreenResearchTab {
                                 TechnologyBonus::UnlockProduction(pr_id) => {
                                     &shared_comps.config(*pr_id)?.info
                                 }
+                                TechnologyBonus::UnlockSetting(st_id) => {
+                                    ui.label(app_st.text(&shared_comps.config(*st_id)?.name)?);
+                                    continue;
+                                }
                             };
                             draw_icon_with_tooltip(
                                 app_st,
@@ -177,6 +216,7 @@ impl Widget for MainScreenResearchTab {
 
 fn draw_technology_icon_tip(
     app_st: &AppState,
+    shared_comps: &SharedComponents,
     ctx: &egui::Context,
     ui: &mut Ui,
     sim: &mut Sim,
@@ -186,7 +226,7 @@ fn draw_technology_icon_tip(
     tt: &mut bool,
 ) -> Result<()> {
     let is_researched = sim.research.is_researched(id);
-    let prerequisites_satisfied = technology.prerequisites_satisfied();
+    let prerequisites_satisfied = sim.research.is_available(id);
     let tint = match (is_researched, prerequisites_satisfied) {
         (false, false) => Color32::from_rgb(255, 120, 120),
         (false, true) => {
@@ -200,6 +240,7 @@ fn draw_technology_icon_tip(
                 Color32::from_rgb(155, 155, 155)
             }
         }
+        (true, _) if sim.research.is_obsolete(id) => Color32::from_rgb(200, 180, 130),
         (true, _) => Color32::WHITE,
     };
     let mut button = egui::ImageButton::new(
@@ -219,7 +260,7 @@ fn draw_technology_icon_tip(
     }
 
     if response.clicked() && !is_researched && prerequisites_satisfied {
-        sim.research.start(id);
+        sim.apply(shared_comps, SimCommand::StartResearch(id))?;
     }
     Ok(())
 }
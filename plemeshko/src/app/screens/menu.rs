@@ -1,12 +1,10 @@
-use std::path::PathBuf;
-
 use anyhow::{Ok, Result};
 use egui::{vec2, Button};
 use unic_langid::LanguageIdentifier;
 
 use crate::{
     app::{env::Env, widgets::Widget},
-    state::{has::HasTexts, text::TextRepository},
+    state::{components::ComponentId, has::HasTexts},
 };
 
 use super::{AppExitEvent, AppScreenTransitionEvent, AppSwitchTranslationEvent};
@@ -14,7 +12,7 @@ use super::{AppExitEvent, AppScreenTransitionEvent, AppSwitchTranslationEvent};
 struct MenuScreenId;
 
 pub struct MenuScreen {
-    language_select_open: Option<Vec<(LanguageIdentifier, PathBuf)>>,
+    language_select_open: Option<Vec<LanguageIdentifier>>,
 }
 
 impl MenuScreen {
@@ -52,11 +50,21 @@ impl Widget for MenuScreen {
                     .unwrap()
                     .emit(super::AppScreen::Load);
             }
+            if ui
+                .add_sized(btnsz, Button::new(app_st.text_core("ui_menu_mods")?))
+                .clicked()
+            {
+                env.get::<AppScreenTransitionEvent>()
+                    .unwrap()
+                    .emit(super::AppScreen::Mods);
+            }
             if ui
                 .add_sized(btnsz, Button::new(app_st.text_core("ui_menu_language")?))
                 .clicked()
             {
-                self.language_select_open = Some(TextRepository::available_translations_core()?);
+                // includes translations added by packs
+                let core = app_st.components.component(ComponentId::core())?;
+                self.language_select_open = Some(core.texts.translations()?);
             }
             if ui
                 .add_sized(btnsz, Button::new(app_st.text_core("ui_menu_exit")?))
@@ -77,7 +85,7 @@ impl Widget for MenuScreen {
                             if ui
                                 .add_sized(
                                     vec2(64., 32.),
-                                    egui::Button::new(tr.0.to_string().to_uppercase()),
+                                    egui::Button::new(tr.to_string().to_uppercase()),
                                 )
                                 .clicked()
                             {
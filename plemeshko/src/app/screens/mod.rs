@@ -1,11 +1,13 @@
-use std::path::PathBuf;
-
 use anyhow::{Context, Result};
-use egui::CentralPanel;
+use egui::{CentralPanel, ProgressBar};
 use enum_map::{enum_map, Enum};
 use unic_langid::LanguageIdentifier;
 
-use crate::state::{components::ComponentId, AppState};
+use crate::state::{
+    components::ComponentId,
+    save::{SaveFormat, Session},
+    AppState, LoadedComponents,
+};
 
 use super::{
     env::Env,
@@ -16,6 +18,7 @@ use super::{
 mod load;
 mod main;
 mod menu;
+mod mods;
 mod new_game;
 
 #[derive(Enum, Clone, Copy, Default)]
@@ -25,17 +28,23 @@ pub enum AppScreen {
     NewGame,
     Load,
     Main,
+    Mods,
 }
 
 pub struct App(Screens<(), AppScreen, App>, Env<'static>);
 
 pub type AppScreenTransitionEvent = ScreenTransitionEvent<AppScreen, App>;
 
-pub struct AppSaveEvent(FlagEvent);
-pub struct AppLoadEvent(SetEvent<String>);
+/// Name to save the game as, or `None` to save it under the session's name.
+pub struct AppSaveEvent(SetEvent<Option<String>>);
+/// Name of the save to load and whether parts of it referring to missing configs are dropped.
+pub struct AppLoadEvent(SetEvent<(String, bool)>);
 pub struct AppExitEvent(FlagEvent);
-pub struct AppNewGameEvent(SetEvent<String>);
-pub struct AppSwitchTranslationEvent(SetEvent<(LanguageIdentifier, PathBuf)>);
+/// Name of the new game and format of its save.
+pub struct AppNewGameEvent(SetEvent<(String, SaveFormat)>);
+pub struct AppSwitchTranslationEvent(SetEvent<LanguageIdentifier>);
+pub struct AppReloadComponentsEvent(SetEvent<LoadedComponents>);
+pub struct AppUnloadComponentEvent(SetEvent<ComponentId>);
 
 impl App {
     pub fn new() -> Self {
@@ -45,21 +54,52 @@ impl App {
                 AppScreen::NewGame => Box::new(new_game::NewGameScreen::new()) as Box<dyn Widget<Response = ()>>,
                 AppScreen::Load => Box::new(load::LoadScreen::new()) as Box<dyn Widget<Response = ()>>,
                 AppScreen::Main => Box::new(main::MainScreen::new()) as Box<dyn Widget<Response = ()>>,
+                AppScreen::Mods => Box::new(mods::ModsScreen::new()) as Box<dyn Widget<Response = ()>>,
             }),
             Env::new(),
         )
     }
 
-    pub fn update(&mut self, _st: &mut AppState) -> Result<()> {
+    pub fn update(&mut self, st: &mut AppState) -> Result<()> {
+        if st.poll_loading()? {
+            return Ok(());
+        }
+        let autosave = st.shared.autosave.lock().unwrap().take();
+        if let Some(snapshot) = autosave {
+            if let Err(e) = crate::state::save::autosave(st, snapshot) {
+                log::error!("Autosave failed: {e:#}");
+            }
+        }
+        if st.hot_reload()? {
+            // widgets keep config ids, which are stale after the reload
+            self.0
+                .replace(AppScreen::Main, Box::new(main::MainScreen::new()));
+        }
         Ok(())
     }
 
     pub fn ui(&mut self, st: &mut AppState, egui_ctx: &egui::Context) -> Result<bool> {
-        let ev_save = AppSaveEvent(FlagEvent::new());
+        if let Some(progress) = st.loading_progress() {
+            // texts aren't loaded yet, so only the progress is shown
+            CentralPanel::default().show(egui_ctx, |ui| {
+                ui.add_space(ui.available_height() / 2.0);
+                ui.vertical_centered(|ui| {
+                    ui.add(
+                        ProgressBar::new(progress)
+                            .desired_width(ui.available_width() / 2.0)
+                            .show_percentage(),
+                    )
+                });
+            });
+            return Ok(false);
+        }
+        let mut ev_save = AppSaveEvent(SetEvent::new());
         let mut ev_load = AppLoadEvent(SetEvent::new());
         let ev_exit = AppExitEvent(FlagEvent::new());
         let mut ev_newgame = AppNewGameEvent(SetEvent::new());
         let mut ev_sw_translation = AppSwitchTranslationEvent(SetEvent::new());
+        let ev_reload_components = AppReloadComponentsEvent(SetEvent::new());
+        let ev_unload_component = AppUnloadComponentEvent(SetEvent::new());
         self.1.with(st, |env| {
             env.with(&egui_ctx.clone(), |env| {
                 env.with(&ev_save, |env| {
@@ -67,9 +107,13 @@ impl App {
                         env.with(&ev_exit, |env| {
                             env.with(&ev_newgame, |env| {
                                 env.with(&ev_sw_translation, |env| {
-                                    CentralPanel::default()
-                                        .show(egui_ctx, |ui| self.0.ui(env, ui))
-                                        .inner
+                                    env.with(&ev_reload_components, |env| {
+                                        env.with(&ev_unload_component, |env| {
+                                            CentralPanel::default()
+                                                .show(egui_ctx, |ui| self.0.ui(env, ui))
+                                                .inner
+                                        })
+                                    })
                                 })
                             })
                         })
@@ -77,28 +121,43 @@ impl App {
                 })
             })
         })?;
-        if ev_save.0.get() {
-            crate::state::save::save(st)?;
+        match ev_save.0.get_mut() {
+            Some(None) => crate::state::save::save(st)?,
+            Some(Some(name)) => {
+                if let Err(e) = crate::state::save::save_as(st, name) {
+                    log::error!("Saving as \"{name}\" failed: {e:#}");
+                }
+            }
+            None => (),
         }
-        if let Some(save_name) = ev_load.0.get_mut() {
-            crate::state::save::load(save_name, st)?;
+        if let Some((save_name, drop_missing)) = ev_load.0.get_mut() {
+            crate::state::save::load(save_name, st, *drop_missing)?;
         }
-        if let Some(game_name) = ev_newgame.0.get_mut() {
+        if let Some((game_name, format)) = ev_newgame.0.get_mut() {
             let mut sim_guard = st.shared.sim.lock().unwrap();
             *sim_guard = Some(
-                crate::sim::Sim::new(&mut st.shared.components.write().unwrap())
+                crate::sim::Sim::new(&st.shared.components.read().unwrap())
                     .context("Creating new game")?,
             );
-            st.session = Some(game_name.clone());
+            *st.shared.autosave.lock().unwrap() = None;
+            st.session = Some(Session {
+                name: game_name.clone(),
+                format: *format,
+            });
         }
         if let Some(tr) = ev_sw_translation.0.get_mut() {
-            for (id, c) in st.components.iter_components_mut() {
-                if id == ComponentId::core() {
-                    c.texts
-                        .switch_translation_exact(tr.0.clone(), tr.1.clone())?;
-                    continue;
-                }
-                c.texts.switch_translation(tr.0.clone())?;
+            st.switch_translation(tr.clone())?;
+        }
+        if let Some(components) = ev_reload_components.0.into_inner() {
+            st.replace_components(components)?;
+        }
+        if let Some(id) = ev_unload_component.0.get() {
+            match st.unload_component(id) {
+                // widgets keep config ids, which may refer to the unloaded component
+                Ok(()) => self
+                    .0
+                    .replace(AppScreen::Main, Box::new(main::MainScreen::new())),
+                Err(e) => log::error!("Unloading component failed: {e:#}"),
             }
         }
         Ok(ev_exit.0.get())
@@ -106,18 +165,18 @@ impl App {
 }
 
 impl AppSaveEvent {
-    delegate::delegate! {
-        to self.0 {
-            pub fn emit(&self);
-        }
+    pub fn emit(&self) {
+        self.0.emit(None);
+    }
+
+    pub fn emit_as(&self, name: String) {
+        self.0.emit(Some(name));
     }
 }
 
 impl AppLoadEvent {
-    delegate::delegate! {
-        to self.0 {
-            pub fn emit(&self, name: String);
-        }
+    pub fn emit(&self, name: String, drop_missing: bool) {
+        self.0.emit((name, drop_missing));
     }
 }
 
@@ -130,17 +189,31 @@ impl AppExitEvent {
 }
 
 impl AppNewGameEvent {
+    pub fn emit(&self, name: String, format: SaveFormat) {
+        self.0.emit((name, format));
+    }
+}
+
+impl AppSwitchTranslationEvent {
     delegate::delegate! {
         to self.0 {
-            pub fn emit(&self, name: String);
+            pub fn emit(&self, tr: LanguageIdentifier);
         }
     }
 }
 
-impl AppSwitchTranslationEvent {
+impl AppReloadComponentsEvent {
+    delegate::delegate! {
+        to self.0 {
+            pub fn emit(&self, components: LoadedComponents);
+        }
+    }
+}
+
+impl AppUnloadComponentEvent {
     delegate::delegate! {
         to self.0 {
-            pub fn emit(&self, tr: (LanguageIdentifier, PathBuf));
+            pub fn emit(&self, id: ComponentId);
         }
     }
 }
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use anyhow::{Ok, Result};
+use egui::{Color32, ScrollArea};
+use fluent::FluentArgs;
+
+use crate::{
+    app::{env::Env, widgets::Widget},
+    params::CORE_LABEL,
+    state::{
+        components::{
+            order_components, ComponentId, ComponentSettings, DiscoveredComponent, LoadingProgress,
+        },
+        discover_components,
+        has::{HasSimMutex, HasTexts},
+        load_components,
+    },
+};
+
+use super::{AppReloadComponentsEvent, AppScreen, AppScreenTransitionEvent};
+
+struct ModEntry {
+    component: DiscoveredComponent,
+    enabled: bool,
+}
+
+pub struct ModsScreen {
+    /// Discovered components in the preferred order, read when the screen is opened.
+    entries: Option<Vec<ModEntry>>,
+    changed: bool,
+    applied: bool,
+    error: Option<String>,
+}
+
+impl ModsScreen {
+    pub fn new() -> Self {
+        ModsScreen {
+            entries: None,
+            changed: false,
+            applied: false,
+            error: None,
+        }
+    }
+
+    fn read_entries() -> Result<Vec<ModEntry>> {
+        let settings = ComponentSettings::read()?;
+        let mut discovered = discover_components()?;
+        settings.sort(&mut discovered);
+        Ok(discovered
+            .into_iter()
+            .map(|component| ModEntry {
+                enabled: settings.is_enabled(&component.label),
+                component,
+            })
+            .collect())
+    }
+
+    fn settings(entries: &[ModEntry]) -> ComponentSettings {
+        ComponentSettings {
+            disabled: entries
+                .iter()
+                .filter(|entry| !entry.enabled)
+                .map(|entry| entry.component.label.clone())
+                .collect(),
+            order: entries
+                .iter()
+                .map(|entry| entry.component.label.clone())
+                .collect(),
+        }
+    }
+}
+
+impl Widget for ModsScreen {
+    type Response = ();
+
+    fn ui(&mut self, env: &mut Env<'_>, ui: &mut egui::Ui) -> Result<Self::Response> {
+        let app_st = env.app_state();
+        if self.entries.is_none() {
+            self.entries = Some(Self::read_entries().unwrap_or_else(|e| {
+                self.error = Some(format!("{e:#}"));
+                Vec::new()
+            }));
+        }
+        let entries = self.entries.as_mut().unwrap();
+        ui.heading(app_st.text_core("ui_mods_title")?);
+        if entries.is_empty() {
+            ui.label(app_st.text_core("ui_mods_none")?);
+        }
+        let mut swap = None;
+        let entry_count = entries.len();
+        ScrollArea::vertical()
+            .max_height(ui.available_height() * 0.75)
+            .show(ui, |ui| {
+                for (i, entry) in entries.iter_mut().enumerate() {
+                    let manifest = &entry.component.manifest;
+                    ui.horizontal(|ui| {
+                        self.changed |= ui.checkbox(&mut entry.enabled, "").changed();
+                        ui.strong(format!("{} {}", manifest.name, manifest.version));
+                        ui.label(format!("({})", entry.component.label));
+                        if ui
+                            .add_enabled(
+                                i > 0,
+                                egui::Button::new(app_st.text_core("ui_mods_move-up")?),
+                            )
+                            .clicked()
+                        {
+                            swap = Some(i - 1);
+                        }
+                        if ui
+                            .add_enabled(
+                                i + 1 < entry_count,
+                                egui::Button::new(app_st.text_core("ui_mods_move-down")?),
+                            )
+                            .clicked()
+                        {
+                            swap = Some(i);
+                        }
+                        Ok(())
+                    })
+                    .inner?;
+                    ui.indent(&entry.component.label, |ui| {
+                        if !manifest.authors.is_empty() {
+                            let mut args = FluentArgs::new();
+                            args.set("authors", manifest.authors.join(", "));
+                            ui.label(app_st.text_core_fmt("ui_mods_authors", &args)?);
+                        }
+                        if !manifest.depends_on.is_empty() {
+                            let dependencies: Vec<_> = manifest
+                                .depends_on
+                                .iter()
+                                .map(|(label, requirement)| format!("{label} {requirement}"))
+                                .collect();
+                            let mut args = FluentArgs::new();
+                            args.set("dependencies", dependencies.join(", "));
+                            ui.label(app_st.text_core_fmt("ui_mods_depends-on", &args)?);
+                        }
+                        Ok(())
+                    })
+                    .inner?;
+                }
+                Ok(())
+            })
+            .inner?;
+        if let Some(i) = swap {
+            entries.swap(i, i + 1);
+            self.changed = true;
+        }
+
+        // dependencies are checked before loading anything, to report all problems at once
+        let core_manifest = &app_st.components.component(ComponentId::core())?.manifest;
+        let enabled: Vec<_> = entries
+            .iter()
+            .filter(|entry| entry.enabled)
+            .map(|entry| entry.component.clone())
+            .collect();
+        let dependency_error = order_components(
+            enabled,
+            &HashMap::from([(CORE_LABEL.to_owned(), core_manifest)]),
+        )
+        .err();
+        if let Some(e) = &dependency_error {
+            ui.colored_label(Color32::from_rgb(240, 160, 160), format!("{e:#}"));
+        }
+        let game_running = app_st.lock_sim().is_some();
+        if game_running {
+            ui.label(app_st.text_core("ui_mods_game-running")?);
+        }
+
+        let mut ev_return = false;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    self.changed && !game_running && dependency_error.is_none(),
+                    egui::Button::new(app_st.text_core("ui_mods_apply")?),
+                )
+                .clicked()
+            {
+                let settings = Self::settings(entries);
+                // components are loaded aside, so the current ones stay intact on failure,
+                // and settings are written only once they load, so the game can start with them
+                let result = load_components(&settings, &LoadingProgress::default())
+                    .and_then(|components| {
+                        settings.write()?;
+                        Ok(components)
+                    })
+                    .map(|components| {
+                        env.get::<AppReloadComponentsEvent>()
+                            .unwrap()
+                            .emit(components)
+                    });
+                self.changed = result.is_err();
+                self.applied = result.is_ok();
+                self.error = result.err().map(|e| format!("{e:#}"));
+            }
+            if ui.button(app_st.text_core("ui_generic_return")?).clicked() {
+                env.get::<AppScreenTransitionEvent>()
+                    .unwrap()
+                    .emit(AppScreen::Menu);
+                ev_return = true;
+            }
+            Ok(())
+        })
+        .inner?;
+        if self.applied {
+            ui.label(app_st.text_core("ui_mods_applied")?);
+        }
+        if let Some(error) = &self.error {
+            ui.colored_label(Color32::from_rgb(240, 160, 160), error);
+        }
+        if ev_return {
+            *self = ModsScreen::new();
+        }
+        Ok(())
+    }
+}
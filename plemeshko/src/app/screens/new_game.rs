@@ -1,18 +1,26 @@
 use anyhow::Ok;
 use egui::TextEdit;
 
-use crate::{app::widgets::Widget, state::has::HasTexts};
+use crate::{
+    app::widgets::Widget,
+    state::{
+        has::HasTexts,
+        save::{is_valid_save_name, SaveFormat},
+    },
+};
 
 use super::{AppNewGameEvent, AppScreen, AppScreenTransitionEvent};
 
 pub struct NewGameScreen {
     name: String,
+    compact_save: bool,
 }
 
 impl NewGameScreen {
     pub fn new() -> Self {
         NewGameScreen {
             name: String::new(),
+            compact_save: false,
         }
     }
 }
@@ -35,10 +43,12 @@ impl Widget for NewGameScreen {
             Ok(())
         })
         .inner?;
-        let valid_name = self
-            .name
-            .chars()
-            .all(|c| c.is_ascii_alphanumeric() || c == '_');
+        ui.checkbox(
+            &mut self.compact_save,
+            app_st.text_core("ui_new-game_compact-save")?,
+        )
+        .on_hover_text(app_st.text_core("ui_new-game_compact-save-hint")?);
+        let valid_name = self.name.is_empty() || is_valid_save_name(&self.name);
         ui.horizontal(|ui| {
             if ui
                 .add_enabled(
@@ -47,9 +57,14 @@ impl Widget for NewGameScreen {
                 )
                 .clicked()
             {
+                let format = if self.compact_save {
+                    SaveFormat::Compact
+                } else {
+                    SaveFormat::Json
+                };
                 env.get::<AppNewGameEvent>()
                     .unwrap()
-                    .emit(self.name.clone());
+                    .emit(self.name.clone(), format);
                 env.get::<AppScreenTransitionEvent>()
                     .unwrap()
                     .emit(AppScreen::Main);
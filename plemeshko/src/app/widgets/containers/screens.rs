@@ -30,6 +30,10 @@ impl<R, E: EnumArray<Screen<R>>, Id> Screens<R, E, Id> {
     {
         Self::new_at(E::default(), screens)
     }
+
+    pub fn replace(&mut self, screen: E, widget: Screen<R>) {
+        self.screens[screen] = widget;
+    }
 }
 
 impl<E, Id> ScreenTransitionEvent<E, Id> {
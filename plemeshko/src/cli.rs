@@ -0,0 +1,56 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    params::SCHEMAS_DIR,
+    state::{
+        audit::audit_assets,
+        components::{ComponentSettings, LoadingProgress},
+        load_components, replay,
+    },
+};
+
+/// Runs the command given in program arguments instead of the game.
+/// Returns `false` when there is no command.
+pub fn run_command(mut args: impl Iterator<Item = String>) -> Result<bool> {
+    let Some(command) = args.next() else {
+        return Ok(false);
+    };
+    match command.as_str() {
+        // export-schemas [directory]
+        "export-schemas" => {
+            let dir = args
+                .next()
+                .map_or_else(|| PathBuf::from(SCHEMAS_DIR), PathBuf::from);
+            crate::sim::config::register()?.export_schemas(&dir)?;
+            log::info!("Schemas exported to {}", dir.display());
+        }
+        // audit
+        "audit" => {
+            let components =
+                load_components(&ComponentSettings::read()?, &LoadingProgress::default())?;
+            let problems = audit_assets(&components.loader, &components.shared, &components.app)?;
+            if !problems.is_empty() {
+                bail!(
+                    "Found {} problems with texts and textures:\n{}",
+                    problems.len(),
+                    problems.join("\n")
+                );
+            }
+            log::info!("No problems with texts and textures found");
+        }
+        // replay <file>
+        "replay" => {
+            let Some(path) = args.next() else {
+                bail!("Replay file not specified");
+            };
+            let components =
+                load_components(&ComponentSettings::read()?, &LoadingProgress::default())?;
+            replay::replay(Path::new(&path), components)?;
+            log::info!("Replay {path} reproduced");
+        }
+        _ => bail!("Unknown command: {command}"),
+    }
+    Ok(true)
+}
@@ -1,6 +1,10 @@
 use winit::event::Event;
 
-use crate::{app::App, sim::Sim, state::AppState};
+use crate::{
+    app::App,
+    sim::Sim,
+    state::{save, AppState},
+};
 
 use self::{
     graphics::{Graphics, RenderError},
@@ -11,6 +15,16 @@ mod graphics;
 mod gui;
 mod window;
 
+/// Stops the simulation, autosaving the game so the player's own saves aren't overwritten.
+fn exit_sim(app_st: &AppState) {
+    let snapshot = app_st.shared.sim.lock().unwrap().as_mut().map(Sim::exit);
+    if let Some(snapshot) = snapshot {
+        if let Err(e) = save::autosave(app_st, snapshot) {
+            log::error!("Saving on exit failed: {e:#}");
+        }
+    }
+}
+
 pub fn run(mut app_st: AppState) -> ! {
     let (event_loop, window) = window::initialize();
     let mut graphics = futures::executor::block_on(Graphics::new(&window));
@@ -36,7 +50,7 @@ pub fn run(mut app_st: AppState) -> ! {
                     }
                     winit::event::WindowEvent::CloseRequested => {
                         *control_flow = winit::event_loop::ControlFlow::Exit;
-                        app_st.shared.sim.lock().unwrap().as_mut().map(Sim::exit);
+                        exit_sim(&app_st);
                     }
                     winit::event::WindowEvent::ScaleFactorChanged {
                         new_inner_size,
@@ -56,7 +70,7 @@ pub fn run(mut app_st: AppState) -> ! {
                 Ok(_) => (),
                 Err(e) => {
                     *control_flow = winit::event_loop::ControlFlow::ExitWithCode(1);
-                    log::error!("App update error: {e}");
+                    log::error!("App update error: {e:#}");
                     return;
                 }
             }
@@ -65,7 +79,10 @@ pub fn run(mut app_st: AppState) -> ! {
         Event::RedrawRequested(window_id) => {
             if window_id == window.id() {
                 match gui.run(&window, |egui_ctx| app.ui(&mut app_st, egui_ctx)) {
-                    Ok(true) => *control_flow = winit::event_loop::ControlFlow::Exit,
+                    Ok(true) => {
+                        *control_flow = winit::event_loop::ControlFlow::Exit;
+                        exit_sim(&app_st);
+                    }
                     Ok(false) => (),
                     Err(e) => {
                         *control_flow = winit::event_loop::ControlFlow::ExitWithCode(1);
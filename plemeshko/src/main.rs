@@ -19,6 +19,7 @@
 use std::time::Instant;
 
 use anyhow::{Context, Result};
+use params::AUTOSAVE_INTERVAL_TICKS;
 use sim::Sim;
 use state::initialize_state;
 
@@ -29,12 +30,16 @@ mod util;
 #[macro_use]
 mod state;
 mod app;
+mod cli;
 mod framework;
 mod params;
 mod sim;
 
 fn main() -> Result<()> {
     crate::log::initialize_log().context("Initializing log")?;
+    if cli::run_command(std::env::args().skip(1)).context("Running command")? {
+        return Ok(());
+    }
     let (_audio_stream, shared_st, app_st) = initialize_state().context("Initializing state")?;
     std::thread::scope(|thread_scope| {
         thread_scope.spawn(|| {
@@ -49,7 +54,11 @@ fn main() -> Result<()> {
                         }
                         let step_result = sim.step(shared_st);
                         match step_result {
-                            Ok(_) => (),
+                            Ok(_) => {
+                                if sim.tick.0 % AUTOSAVE_INTERVAL_TICKS == 0 {
+                                    *shared_st.autosave.lock().unwrap() = Some(sim.snapshot());
+                                }
+                            }
                             Err(e) => {
                                 log::error!("Simulation error: {e:#}");
                                 // todo: signal to the ui when step fails
@@ -1,18 +1,39 @@
+use std::time::Duration;
+
 pub const WINDOW_TITLE: &str = "Plemeshko :3";
 pub const STARTUP_WINDOW_WIDTH: u32 = 960;
 pub const STARTUP_WINDOW_HEIGHT: u32 = 540;
 
 pub const SAVES_DIR: &str = "saves";
+/// Number of ticks between autosaves of the current game.
+pub const AUTOSAVE_INTERVAL_TICKS: i64 = 300;
+/// Number of autosaves kept for each game, the oldest one is overwritten by the next autosave.
+pub const AUTOSAVE_SLOTS: i64 = 3;
+pub const TEMPLATES_DIR: &str = "templates";
+pub const REPLAYS_DIR: &str = "replays";
 pub const MODS_DIR: &str = "mods";
 pub const CORE_DIR: &str = "core";
+pub const SCHEMAS_DIR: &str = "schemas";
 
+pub const COMPONENT_SETTINGS_FILE: &str = "mods.json";
+pub const COMPONENT_MANIFEST_FILE: &str = "component.json";
 pub const COMPONENT_CONFIGS_DIR: &str = "configs";
 pub const COMPONENT_TEXTS_DIR: &str = "texts";
 pub const COMPONENT_TEXTURES_DIR: &str = "textures";
 
+/// Environment variable enabling reloading of components when their files change.
+pub const HOT_RELOAD_ENV_VAR: &str = "PLEMESHKO_HOT_RELOAD";
+pub const HOT_RELOAD_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 pub const CORE_RESOURCE_HUMAN: &str = "human";
 pub const CORE_RESOURCE_FOOD: &str = "food";
 
+/// Part of a lost technology's cost kept as progress towards researching it again.
+pub const TECHNOLOGY_LOSS_REFUND_PERCENT: u64 = 50;
+
+/// Default size in pixels of cells of texture atlases, which icons can refer to by their positions.
+pub const TEXTURE_CELL_SIZE: u32 = 128;
+
 pub const CORE_LABEL: &str = "";
 pub const LABEL_SEPARATOR: char = '/';
 
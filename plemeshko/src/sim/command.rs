@@ -0,0 +1,233 @@
+use anyhow::{ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    state::{
+        components::{ComponentsRef, SharedComponents},
+        config::FatConfigLabel,
+        serializable::Serializable,
+    },
+    util::cor::Cor,
+};
+
+use super::{
+    config::{
+        resource::{Resource, ResourceId},
+        technology::{Technology, TechnologyId},
+    },
+    production::{Production, ProductionTemplate, RawProductionTemplate},
+    units::ResourceAmount,
+    Sim,
+};
+
+#[derive(Serialize, Deserialize)]
+pub enum RawSimCommand {
+    CreateProduction {
+        template: RawProductionTemplate,
+        count: u32,
+        index: usize,
+    },
+    ActivateProduction {
+        index: usize,
+        delta: u32,
+    },
+    DeactivateProduction {
+        index: usize,
+        delta: u32,
+    },
+    RemoveProduction {
+        index: usize,
+    },
+    StartResearch(FatConfigLabel<Technology>),
+    SaveTemplate(RawProductionTemplate),
+    RemoveTemplate {
+        index: usize,
+    },
+    SpawnResource {
+        resource: FatConfigLabel<Resource>,
+        amount: ResourceAmount,
+    },
+}
+
+/// Action of the player, applied by the sim between its steps and recorded, so that games can be replayed.
+#[derive(Clone)]
+pub enum SimCommand {
+    /// Creates a production of `count` active units at `index` of the productions, paying their cost.
+    CreateProduction {
+        template: ProductionTemplate,
+        count: u32,
+        index: usize,
+    },
+    /// Activates more units of the production, building ones it lacks.
+    ActivateProduction {
+        index: usize,
+        delta: u32,
+    },
+    DeactivateProduction {
+        index: usize,
+        delta: u32,
+    },
+    RemoveProduction {
+        index: usize,
+    },
+    /// Starts researching the technology, pausing the current research.
+    StartResearch(TechnologyId),
+    /// Stores the template, replacing one of the same name.
+    SaveTemplate(ProductionTemplate),
+    RemoveTemplate {
+        index: usize,
+    },
+    /// Adds resources to the depot, for debugging.
+    SpawnResource {
+        resource: ResourceId,
+        amount: ResourceAmount,
+    },
+}
+
+impl SimCommand {
+    pub(super) fn execute(self, sim: &mut Sim, shared_comps: &SharedComponents) -> Result<()> {
+        match self {
+            SimCommand::CreateProduction {
+                template,
+                count,
+                index,
+            } => {
+                ensure!(index <= sim.productions.len(), "No production slot {index}");
+                let production =
+                    Production::from_template(shared_comps, &sim.research, template, count)?;
+                ensure!(
+                    sim.depot.cor_has_all_times(production.cost(), count as i64) >= count as i64,
+                    "Not enough resources to create production \"{}\"",
+                    production.name()
+                );
+                sim.depot
+                    .cor_sub_all_times_unchecked(production.cost(), count as i64);
+                sim.productions.insert(index, production);
+            }
+            SimCommand::ActivateProduction { index, delta } => {
+                let production = sim
+                    .productions
+                    .get_mut(index)
+                    .with_context(|| format!("No production {index}"))?;
+                ensure!(
+                    !production.is_locked(shared_comps, &sim.research),
+                    "Production \"{}\" uses locked methods",
+                    production.name()
+                );
+                let inactive = production.count() - production.active();
+                let built = delta.saturating_sub(inactive);
+                ensure!(
+                    sim.depot.cor_has_all_times(production.cost(), built as i64) >= built as i64,
+                    "Not enough resources to build {built} units of production \"{}\"",
+                    production.name()
+                );
+                sim.depot
+                    .cor_sub_all_times_unchecked(production.cost(), built as i64);
+                let new_active = production.active() + delta;
+                if new_active > production.count() {
+                    production.set_count(new_active);
+                }
+                production.set_active(new_active);
+            }
+            SimCommand::DeactivateProduction { index, delta } => {
+                let production = sim
+                    .productions
+                    .get_mut(index)
+                    .with_context(|| format!("No production {index}"))?;
+                production.set_active(production.active().saturating_sub(delta));
+            }
+            SimCommand::RemoveProduction { index } => {
+                ensure!(index < sim.productions.len(), "No production {index}");
+                sim.productions.remove(index);
+            }
+            SimCommand::StartResearch(id) => {
+                ensure!(
+                    !sim.research.is_researched(id) && sim.research.is_available(id),
+                    "Technology {} can't be researched",
+                    shared_comps.config_label(id)?
+                );
+                sim.research.start(id);
+            }
+            SimCommand::SaveTemplate(template) => {
+                sim.templates.retain(|t| t.name != template.name);
+                sim.templates.push(template);
+            }
+            SimCommand::RemoveTemplate { index } => {
+                ensure!(index < sim.templates.len(), "No template {index}");
+                sim.templates.remove(index);
+            }
+            SimCommand::SpawnResource { resource, amount } => {
+                sim.depot.cor_put(&resource, amount);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serializable for SimCommand {
+    type Raw = RawSimCommand;
+
+    fn from_serializable(raw: Self::Raw, ctx: ComponentsRef<'_>) -> Result<Self> {
+        Ok(match raw {
+            RawSimCommand::CreateProduction {
+                template,
+                count,
+                index,
+            } => SimCommand::CreateProduction {
+                template: Serializable::from_serializable(template, ctx)?,
+                count,
+                index,
+            },
+            RawSimCommand::ActivateProduction { index, delta } => {
+                SimCommand::ActivateProduction { index, delta }
+            }
+            RawSimCommand::DeactivateProduction { index, delta } => {
+                SimCommand::DeactivateProduction { index, delta }
+            }
+            RawSimCommand::RemoveProduction { index } => SimCommand::RemoveProduction { index },
+            RawSimCommand::StartResearch(id) => {
+                SimCommand::StartResearch(Serializable::from_serializable(id, ctx)?)
+            }
+            RawSimCommand::SaveTemplate(template) => {
+                SimCommand::SaveTemplate(Serializable::from_serializable(template, ctx)?)
+            }
+            RawSimCommand::RemoveTemplate { index } => SimCommand::RemoveTemplate { index },
+            RawSimCommand::SpawnResource { resource, amount } => SimCommand::SpawnResource {
+                resource: Serializable::from_serializable(resource, ctx)?,
+                amount,
+            },
+        })
+    }
+
+    fn into_serializable(self, ctx: ComponentsRef<'_>) -> Result<Self::Raw> {
+        Ok(match self {
+            SimCommand::CreateProduction {
+                template,
+                count,
+                index,
+            } => RawSimCommand::CreateProduction {
+                template: template.into_serializable(ctx)?,
+                count,
+                index,
+            },
+            SimCommand::ActivateProduction { index, delta } => {
+                RawSimCommand::ActivateProduction { index, delta }
+            }
+            SimCommand::DeactivateProduction { index, delta } => {
+                RawSimCommand::DeactivateProduction { index, delta }
+            }
+            SimCommand::RemoveProduction { index } => RawSimCommand::RemoveProduction { index },
+            SimCommand::StartResearch(id) => {
+                RawSimCommand::StartResearch(id.into_serializable(ctx)?)
+            }
+            SimCommand::SaveTemplate(template) => {
+                RawSimCommand::SaveTemplate(template.into_serializable(ctx)?)
+            }
+            SimCommand::RemoveTemplate { index } => RawSimCommand::RemoveTemplate { index },
+            SimCommand::SpawnResource { resource, amount } => RawSimCommand::SpawnResource {
+                resource: resource.into_serializable(ctx)?,
+                amount,
+            },
+        })
+    }
+}
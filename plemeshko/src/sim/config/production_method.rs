@@ -1,12 +1,15 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     state::{
         components::{ComponentsRef, SharedComponents},
         config::{
-            Config, ConfigsLoadingContext, FatConfigId, FatConfigLabel, Info, Prepare, RawInfo,
+            Config, ConfigChecker, ConfigsLoadingContext, FatConfigId, FatConfigLabel, Info,
+            Prepare, RawInfo,
         },
+        research::Research,
         serializable::Serializable,
         text::TextIdFactory,
     },
@@ -19,7 +22,7 @@ use super::{
     setting_group::{SettingGroup, SettingGroupId},
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct RawProductionMethod {
     #[serde(flatten)]
     pub info: RawInfo,
@@ -43,7 +46,7 @@ pub struct RawFixedProductionMethod {
     pub settings: Vec<FatConfigLabel<Setting>>,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 pub struct FixedProductionMethod {
     pub id: ProductionMethodId,
     pub settings: Vec<SettingId>,
@@ -72,6 +75,74 @@ impl FixedProductionMethod {
         })
     }
 
+    /// Selects the first unlocked setting in each group.
+    /// Returns `None` when some group has no unlocked settings.
+    pub fn new_unlocked(
+        shared_comps: &SharedComponents,
+        research: &Research,
+        id: ProductionMethodId,
+    ) -> Result<Option<FixedProductionMethod>> {
+        let method = shared_comps.config(id)?;
+        let mut settings = Vec::with_capacity(method.setting_groups.len());
+        for &setting_group in method.setting_groups.iter() {
+            match shared_comps
+                .config(setting_group)?
+                .settings
+                .iter()
+                .find(|&&setting_id| research.is_setting_unlocked(setting_id))
+            {
+                Some(&setting_id) => settings.push(setting_id),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(FixedProductionMethod { id, settings }))
+    }
+
+    /// Checks that each setting belongs to the respective setting group of the method.
+    pub fn validate(&self, shared_comps: &SharedComponents) -> Result<()> {
+        let method = shared_comps.config(self.id)?;
+        let method_label = shared_comps.config_label(self.id)?;
+        if method.setting_groups.len() != self.settings.len() {
+            bail!(
+                "Production method \"{method_label}\" has {} setting groups, but {} settings were selected",
+                method.setting_groups.len(),
+                self.settings.len()
+            );
+        }
+        for (&setting_group_id, &setting_id) in method.setting_groups.iter().zip(&self.settings) {
+            let setting = shared_comps.config(setting_id)?;
+            let setting_label = shared_comps.config_label(setting_id)?;
+            if setting.group != setting_group_id {
+                bail!(
+                    "Setting \"{setting_label}\" doesn't belong to the group \"{}\" of production method \"{method_label}\"",
+                    shared_comps.config_label(setting_group_id)?
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that the method and all selected settings are unlocked.
+    pub fn check_unlocked(
+        &self,
+        shared_comps: &SharedComponents,
+        research: &Research,
+    ) -> Result<()> {
+        let method_label = shared_comps.config_label(self.id)?;
+        if !research.is_production_unlocked(self.id) {
+            bail!("Production method is locked: {method_label}");
+        }
+        for &setting_id in self.settings.iter() {
+            if !research.is_setting_unlocked(setting_id) {
+                bail!(
+                    "Setting of production method \"{method_label}\" is locked: {}",
+                    shared_comps.config_label(setting_id)?
+                );
+            }
+        }
+        Ok(())
+    }
+
     pub fn accumulate(
         &self,
         shared_comps: &SharedComponents,
@@ -111,6 +182,17 @@ impl Config for ProductionMethod {
     type Raw = RawProductionMethod;
 
     const TAG: &'static str = "production-method";
+
+    fn validate(checker: &mut ConfigChecker<'_>) -> Result<()> {
+        let shared_comps = checker.shared_comps;
+        for method in shared_comps.iter_configs::<ProductionMethod>() {
+            let (id, method) = method?;
+            let from = checker.describe(id);
+            checker.check_refs(&from, method.setting_groups.iter().copied());
+            checker.check_info(&from, &method.info);
+        }
+        Ok(())
+    }
 }
 
 impl Serializable for FixedProductionMethod {
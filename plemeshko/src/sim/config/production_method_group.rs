@@ -1,14 +1,15 @@
 use anyhow::Result;
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::state::{
-    config::{Config, ConfigsLoadingContext, FatConfigId, FatConfigLabel, Prepare},
+    config::{Config, ConfigChecker, ConfigsLoadingContext, FatConfigId, FatConfigLabel, Prepare},
     text::{FatTextId, TextIdFactory},
 };
 
 use super::production_method::{ProductionMethod, ProductionMethodId};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct RawProductionMethodGroup {
     pub variants: Vec<FatConfigLabel<ProductionMethod>>,
 }
@@ -43,4 +44,15 @@ impl Config for ProductionMethodGroup {
     type Raw = RawProductionMethodGroup;
 
     const TAG: &'static str = "production-method-group";
+
+    fn validate(checker: &mut ConfigChecker<'_>) -> Result<()> {
+        let shared_comps = checker.shared_comps;
+        for group in shared_comps.iter_configs::<ProductionMethodGroup>() {
+            let (id, group) = group?;
+            let from = checker.describe(id);
+            checker.check_refs(&from, group.variants.iter().copied());
+            checker.check_text(&from, &group.name);
+        }
+        Ok(())
+    }
 }
@@ -1,20 +1,25 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use anyhow::Result;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     sim::units::{ResourceAmount, ResourceWeight},
     state::{
         components::ComponentsRef,
-        config::{Config, FatConfigId, FatConfigLabel, Info, Prepare, RawInfo},
+        config::{Config, ConfigChecker, FatConfigId, FatConfigLabel, Info, Prepare, RawInfo},
         serializable::Serializable,
     },
 };
 
-use super::transport_group::{TransportGroup, TransportGroupId};
+use super::{
+    technology::{Technology, TechnologyBonus},
+    transport_group::{TransportGroup, TransportGroupId},
+    transport_method::TransportMethod,
+};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct RawResource {
     #[serde(flatten)]
     pub info: RawInfo,
@@ -34,7 +39,7 @@ pub type ResourceId = FatConfigId<Resource>;
 pub type RawResourceMap = HashMap<FatConfigLabel<Resource>, ResourceAmount>;
 pub type ResourceMap = HashMap<ResourceId, ResourceAmount>;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct RawResourceIo {
     #[serde(default)]
     pub input: RawResourceMap,
@@ -77,6 +82,41 @@ impl Config for Resource {
     type Raw = RawResource;
 
     const TAG: &'static str = "resource";
+
+    fn validate(checker: &mut ConfigChecker<'_>) -> Result<()> {
+        let shared_comps = checker.shared_comps;
+        // transport methods unlocked initially or by some technology
+        let mut unlockable = HashSet::new();
+        for method in shared_comps.iter_configs::<TransportMethod>() {
+            let (id, method) = method?;
+            if method.initially_unlocked {
+                unlockable.insert(id);
+            }
+        }
+        for technology in shared_comps.iter_configs::<Technology>() {
+            for bonus in &technology?.1.bonuses {
+                if let TechnologyBonus::UnlockTransport(id) = bonus {
+                    unlockable.insert(*id);
+                }
+            }
+        }
+        for resource in shared_comps.iter_configs::<Resource>() {
+            let (id, resource) = resource?;
+            let from = checker.describe(id);
+            checker.check_info(&from, &resource.info);
+            if !checker.check_ref(&from, resource.transport_group) {
+                continue;
+            }
+            let group = shared_comps.config(resource.transport_group)?;
+            if !group.transports.iter().any(|id| unlockable.contains(id)) {
+                checker.report(format!(
+                    "{from} is transported by {}, which has no transport methods that can be unlocked",
+                    checker.describe(resource.transport_group)
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Prepare for RawResourceIo {
@@ -1,11 +1,12 @@
 use anyhow::Result;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     sim::units::Ticks,
     state::{
         components::SharedComponents,
-        config::{Config, FatConfigId, FatConfigLabel, Prepare},
+        config::{Config, ConfigChecker, FatConfigId, FatConfigLabel, Prepare},
         text::FatTextId,
     },
 };
@@ -15,7 +16,7 @@ use super::{
     setting_group::{SettingGroup, SettingGroupId},
 };
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct RawSetting {
     pub group: FatConfigLabel<SettingGroup>,
     #[serde(flatten)]
@@ -24,6 +25,13 @@ pub struct RawSetting {
     pub cost: RawResourceMap,
     #[serde(default)]
     pub time_to_complete: Ticks,
+    /// Settings are unlocked unless they're meant to be unlocked by technologies.
+    #[serde(default = "default_initially_unlocked")]
+    pub initially_unlocked: bool,
+}
+
+fn default_initially_unlocked() -> bool {
+    true
 }
 
 #[derive(Debug)]
@@ -33,6 +41,7 @@ pub struct Setting {
     pub resource_io: ResourceIo,
     pub cost: ResourceMap,
     pub time_to_complete: Ticks,
+    pub initially_unlocked: bool,
 }
 
 pub type SettingId = FatConfigId<Setting>;
@@ -59,6 +68,7 @@ impl Prepare for RawSetting {
                 resource_io: self.resource_io.prepare(ctx, tif)?,
                 cost: self.cost.prepare(ctx, tif)?,
                 time_to_complete: self.time_to_complete,
+                initially_unlocked: self.initially_unlocked,
             })
         })
     }
@@ -68,4 +78,18 @@ impl Config for Setting {
     type Raw = RawSetting;
 
     const TAG: &'static str = "setting";
+
+    fn validate(checker: &mut ConfigChecker<'_>) -> Result<()> {
+        let shared_comps = checker.shared_comps;
+        for setting in shared_comps.iter_configs::<Setting>() {
+            let (id, setting) = setting?;
+            let from = checker.describe(id);
+            checker.check_ref(&from, setting.group);
+            checker.check_refs(&from, setting.resource_io.input.keys().copied());
+            checker.check_refs(&from, setting.resource_io.output.keys().copied());
+            checker.check_refs(&from, setting.cost.keys().copied());
+            checker.check_text(&from, &setting.name);
+        }
+        Ok(())
+    }
 }
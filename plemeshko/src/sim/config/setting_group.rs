@@ -1,15 +1,16 @@
 use anyhow::Result;
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::state::{
     components::{ComponentIndexer, SharedComponents},
-    config::{Config, ConfigsLoadingContext, FatConfigId, Prepare},
+    config::{Config, ConfigChecker, ConfigsLoadingContext, FatConfigId, Prepare},
     text::TextIdFactory,
 };
 
 use super::setting::{Setting, SettingId};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct RawSettingGroup {}
 
 #[derive(Debug)]
@@ -38,6 +39,17 @@ impl Config for SettingGroup {
 
     const TAG: &'static str = "setting-group";
 
+    fn validate(checker: &mut ConfigChecker<'_>) -> Result<()> {
+        let shared_comps = checker.shared_comps;
+        for setting_group in shared_comps.iter_configs::<SettingGroup>() {
+            let (id, setting_group) = setting_group?;
+            if setting_group.settings.is_empty() {
+                checker.report(format!("{} has no settings", checker.describe(id)));
+            }
+        }
+        Ok(())
+    }
+
     fn finalize(indexer: &ComponentIndexer, shared_comps: &mut SharedComponents) -> Result<()> {
         // clear all setting groups
         for setting_group in shared_comps.iter_configs_mut::<SettingGroup>() {
@@ -48,11 +60,14 @@ impl Config for SettingGroup {
         // for all components - find all settings and push to respective groups
         let component_slot_ids = indexer.indices();
         for component_slot_id in component_slot_ids {
-            let component_setting_ids = match shared_comps.component_slot(component_slot_id)? {
-                Some(component) => component.configs.indexer::<Setting>()?.indices::<Setting>(),
-                None => continue,
-            };
-            let component_id = component_slot_id.assume_occupied();
+            let (component_id, component_setting_ids) =
+                match shared_comps.component_slot(component_slot_id)? {
+                    Some(component) => (
+                        component.id(),
+                        component.configs.indexer::<Setting>()?.indices::<Setting>(),
+                    ),
+                    None => continue,
+                };
             for component_setting_id in component_setting_ids {
                 let setting_group_id = shared_comps
                     .component_slot(component_slot_id)
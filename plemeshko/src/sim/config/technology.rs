@@ -1,36 +1,48 @@
+use std::collections::{HashMap, HashSet};
+
 use anyhow::Result;
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::state::{
-    config::{Config, FatConfigId, FatConfigLabel, Info, Prepare, RawInfo},
+    config::{Config, ConfigChecker, FatConfigId, FatConfigLabel, Info, Prepare, RawInfo},
     research::Research,
 };
 
 use super::{
     production_method::{ProductionMethod, ProductionMethodId},
+    setting::{Setting, SettingId},
     transport_method::{TransportMethod, TransportMethodId},
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)]
 pub enum RawTechnologyBonus {
     UnlockTransport(FatConfigLabel<TransportMethod>),
     UnlockProduction(FatConfigLabel<ProductionMethod>),
+    UnlockSetting(FatConfigLabel<Setting>),
 }
 
 #[derive(Clone, Copy, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum TechnologyBonus {
     UnlockTransport(TransportMethodId),
     UnlockProduction(ProductionMethodId),
+    UnlockSetting(SettingId),
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct RawTechnology {
     #[serde(flatten)]
     pub info: RawInfo,
     pub bonuses: Vec<RawTechnologyBonus>,
     pub prerequisites: Vec<FatConfigLabel<Technology>>,
     pub cost: u64,
+    #[serde(default)]
+    pub obsoleted_by: Vec<FatConfigLabel<Technology>>,
+    #[serde(default)]
+    pub lost_on_collapse: bool,
 }
 
 #[derive(Debug)]
@@ -39,23 +51,47 @@ pub struct Technology {
     pub bonuses: Vec<TechnologyBonus>,
     pub prerequisites: Vec<TechnologyId>,
     pub cost: u64,
-
-    prerequisites_satisfied: bool,
+    /// Technologies which revoke bonuses of this one once researched.
+    pub obsoleted_by: Vec<TechnologyId>,
+    /// Whether the technology is forgotten when the population starves.
+    pub lost_on_collapse: bool,
 }
 
 pub type TechnologyId = FatConfigId<Technology>;
 
 impl Technology {
-    pub fn check_prerequisites(&mut self, research: &Research) {
-        self.prerequisites_satisfied = self
-            .prerequisites
+    pub fn prerequisites_satisfied(&self, research: &Research) -> bool {
+        self.prerequisites
             .iter()
-            .all(|id| research.is_researched(*id));
+            .all(|id| research.is_researched(*id))
     }
+}
 
-    pub fn prerequisites_satisfied(&self) -> bool {
-        self.prerequisites_satisfied
+/// Finds cycles of prerequisites reachable from the technology, depth-first.
+/// `finished` is `false` for technologies on the current path and `true` for fully visited ones.
+fn find_prerequisite_cycles(
+    id: TechnologyId,
+    prerequisites: &HashMap<TechnologyId, &[TechnologyId]>,
+    finished: &mut HashMap<TechnologyId, bool>,
+    path: &mut Vec<TechnologyId>,
+    cycles: &mut Vec<Vec<TechnologyId>>,
+) {
+    match finished.get(&id) {
+        Some(true) => return,
+        Some(false) => {
+            let start = path.iter().position(|on_path| *on_path == id).unwrap();
+            cycles.push(path[start..].to_vec());
+            return;
+        }
+        None => (),
     }
+    finished.insert(id, false);
+    path.push(id);
+    for prerequisite in prerequisites.get(&id).copied().unwrap_or_default() {
+        find_prerequisite_cycles(*prerequisite, prerequisites, finished, path, cycles);
+    }
+    path.pop();
+    finished.insert(id, true);
 }
 
 impl Prepare for RawTechnologyBonus {
@@ -74,6 +110,9 @@ impl Prepare for RawTechnologyBonus {
                 RawTechnologyBonus::UnlockProduction(id) => {
                     TechnologyBonus::UnlockProduction(id.prepare(ctx, tif)?)
                 }
+                RawTechnologyBonus::UnlockSetting(id) => {
+                    TechnologyBonus::UnlockSetting(id.prepare(ctx, tif)?)
+                }
             })
         })
     }
@@ -94,7 +133,8 @@ impl Prepare for RawTechnology {
                 bonuses: self.bonuses.prepare(ctx, tif)?,
                 prerequisites: self.prerequisites.prepare(ctx, tif)?,
                 cost: self.cost,
-                prerequisites_satisfied: false,
+                obsoleted_by: self.obsoleted_by.prepare(ctx, tif)?,
+                lost_on_collapse: self.lost_on_collapse,
             })
         })
     }
@@ -104,4 +144,85 @@ impl Config for Technology {
     type Raw = RawTechnology;
 
     const TAG: &'static str = "technology";
+
+    fn validate(checker: &mut ConfigChecker<'_>) -> Result<()> {
+        let technologies: Vec<_> = checker
+            .shared_comps
+            .iter_configs::<Technology>()
+            .try_collect()?;
+        for (id, technology) in &technologies {
+            let from = checker.describe(*id);
+            checker.check_refs(&from, technology.prerequisites.iter().copied());
+            checker.check_refs(&from, technology.obsoleted_by.iter().copied());
+            for bonus in &technology.bonuses {
+                match *bonus {
+                    TechnologyBonus::UnlockTransport(id) => checker.check_ref(&from, id),
+                    TechnologyBonus::UnlockProduction(id) => checker.check_ref(&from, id),
+                    TechnologyBonus::UnlockSetting(id) => checker.check_ref(&from, id),
+                };
+            }
+            checker.check_info(&from, &technology.info);
+        }
+
+        let prerequisites: HashMap<_, _> = technologies
+            .iter()
+            .map(|(id, technology)| (*id, technology.prerequisites.as_slice()))
+            .collect();
+        let mut finished = HashMap::new();
+        let mut cycles = Vec::new();
+        for (id, _) in &technologies {
+            find_prerequisite_cycles(
+                *id,
+                &prerequisites,
+                &mut finished,
+                &mut Vec::new(),
+                &mut cycles,
+            );
+        }
+        let mut in_cycles = HashSet::new();
+        for cycle in cycles {
+            let mut cycle: Vec<_> = cycle
+                .into_iter()
+                .map(|id| {
+                    in_cycles.insert(id);
+                    checker.describe(id)
+                })
+                .collect();
+            // the same cycle is reported the same way regardless of where it was entered
+            let first = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap();
+            cycle.rotate_left(first);
+            cycle.push(cycle[0].clone());
+            checker.report(format!(
+                "prerequisites of technologies form a cycle: {}",
+                cycle.join(" -> ")
+            ));
+        }
+
+        // technologies are researchable once all of their prerequisites are
+        let mut researchable = HashSet::with_capacity(technologies.len());
+        loop {
+            let researchable_count = researchable.len();
+            for (id, technology) in &technologies {
+                if technology
+                    .prerequisites
+                    .iter()
+                    .all(|prerequisite| researchable.contains(prerequisite))
+                {
+                    researchable.insert(*id);
+                }
+            }
+            if researchable.len() == researchable_count {
+                break;
+            }
+        }
+        for (id, _) in &technologies {
+            if !researchable.contains(id) && !in_cycles.contains(id) {
+                checker.report(format!(
+                    "{} can't be researched, some of its prerequisites can't be",
+                    checker.describe(*id)
+                ));
+            }
+        }
+        Ok(())
+    }
 }
@@ -1,14 +1,15 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::state::{
     components::{ComponentIndexer, SharedComponents},
-    config::{Config, FatConfigId, Prepare},
+    config::{Config, ConfigChecker, FatConfigId, Prepare},
     text::FatTextId,
 };
 
 use super::transport_method::{TransportMethod, TransportMethodId};
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct RawTransportGroup {}
 
 #[derive(Debug)]
@@ -40,6 +41,16 @@ impl Config for TransportGroup {
 
     const TAG: &'static str = "transport-group";
 
+    fn validate(checker: &mut ConfigChecker<'_>) -> anyhow::Result<()> {
+        let shared_comps = checker.shared_comps;
+        for group in shared_comps.iter_configs::<TransportGroup>() {
+            let (id, group) = group?;
+            let from = checker.describe(id);
+            checker.check_text(&from, &group.name);
+        }
+        Ok(())
+    }
+
     fn finalize(
         indexer: &ComponentIndexer,
         shared_comps: &mut SharedComponents,
@@ -53,14 +64,17 @@ impl Config for TransportGroup {
         // for all components - find all settings and push to respective groups
         let component_slot_ids = indexer.indices();
         for component_slot_id in component_slot_ids {
-            let component_transport_ids = match shared_comps.component_slot(component_slot_id)? {
-                Some(component) => component
-                    .configs
-                    .indexer::<TransportMethod>()?
-                    .indices::<TransportMethod>(),
-                None => continue,
-            };
-            let component_id = component_slot_id.assume_occupied();
+            let (component_id, component_transport_ids) =
+                match shared_comps.component_slot(component_slot_id)? {
+                    Some(component) => (
+                        component.id(),
+                        component
+                            .configs
+                            .indexer::<TransportMethod>()?
+                            .indices::<TransportMethod>(),
+                    ),
+                    None => continue,
+                };
             for component_transport_id in component_transport_ids {
                 let transport_group_id = shared_comps
                     .component_slot(component_slot_id)
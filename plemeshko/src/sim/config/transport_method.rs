@@ -1,8 +1,9 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::{
     sim::units::ResourceWeight,
-    state::config::{Config, FatConfigId, FatConfigLabel, Info, Prepare, RawInfo},
+    state::config::{Config, ConfigChecker, FatConfigId, FatConfigLabel, Info, Prepare, RawInfo},
 };
 
 use super::{
@@ -10,7 +11,7 @@ use super::{
     transport_group::{TransportGroup, TransportGroupId},
 };
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct RawTransportMethod {
     #[serde(flatten)]
     pub info: RawInfo,
@@ -60,4 +61,17 @@ impl Config for TransportMethod {
     type Raw = RawTransportMethod;
 
     const TAG: &'static str = "transport-method";
+
+    fn validate(checker: &mut ConfigChecker<'_>) -> anyhow::Result<()> {
+        let shared_comps = checker.shared_comps;
+        for method in shared_comps.iter_configs::<TransportMethod>() {
+            let (id, method) = method?;
+            let from = checker.describe(id);
+            checker.check_ref(&from, method.group);
+            checker.check_refs(&from, method.fuel.input.keys().copied());
+            checker.check_refs(&from, method.fuel.output.keys().copied());
+            checker.check_info(&from, &method.info);
+        }
+        Ok(())
+    }
 }
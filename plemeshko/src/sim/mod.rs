@@ -1,11 +1,13 @@
+pub mod command;
 pub mod config;
+pub mod planner;
 pub mod production;
+pub mod rng;
 pub mod units;
 
 use std::{cmp::Ordering, time::Duration};
 
 use anyhow::Result;
-use rand::random;
 use rodio::Source;
 use serde::{Deserialize, Serialize};
 
@@ -13,16 +15,21 @@ use crate::{
     state::{
         components::{ComponentsRef, SharedComponents},
         research::{RawResearch, Research},
-        serializable::Serializable,
+        serializable::{from_serializable_dropping, Serializable},
         SharedState,
     },
     util::cor::Cor,
 };
 
 use self::{
+    command::SimCommand,
     config::resource::{RawResourceMap, ResourceMap},
-    production::{Production, ProductionSnapshot, RawProductionSnapshot},
-    units::ResourceAmount,
+    production::{
+        Production, ProductionSnapshot, ProductionTemplate, RawProductionSnapshot,
+        RawProductionTemplate,
+    },
+    rng::SimRng,
+    units::{ResourceAmount, Ticks},
 };
 
 #[derive(Serialize, Deserialize)]
@@ -32,14 +39,21 @@ pub struct RawSimSnapshot {
     research: RawResearch,
     nutrition: i64,
     pop_growth_stack: f64,
+    tick: Ticks,
+    templates: Vec<RawProductionTemplate>,
+    rng: SimRng,
 }
 
+#[derive(Clone)]
 pub struct SimSnapshot {
     depot: ResourceMap,
     productions: Vec<ProductionSnapshot>,
     research: Research,
     nutrition: i64,
     pop_growth_stack: f64,
+    tick: Ticks,
+    templates: Vec<ProductionTemplate>,
+    rng: SimRng,
 }
 
 pub struct Sim {
@@ -51,6 +65,14 @@ pub struct Sim {
     pub research: Research,
     pub nutrition: i64,
     pub pop_growth_stack: f64,
+    /// Number of steps since the start of the game.
+    pub tick: Ticks,
+    pub templates: Vec<ProductionTemplate>,
+    rng: SimRng,
+    /// State the game was started or loaded with, which recorded commands are replayed from.
+    replay_start: SimSnapshot,
+    /// Commands applied since `replay_start`, with ticks they were applied at.
+    commands: Vec<(Ticks, SimCommand)>,
 }
 
 impl Sim {
@@ -58,12 +80,16 @@ impl Sim {
     pub const TICK_THRESHOLD: Duration = Duration::from_millis(1);
 
     pub fn restore(shared_comps: &SharedComponents, snapshot: SimSnapshot) -> anyhow::Result<Self> {
+        let replay_start = snapshot.clone();
         let SimSnapshot {
             depot,
             productions,
             research,
             nutrition,
             pop_growth_stack,
+            tick,
+            templates,
+            rng,
         } = snapshot;
         Ok(Sim {
             exited: false,
@@ -76,6 +102,11 @@ impl Sim {
             research,
             nutrition,
             pop_growth_stack,
+            tick,
+            templates,
+            rng,
+            replay_start,
+            commands: Vec::new(),
         })
     }
 
@@ -86,28 +117,51 @@ impl Sim {
             research: self.research.clone(),
             nutrition: self.nutrition,
             pop_growth_stack: self.pop_growth_stack,
+            tick: self.tick,
+            templates: self.templates.clone(),
+            rng: self.rng.clone(),
         }
     }
 
-    pub fn new(shared_comps: &mut SharedComponents) -> Result<Self> {
-        Ok(Sim {
-            state_changed: false,
-            exited: false,
-            depot: ResourceMap::new(),
-            productions: Vec::new(),
-            research: Research::new(shared_comps)?,
-            nutrition: 100,
-            pop_growth_stack: 0.0,
-        })
+    pub fn new(shared_comps: &SharedComponents) -> Result<Self> {
+        Self::restore(
+            shared_comps,
+            SimSnapshot {
+                depot: ResourceMap::new(),
+                productions: Vec::new(),
+                research: Research::new(shared_comps)?,
+                nutrition: 100,
+                pop_growth_stack: 0.0,
+                tick: Ticks::default(),
+                templates: Vec::new(),
+                rng: SimRng::from_entropy(),
+            },
+        )
+    }
+
+    /// Applies the player's command, recording it to be replayed.
+    pub fn apply(&mut self, shared_comps: &SharedComponents, command: SimCommand) -> Result<()> {
+        command.clone().execute(self, shared_comps)?;
+        self.commands.push((self.tick, command));
+        Ok(())
+    }
+
+    pub fn replay_start(&self) -> &SimSnapshot {
+        &self.replay_start
+    }
+
+    pub fn commands(&self) -> &[(Ticks, SimCommand)] {
+        &self.commands
     }
 
     pub fn exited(&self) -> bool {
         self.exited
     }
 
-    pub fn exit(&mut self) {
+    /// Stops the simulation, returning its final state to be saved.
+    pub fn exit(&mut self) -> SimSnapshot {
         self.exited = true;
-        // todo: finalization code, mb dropping resources / saving state
+        self.snapshot()
     }
 
     pub fn handle_state_changed(&mut self) -> bool {
@@ -135,7 +189,9 @@ impl Sim {
             self.productions[i].step_output(env, &mut self.depot)?;
         }
 
-        self.research.step(&env.components)?;
+        self.tick += Ticks(1);
+        self.research.step(&env.components, self.tick)?;
+        let was_fed = self.nutrition > 0;
 
         if self.nutrition > 10 {
             self.nutrition -= 10;
@@ -143,8 +199,12 @@ impl Sim {
             self.nutrition -= self.nutrition;
         }
 
-        let population = self.depot.get(&env.human_id).copied().unwrap_or_default();
-        let mut depot_food = self.depot.get(&env.food_id).copied().unwrap_or_default();
+        let (human_id, food_id) = {
+            let shared_comps = env.components.read().unwrap();
+            (shared_comps.human_id()?, shared_comps.food_id()?)
+        };
+        let population = self.depot.get(&human_id).copied().unwrap_or_default();
+        let mut depot_food = self.depot.get(&food_id).copied().unwrap_or_default();
         let food_need_value = (100 - self.nutrition) * 8 / 10;
         let food_needed = food_need_value * population.0;
         let food_eaten = match depot_food.0.cmp(&food_needed) {
@@ -166,36 +226,64 @@ impl Sim {
         let mut nutrition_increase = (10 * food_eaten) as f64 / (8.0 * population.0 as f64);
         self.nutrition += nutrition_increase.floor() as i64;
         nutrition_increase -= nutrition_increase.floor();
-        if random::<f64>() < nutrition_increase {
+        if self.rng.next_f64() < nutrition_increase {
             self.nutrition += 1;
         }
 
         self.pop_growth_stack += population.0 as f64 * ((self.nutrition - 50) as f64 / 10000.0);
 
         self.depot.cor_put(
-            &env.human_id,
+            &human_id,
             ResourceAmount(self.pop_growth_stack.ceil() as i64),
         );
-        self.depot
-            .cor_put(&env.food_id, ResourceAmount(-food_eaten));
+        self.depot.cor_put(&food_id, ResourceAmount(-food_eaten));
 
         self.pop_growth_stack -= self.pop_growth_stack.ceil();
 
+        // Starvation collapse: the population runs out of nutrition
+        if was_fed && self.nutrition <= 0 && population.0 > 0 {
+            let shared_comps = env.components.read().unwrap();
+            self.research.collapse(&shared_comps, self.tick)?;
+            // productions can't keep working with methods whose technologies were lost
+            for production in self.productions.iter_mut() {
+                if production.active() > 0 && production.is_locked(&shared_comps, &self.research) {
+                    log::info!(
+                        "Production deactivated after collapse: {}",
+                        production.name()
+                    );
+                    production.set_active(0);
+                }
+            }
+        }
+
         self.state_changed = true;
         Ok(())
     }
 }
 
+impl SimSnapshot {
+    pub fn tick(&self) -> Ticks {
+        self.tick
+    }
+
+    pub fn depot(&self) -> &ResourceMap {
+        &self.depot
+    }
+}
+
 impl Serializable for SimSnapshot {
     type Raw = RawSimSnapshot;
 
     fn from_serializable(raw: Self::Raw, ctx: ComponentsRef<'_>) -> Result<Self> {
         Ok(SimSnapshot {
-            depot: Serializable::from_serializable(raw.depot, ctx)?,
-            productions: Serializable::from_serializable(raw.productions, ctx)?,
+            depot: from_serializable_dropping(raw.depot, ctx)?,
+            productions: from_serializable_dropping(raw.productions, ctx)?,
             research: Serializable::from_serializable(raw.research, ctx)?,
             nutrition: raw.nutrition,
             pop_growth_stack: raw.pop_growth_stack,
+            tick: raw.tick,
+            templates: from_serializable_dropping(raw.templates, ctx)?,
+            rng: raw.rng,
         })
     }
 
@@ -206,6 +294,9 @@ impl Serializable for SimSnapshot {
             research: self.research.into_serializable(ctx)?,
             nutrition: self.nutrition,
             pop_growth_stack: self.pop_growth_stack,
+            tick: self.tick,
+            templates: self.templates.into_serializable(ctx)?,
+            rng: self.rng,
         })
     }
 }
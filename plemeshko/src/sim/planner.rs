@@ -0,0 +1,294 @@
+use std::{collections::HashMap, ops::AddAssign};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    state::{components::SharedComponents, research::Research},
+    util::cor::Cor,
+};
+
+use super::{
+    config::{
+        production_method::{FixedProductionMethod, ProductionMethod},
+        resource::{ResourceId, ResourceIo, ResourceMap},
+        transport_group::TransportGroupId,
+    },
+    units::{ResourceAmount, ResourceWeight},
+};
+
+/// Limit on the number of deficits resolved by the planner,
+/// exceeded when a chain of recipes feeds itself without converging.
+const MAX_PLANNER_ITERATIONS: usize = 256;
+
+/// How the planner covers the demand for a resource.
+#[derive(Clone, PartialEq, Eq)]
+pub enum RecipeChoice {
+    /// The resource is taken from the depot.
+    Depot,
+    Method(FixedProductionMethod),
+}
+
+/// Production method with selected settings, run by some number of units each tick.
+pub struct PlanStep {
+    pub method: FixedProductionMethod,
+    pub units: i64,
+    /// Input and output of a single unit per tick.
+    pub single_io: ResourceIo,
+    /// Cost of a single unit.
+    pub single_cost: ResourceMap,
+}
+
+pub struct ProductionPlan {
+    pub steps: Vec<PlanStep>,
+    /// Resources for which a recipe was chosen, in order of resolution,
+    /// with the index of the respective step or `None` when taken from the depot.
+    pub resolved: Vec<(ResourceId, Option<usize>)>,
+    /// Resources which have to be supplied from the depot each tick.
+    pub external_input: ResourceMap,
+    /// Resources produced each tick in excess of the targets.
+    pub surplus: ResourceMap,
+    /// Input and output of all units per tick, including resources returned back like workers.
+    pub total_io: ResourceIo,
+    /// Total cost of all units.
+    pub cost: ResourceMap,
+    /// Weight moved between the depot and all units each tick.
+    pub transport_weight: HashMap<TransportGroupId, ResourceWeight>,
+}
+
+fn net_output(io: &ResourceIo, id: ResourceId) -> ResourceAmount {
+    io.output.get(&id).copied().unwrap_or_default() - io.input.get(&id).copied().unwrap_or_default()
+}
+
+/// Lists all unlocked production methods and setting combinations with positive net output of the resource.
+pub fn recipe_candidates(
+    shared_comps: &SharedComponents,
+    research: &Research,
+    resource_id: ResourceId,
+) -> Result<Vec<FixedProductionMethod>> {
+    let mut candidates = Vec::new();
+    for method in shared_comps.iter_configs::<ProductionMethod>() {
+        let (method_id, method) = method?;
+        if !research.is_production_unlocked(method_id) {
+            continue;
+        }
+        let mut combinations = vec![Vec::with_capacity(method.setting_groups.len())];
+        for &setting_group_id in &method.setting_groups {
+            let settings = &shared_comps.config(setting_group_id)?.settings;
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    settings
+                        .iter()
+                        .filter(|&&setting_id| research.is_setting_unlocked(setting_id))
+                        .map(move |&setting_id| {
+                            let mut combination = combination.clone();
+                            combination.push(setting_id);
+                            combination
+                        })
+                })
+                .collect();
+        }
+        for settings in combinations {
+            let candidate = FixedProductionMethod {
+                id: method_id,
+                settings,
+            };
+            let mut io = ResourceIo::new();
+            candidate.accumulate(shared_comps, &mut ResourceMap::new(), &mut io)?;
+            if net_output(&io, resource_id) > ResourceAmount(0) {
+                candidates.push(candidate);
+            }
+        }
+    }
+    Ok(candidates)
+}
+
+/// Picks the candidate with the highest net output of the resource, the depot when there are none.
+fn default_recipe(
+    shared_comps: &SharedComponents,
+    research: &Research,
+    resource_id: ResourceId,
+) -> Result<RecipeChoice> {
+    let mut best = None;
+    for candidate in recipe_candidates(shared_comps, research, resource_id)? {
+        let mut io = ResourceIo::new();
+        candidate.accumulate(shared_comps, &mut ResourceMap::new(), &mut io)?;
+        let output = net_output(&io, resource_id);
+        match &best {
+            Some((best_output, _)) if output <= *best_output => {}
+            _ => best = Some((output, candidate)),
+        }
+    }
+    Ok(match best {
+        Some((_, candidate)) => RecipeChoice::Method(candidate),
+        None => RecipeChoice::Depot,
+    })
+}
+
+/// Calculates units of production needed to output `targets` each tick.
+/// Recipes are taken from `choices`, resources without a choice use the most productive unlocked recipe.
+pub fn plan(
+    shared_comps: &SharedComponents,
+    research: &Research,
+    targets: &ResourceMap,
+    choices: &HashMap<ResourceId, RecipeChoice>,
+) -> Result<ProductionPlan> {
+    let mut steps = Vec::<PlanStep>::new();
+    let mut resolved = Vec::<(ResourceId, Option<usize>)>::new();
+    let mut balance = ResourceMap::new();
+    for (&id, &amount) in targets {
+        balance.cor_put(&id, -amount);
+    }
+
+    let mut iterations = 0;
+    loop {
+        let deficit = balance
+            .iter()
+            .filter(|&(id, &amount)| {
+                amount < ResourceAmount(0)
+                    && !resolved
+                        .iter()
+                        .any(|&(rid, step)| rid == *id && step.is_none())
+            })
+            .map(|(&id, &amount)| (id, amount))
+            .min_by_key(|&(id, _)| id);
+        let Some((resource_id, deficit)) = deficit else {
+            break;
+        };
+        iterations += 1;
+        if iterations > MAX_PLANNER_ITERATIONS {
+            bail!(
+                "Production chain doesn't converge after {MAX_PLANNER_ITERATIONS} iterations, recipes may feed each other"
+            );
+        }
+
+        let step_index = match resolved.iter().find(|&&(rid, _)| rid == resource_id) {
+            Some(&(_, step_index)) => step_index,
+            None => {
+                let choice = match choices.get(&resource_id) {
+                    Some(choice) => choice.clone(),
+                    None => default_recipe(shared_comps, research, resource_id)?,
+                };
+                let step_index = match choice {
+                    RecipeChoice::Depot => None,
+                    RecipeChoice::Method(method) => {
+                        match steps.iter().position(|step| step.method == method) {
+                            Some(step_index) => Some(step_index),
+                            None => {
+                                let mut single_io = ResourceIo::new();
+                                let mut single_cost = ResourceMap::new();
+                                method.accumulate(
+                                    shared_comps,
+                                    &mut single_cost,
+                                    &mut single_io,
+                                )?;
+                                steps.push(PlanStep {
+                                    method,
+                                    units: 0,
+                                    single_io,
+                                    single_cost,
+                                });
+                                Some(steps.len() - 1)
+                            }
+                        }
+                    }
+                };
+                resolved.push((resource_id, step_index));
+                step_index
+            }
+        };
+        let Some(step_index) = step_index else {
+            continue;
+        };
+
+        let step = &mut steps[step_index];
+        let output = net_output(&step.single_io, resource_id);
+        if output <= ResourceAmount(0) {
+            bail!(
+                "Production method \"{}\" doesn't produce \"{}\"",
+                shared_comps.config_label(step.method.id)?,
+                shared_comps.config_label(resource_id)?
+            );
+        }
+        let units = (-deficit).div_ceil(output);
+        step.units += units;
+        balance.cor_put_all_times(&step.single_io.output, units);
+        // inputs may be absent from the balance, unlike the depot `cor_sub_all_times_unchecked` is meant for
+        for (&id, &amount) in &step.single_io.input {
+            *balance.entry(id).or_default() -= amount * units;
+        }
+    }
+
+    let mut total_io = ResourceIo::new();
+    let mut cost = ResourceMap::new();
+    let mut transport_weight = HashMap::<TransportGroupId, ResourceWeight>::new();
+    for step in &steps {
+        total_io
+            .input
+            .cor_put_all_times(&step.single_io.input, step.units);
+        total_io
+            .output
+            .cor_put_all_times(&step.single_io.output, step.units);
+        cost.cor_put_all_times(&step.single_cost, step.units);
+        for (&id, &amount) in step.single_io.input.iter().chain(&step.single_io.output) {
+            let resource = shared_comps.config(id)?;
+            transport_weight
+                .entry(resource.transport_group)
+                .or_default()
+                .add_assign(amount * step.units * resource.transport_weight);
+        }
+    }
+
+    let mut external_input = ResourceMap::new();
+    let mut surplus = ResourceMap::new();
+    for (id, amount) in balance {
+        if amount < ResourceAmount(0) {
+            external_input.insert(id, -amount);
+        } else if amount > ResourceAmount(0) {
+            surplus.insert(id, amount);
+        }
+    }
+
+    Ok(ProductionPlan {
+        steps,
+        resolved,
+        external_input,
+        surplus,
+        total_io,
+        cost,
+        transport_weight,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::RwLock;
+
+    use super::*;
+    use crate::{sim::units::Ticks, state::load_test_core};
+
+    #[test]
+    fn plans_resources_with_inputs_missing_from_targets() {
+        let components = load_test_core();
+        let shared = RwLock::new(components.shared);
+        let shared_comps = shared.read().unwrap();
+        let core_configs = &shared_comps.core().unwrap().configs;
+        let mut research = Research::new(&shared_comps).unwrap();
+        // handicraft production making instruments is unlocked by tech2
+        for technology in ["tech1", "tech2"] {
+            let id = core_configs.id_from_raw(technology).unwrap().in_core();
+            research.start(id);
+            while !research.is_researched(id) {
+                research.step(&shared, Ticks(0)).unwrap();
+            }
+        }
+        let instrument: ResourceId = core_configs
+            .id_from_raw("stone-instrument")
+            .unwrap()
+            .in_core();
+        let targets = ResourceMap::from([(instrument, ResourceAmount(1))]);
+        let plan = plan(&shared_comps, &research, &targets, &HashMap::new()).unwrap();
+        assert!(!plan.steps.is_empty());
+        assert!(net_output(&plan.total_io, instrument) >= ResourceAmount(1));
+    }
+}
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use serde::{Deserialize, Serialize};
 
 use crate::{
@@ -6,6 +6,7 @@ use crate::{
     state::{
         components::{ComponentsRef, SharedComponents},
         config::FatConfigLabel,
+        research::Research,
         serializable::Serializable,
         SharedState,
     },
@@ -51,6 +52,21 @@ pub struct ProductionSnapshot {
     reserve_export_threshold: u32,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct RawProductionTemplate {
+    name: String,
+    selected_methods: Vec<RawFixedProductionMethod>,
+    transport: HashMap<FatConfigLabel<TransportGroup>, FatConfigLabel<TransportMethod>>,
+}
+
+/// Configuration of a production without its state, used to create new productions.
+#[derive(Clone)]
+pub struct ProductionTemplate {
+    pub name: String,
+    pub selected_methods: Vec<FixedProductionMethod>,
+    pub transport: HashMap<TransportGroupId, TransportMethodId>,
+}
+
 pub struct Production {
     state: ProductionSnapshot,
     single_io: ResourceIo,
@@ -64,28 +80,54 @@ pub struct Production {
 impl Production {
     pub fn new(
         shared_comps: &SharedComponents,
+        research: &Research,
         name: String,
         selected_methods: Vec<FixedProductionMethod>,
         transport: HashMap<TransportGroupId, TransportMethodId>,
     ) -> anyhow::Result<Self> {
-        Self::restore(
+        let snapshot = ProductionSnapshot {
+            name,
+            selected_methods,
+            transport,
+            storage: ResourceMap::new(),
+            count: 1,
+            active: 1,
+            reserve_export_threshold: 1,
+        };
+        snapshot
+            .check_unlocked(shared_comps, research)
+            .with_context(|| format!("Invalid production \"{}\"", snapshot.name))?;
+        Self::restore(shared_comps, snapshot)
+    }
+
+    /// Creates production with `count` active units.
+    pub fn from_template(
+        shared_comps: &SharedComponents,
+        research: &Research,
+        template: ProductionTemplate,
+        count: u32,
+    ) -> anyhow::Result<Self> {
+        let mut production = Self::new(
             shared_comps,
-            ProductionSnapshot {
-                name,
-                selected_methods,
-                transport,
-                storage: ResourceMap::new(),
-                count: 1,
-                active: 1,
-                reserve_export_threshold: 1,
-            },
-        )
+            research,
+            template.name,
+            template.selected_methods,
+            template.transport,
+        )?;
+        production.set_count(count);
+        production.set_active(count);
+        Ok(production)
     }
 
+    /// Restored productions may use methods and settings which were locked since they were created,
+    /// see [`Production::is_locked`].
     pub fn restore(
         shared_comps: &SharedComponents,
         snapshot: ProductionSnapshot,
     ) -> anyhow::Result<Self> {
+        snapshot
+            .validate(shared_comps)
+            .with_context(|| format!("Invalid production \"{}\"", snapshot.name))?;
         let mut single_input = HashMap::<ResourceId, ResourceAmount>::new();
         let mut single_output = HashMap::<ResourceId, ResourceAmount>::new();
         let mut cost = ResourceMap::new();
@@ -107,6 +149,17 @@ impl Production {
                 cost.cor_put_all(&setting.cost);
             }
         }
+        // steps expect transport for every resource moved
+        for &resource_id in single_input.keys().chain(single_output.keys()) {
+            let group = shared_comps.config(resource_id)?.transport_group;
+            if !snapshot.transport.contains_key(&group) {
+                bail!(
+                    "Production \"{}\" has no transport method for the transport group \"{}\"",
+                    snapshot.name,
+                    shared_comps.config_label(group)?
+                );
+            }
+        }
 
         Ok(Production {
             state: snapshot,
@@ -124,6 +177,14 @@ impl Production {
         self.state.clone()
     }
 
+    pub fn template(&self) -> ProductionTemplate {
+        ProductionTemplate {
+            name: self.state.name.clone(),
+            selected_methods: self.state.selected_methods.clone(),
+            transport: self.state.transport.clone(),
+        }
+    }
+
     pub fn name(&self) -> &str {
         &self.state.name
     }
@@ -156,6 +217,12 @@ impl Production {
         self.state.active = active;
     }
 
+    /// Whether the production uses methods, settings or transport which aren't unlocked,
+    /// for example after their technologies were lost. Such productions can't be activated.
+    pub fn is_locked(&self, shared_comps: &SharedComponents, research: &Research) -> bool {
+        self.state.check_unlocked(shared_comps, research).is_err()
+    }
+
     pub fn single_io(&self) -> &ResourceIo {
         &self.single_io
     }
@@ -202,8 +269,10 @@ impl Production {
             }
         }
 
-        requested_resources
-            .sort_unstable_by_key(|(_, _, _, transportation_priority)| *transportation_priority);
+        // ids break ties, so that steps don't depend on the order of hash maps and replays are deterministic
+        requested_resources.sort_unstable_by_key(|(res_id, _, _, transportation_priority)| {
+            (*transportation_priority, **res_id)
+        });
 
         for (res_id, res, req_amount, _) in requested_resources {
             let tr_group = &res.transport_group;
@@ -291,10 +360,14 @@ impl Production {
         let shared_comps = shared_st.components.read().unwrap();
         let mut transport_state =
             HashMap::<TransportGroupId, (&TransportMethod, ResourceWeight)>::new();
+        let human_id = shared_comps.human_id()?;
         let active = self.active() as i64;
-        for (&res_id, res_amount) in self.state.storage.iter_mut() {
+        // sorted, so that steps don't depend on the order of hash maps and replays are deterministic
+        let mut stored: Vec<_> = self.state.storage.iter_mut().collect();
+        stored.sort_unstable_by_key(|(res_id, _)| **res_id);
+        for (&res_id, res_amount) in stored {
             // humans are always exported back to the global storage
-            if self.state.reserve_export_threshold > 0 && res_id != shared_st.human_id {
+            if self.state.reserve_export_threshold > 0 && res_id != human_id {
                 // other resources are exported when above the reserve limit
                 if let Some(&single_input) = self.single_io.input.get(&res_id) {
                     let tick_input = single_input * active;
@@ -364,15 +437,47 @@ impl Production {
     // }
 }
 
+impl ProductionSnapshot {
+    /// Checks that settings of all selected production methods belong to them.
+    pub fn validate(&self, shared_comps: &SharedComponents) -> Result<()> {
+        for selected_method in self.selected_methods.iter() {
+            selected_method.validate(shared_comps)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that all selected production methods, their settings and transport methods are unlocked.
+    pub fn check_unlocked(
+        &self,
+        shared_comps: &SharedComponents,
+        research: &Research,
+    ) -> Result<()> {
+        for selected_method in self.selected_methods.iter() {
+            selected_method.check_unlocked(shared_comps, research)?;
+        }
+        for &transport_id in self.transport.values() {
+            if !research.is_transport_unlocked(transport_id) {
+                bail!(
+                    "Transport method is locked: {}",
+                    shared_comps.config_label(transport_id)?
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
 impl Serializable for ProductionSnapshot {
     type Raw = RawProductionSnapshot;
 
     fn from_serializable(raw: Self::Raw, ctx: ComponentsRef<'_>) -> Result<Self> {
+        let context = || format!("Production \"{}\"", raw.name);
         Ok(ProductionSnapshot {
+            selected_methods: Serializable::from_serializable(raw.selected_methods, ctx)
+                .with_context(context)?,
+            transport: Serializable::from_serializable(raw.transport, ctx).with_context(context)?,
+            storage: Serializable::from_serializable(raw.storage, ctx).with_context(context)?,
             name: raw.name,
-            selected_methods: Serializable::from_serializable(raw.selected_methods, ctx)?,
-            transport: Serializable::from_serializable(raw.transport, ctx)?,
-            storage: Serializable::from_serializable(raw.storage, ctx)?,
             count: raw.count,
             active: raw.active,
             reserve_export_threshold: raw.reserve_export_threshold,
@@ -391,3 +496,25 @@ impl Serializable for ProductionSnapshot {
         })
     }
 }
+
+impl Serializable for ProductionTemplate {
+    type Raw = RawProductionTemplate;
+
+    fn from_serializable(raw: Self::Raw, ctx: ComponentsRef<'_>) -> Result<Self> {
+        let context = || format!("Template \"{}\"", raw.name);
+        Ok(ProductionTemplate {
+            selected_methods: Serializable::from_serializable(raw.selected_methods, ctx)
+                .with_context(context)?,
+            transport: Serializable::from_serializable(raw.transport, ctx).with_context(context)?,
+            name: raw.name,
+        })
+    }
+
+    fn into_serializable(self, ctx: ComponentsRef<'_>) -> Result<Self::Raw> {
+        Ok(RawProductionTemplate {
+            name: self.name,
+            selected_methods: self.selected_methods.into_serializable(ctx)?,
+            transport: self.transport.into_serializable(ctx)?,
+        })
+    }
+}
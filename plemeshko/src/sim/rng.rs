@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// Random number generator of the simulation, saved with it so that replays draw the same numbers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SimRng(u64);
+
+impl SimRng {
+    pub fn from_entropy() -> Self {
+        SimRng(rand::random())
+    }
+
+    /// SplitMix64.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly distributed in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -9,6 +9,7 @@ macro_rules! declare_unit {
             derive_more::Display,
             serde::Serialize,
             serde::Deserialize,
+            schemars::JsonSchema,
             PartialEq,
             PartialOrd,
             Eq,
@@ -0,0 +1,228 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use fluent::FluentResource;
+use fluent_syntax::ast;
+use unic_langid::LanguageIdentifier;
+
+use super::{
+    components::{AppComponents, ComponentLoader, SharedComponents},
+    config::ConfigChecker,
+    text::TextRepository,
+    texture::FatTextureId,
+};
+
+/// Messages of a translation by their ids, with names of variables they use.
+type TranslationMessages = BTreeMap<String, BTreeSet<String>>;
+
+/// Finds problems with texts and textures of loaded components, which otherwise show up only when drawn:
+/// messages missing from translations, unused ones, ones whose arguments differ between translations
+/// and icons which fall back to the placeholder texture.
+pub fn audit_assets(
+    loader: &ComponentLoader,
+    shared_comps: &SharedComponents,
+    app_comps: &AppComponents,
+) -> Result<Vec<String>> {
+    let registry = loader.config_type_registry();
+    let mut checker = ConfigChecker::new(loader.indexer(), shared_comps);
+    for validate in registry.validators() {
+        validate(&mut checker)?;
+    }
+    let texts = std::mem::take(&mut checker.texts);
+    let textures = std::mem::take(&mut checker.textures);
+    let mut problems = checker.into_problems();
+
+    for (from, texture) in textures {
+        if texture == FatTextureId::new_invalid() {
+            problems.push(format!("{from} has no icon"));
+            continue;
+        }
+        let found = app_comps
+            .component(texture.0)
+            .is_ok_and(|component| component.textures.get(texture.1).is_some());
+        if !found {
+            problems.push(format!("{from} refers to a missing texture"));
+        }
+    }
+
+    // messages of configs are prefixed by their tags, others are used by the ui directly
+    let config_prefixes: Vec<_> = registry.tags().map(|tag| format!("{tag}_")).collect();
+    for (component_id, component) in app_comps.iter_components() {
+        let component_label = loader.indexer().label(component_id)?;
+        let expected: HashMap<&str, &str> = texts
+            .iter()
+            .filter(|(_, text)| text.0 == component_id)
+            .map(|(from, text)| (text.1.as_str(), from.as_str()))
+            .collect();
+        // translations of packs override messages of the component's ones
+        let mut translations = BTreeMap::<String, (LanguageIdentifier, TranslationMessages)>::new();
+        for texts_dir in component.texts.directories() {
+            for (langid, path) in TextRepository::available_translations(texts_dir)? {
+                let mut messages = TranslationMessages::new();
+                read_messages(&path, &mut messages, &mut problems)?;
+                translations
+                    .entry(langid.to_string())
+                    .or_insert_with(|| (langid, TranslationMessages::new()))
+                    .1
+                    .extend(messages);
+            }
+        }
+        if translations.is_empty() && !expected.is_empty() {
+            problems.push(format!(
+                "component \"{component_label}\" has no translations"
+            ));
+        }
+        let translations: Vec<(LanguageIdentifier, TranslationMessages)> =
+            translations.into_values().collect();
+
+        for (langid, messages) in &translations {
+            let prefix = format!("component \"{component_label}\", translation {langid}");
+            for (&text, &from) in &expected {
+                if !messages.contains_key(text) {
+                    problems.push(format!("{prefix}: message \"{text}\" of {from} is missing"));
+                }
+            }
+            let mut missing = BTreeMap::<&str, Vec<String>>::new();
+            for (other_langid, other_messages) in &translations {
+                for text in other_messages.keys() {
+                    if !messages.contains_key(text) && !expected.contains_key(text.as_str()) {
+                        missing
+                            .entry(text)
+                            .or_default()
+                            .push(other_langid.to_string());
+                    }
+                }
+            }
+            for (text, present_in) in missing {
+                problems.push(format!(
+                    "{prefix}: message \"{text}\" present in {} is missing",
+                    present_in.join(", ")
+                ));
+            }
+            for text in messages.keys() {
+                let of_config = config_prefixes
+                    .iter()
+                    .any(|config_prefix| text.starts_with(config_prefix));
+                if of_config && !expected.contains_key(text.as_str()) {
+                    problems.push(format!("{prefix}: message \"{text}\" is unused"));
+                }
+            }
+        }
+
+        for (i, (langid, messages)) in translations.iter().enumerate() {
+            for (other_langid, other_messages) in &translations[i + 1..] {
+                for (text, variables) in messages {
+                    match other_messages.get(text) {
+                        Some(other_variables) if other_variables != variables => {
+                            problems.push(format!(
+                                "component \"{component_label}\": message \"{text}\" uses arguments {variables:?} in {langid}, but {other_variables:?} in {other_langid}"
+                            ));
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        }
+    }
+    problems.sort();
+    Ok(problems)
+}
+
+/// Reads ids of messages and variables they use from all files in the directory and its subdirectories.
+fn read_messages(
+    dir: &Path,
+    messages: &mut TranslationMessages,
+    problems: &mut Vec<String>,
+) -> Result<()> {
+    let dir_entries = std::fs::read_dir(dir)
+        .with_context(|| format!("Reading translation directory {}", dir.display()))?;
+    for dir_entry in dir_entries {
+        let entry_path = dir_entry?.path();
+        if entry_path.is_dir() {
+            read_messages(&entry_path, messages, problems)?;
+            continue;
+        }
+        let source = std::fs::read_to_string(&entry_path)?;
+        let resource = match FluentResource::try_new(source) {
+            Ok(resource) => resource,
+            Err((resource, errors)) => {
+                for e in errors {
+                    problems.push(format!("{}: {e}", entry_path.display()));
+                }
+                resource
+            }
+        };
+        for entry in resource.entries() {
+            let ast::Entry::Message(message) = entry else {
+                continue;
+            };
+            let mut variables = BTreeSet::new();
+            let patterns = message
+                .value
+                .iter()
+                .chain(message.attributes.iter().map(|attribute| &attribute.value));
+            for pattern in patterns {
+                collect_pattern_variables(pattern, &mut variables);
+            }
+            if messages
+                .insert(message.id.name.to_owned(), variables)
+                .is_some()
+            {
+                problems.push(format!(
+                    "{}: message \"{}\" is defined more than once",
+                    entry_path.display(),
+                    message.id.name
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn collect_pattern_variables(pattern: &ast::Pattern<&str>, variables: &mut BTreeSet<String>) {
+    for element in &pattern.elements {
+        if let ast::PatternElement::Placeable { expression } = element {
+            collect_expression_variables(expression, variables);
+        }
+    }
+}
+
+fn collect_expression_variables(
+    expression: &ast::Expression<&str>,
+    variables: &mut BTreeSet<String>,
+) {
+    match expression {
+        ast::Expression::Select { selector, variants } => {
+            collect_inline_variables(selector, variables);
+            for variant in variants {
+                collect_pattern_variables(&variant.value, variables);
+            }
+        }
+        ast::Expression::Inline(inline) => collect_inline_variables(inline, variables),
+    }
+}
+
+fn collect_inline_variables(
+    inline: &ast::InlineExpression<&str>,
+    variables: &mut BTreeSet<String>,
+) {
+    match inline {
+        ast::InlineExpression::VariableReference { id } => {
+            variables.insert(id.name.to_owned());
+        }
+        ast::InlineExpression::FunctionReference { arguments, .. } => {
+            let named = arguments.named.iter().map(|argument| &argument.value);
+            for argument in arguments.positional.iter().chain(named) {
+                collect_inline_variables(argument, variables);
+            }
+        }
+        ast::InlineExpression::Placeable { expression } => {
+            collect_expression_variables(expression, variables);
+        }
+        // arguments of terms are local to them
+        _ => (),
+    }
+}
@@ -1,6 +1,6 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, path::PathBuf};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use colored::Colorize;
 use fluent::FluentArgs;
 
@@ -9,11 +9,17 @@ use crate::state::{
     texture::TextureRepository,
 };
 
-use super::ComponentId;
+use super::{ComponentId, ComponentManifest};
 
 pub struct AppComponent {
+    pub(super) id: ComponentId,
+    /// Directory the component was loaded from.
+    pub dir: PathBuf,
+    pub manifest: ComponentManifest,
     pub texts: TextRepository,
     pub textures: TextureRepository,
+    /// Hash of the component's config files, which saves record to detect changed components.
+    pub configs_hash: String,
 }
 
 #[derive(Default)]
@@ -42,8 +48,9 @@ impl AppComponent {
 
 impl AppComponents {
     pub fn component(&self, id: ComponentId) -> Result<&AppComponent> {
-        self.0
-            .get(id.0 as usize)
+        let component = self
+            .0
+            .get(id.to_index())
             .ok_or_else(|| anyhow!("Component id out of range: {}", id.0))?
             .as_ref()
             .ok_or_else(|| {
@@ -51,7 +58,15 @@ impl AppComponents {
                     "Component hasn't finished loading or was unloaded: {}",
                     id.0
                 )
-            })
+            })?;
+        if component.id != id {
+            bail!("Component was unloaded, its slot is reused: {}", id.0);
+        }
+        Ok(component)
+    }
+
+    pub fn iter_components(&self) -> impl Iterator<Item = (ComponentId, &AppComponent)> {
+        self.0.iter().filter_map(|c| c.as_ref().map(|c| (c.id, c)))
     }
 
     pub fn iter_components_mut(
@@ -59,7 +74,6 @@ impl AppComponents {
     ) -> impl Iterator<Item = (ComponentId, &mut AppComponent)> {
         self.0
             .iter_mut()
-            .enumerate()
-            .filter_map(|(id, c)| c.as_mut().map(|c| (ComponentId(id as u16), c)))
+            .filter_map(|c| c.as_mut().map(|c| (c.id, c)))
     }
 }
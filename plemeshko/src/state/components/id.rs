@@ -1,5 +1,8 @@
+use std::fmt::{Display, Formatter};
+
 use anyhow::{anyhow, Result};
 use bytemuck::TransparentWrapper;
+use schemars::{gen::SchemaGenerator, schema::Schema, JsonSchema};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::params::LABEL_SEPARATOR;
@@ -14,6 +17,12 @@ pub struct ComponentLabel(pub(super) String);
 // Has `#[repr(transparent)]` and only 1 field.
 unsafe impl TransparentWrapper<String> for ComponentLabel {}
 
+impl Display for ComponentLabel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
 #[derive(Hash, PartialEq, Eq, Debug, Clone)]
 pub struct RawFatLabel(
     /// Component prefix of the label, `None` when the label is local (allowed only inside components).
@@ -22,10 +31,12 @@ pub struct RawFatLabel(
 );
 
 pub(super) type RawComponentId = u16;
+pub(super) type ComponentGeneration = u16;
 
+/// Slot of the component and the slot's generation, which changes when the slot is reused,
+/// so ids of unloaded components don't refer to components loaded in their place.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[repr(transparent)]
-pub struct ComponentId(pub(super) RawComponentId);
+pub struct ComponentId(pub(super) RawComponentId, pub(super) ComponentGeneration);
 
 #[derive(Clone, Copy)]
 pub struct ComponentSlotId(pub(super) usize);
@@ -44,7 +55,7 @@ impl RawFatLabel {
 
 impl ComponentId {
     pub const fn core() -> Self {
-        ComponentId(0)
+        ComponentId(0, 0)
     }
 
     pub(super) fn to_index(self) -> usize {
@@ -52,12 +63,6 @@ impl ComponentId {
     }
 }
 
-impl ComponentSlotId {
-    pub fn assume_occupied(self) -> ComponentId {
-        ComponentId(self.0.try_into().unwrap())
-    }
-}
-
 /// Separates component label prefix from object label.
 /// Component label is `None` when there is no label, meaning "local" component.
 fn split_label<'de, D: Deserializer<'de>>(
@@ -95,6 +100,20 @@ impl<'de> Deserialize<'de> for RawFatLabel {
     }
 }
 
+impl JsonSchema for RawFatLabel {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "FatLabel".to_owned()
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        String::json_schema(gen)
+    }
+}
+
 impl Serialize for RawFatLabel {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
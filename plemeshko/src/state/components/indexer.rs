@@ -1,23 +1,98 @@
-use anyhow::Result;
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Result};
 use bytemuck::TransparentWrapper;
 
-use crate::state::raw_indexer::RawIndexer;
+use crate::state::config::with_suggestion;
+
+use super::{ComponentGeneration, ComponentId, ComponentLabel, ComponentSlotId, RawComponentId};
 
-use super::{ComponentId, ComponentLabel, ComponentSlotId, RawComponentId};
+struct ComponentSlot {
+    /// `None` when the slot was freed by unloading its component.
+    label: Option<String>,
+    generation: ComponentGeneration,
+}
 
 #[derive(Default)]
-pub struct ComponentIndexer(pub(super) RawIndexer<String, RawComponentId>);
+pub struct ComponentIndexer {
+    label_to_id: HashMap<String, ComponentId>,
+    slots: Vec<ComponentSlot>,
+}
 
 impl ComponentIndexer {
     pub fn id(&self, label: &ComponentLabel) -> Result<ComponentId> {
-        self.0.id(&label.0).map(ComponentId)
+        match self.label_to_id.get(&label.0) {
+            Some(&id) => Ok(id),
+            None => Err(anyhow!(
+                "{}",
+                with_suggestion(
+                    format!("Label not registered: {}", label.0),
+                    &label.0,
+                    self.label_to_id.keys().map(String::as_str)
+                )
+            )),
+        }
     }
 
     pub fn label(&self, id: ComponentId) -> Result<&ComponentLabel> {
-        self.0.label(id.0).map(ComponentLabel::wrap_ref)
+        let slot = self
+            .slots
+            .get(id.to_index())
+            .ok_or_else(|| anyhow!("Label corresponding to the requested index wasn't found"))?;
+        match &slot.label {
+            Some(label) if slot.generation == id.1 => Ok(ComponentLabel::wrap_ref(label)),
+            _ => Err(anyhow!("Component was unloaded: {}", id.0)),
+        }
     }
 
     pub fn indices(&self) -> impl Iterator<Item = ComponentSlotId> {
-        (0..self.0.id_to_label.len()).map(ComponentSlotId)
+        (0..self.slots.len()).map(ComponentSlotId)
+    }
+
+    /// Assigns an id to the component, reusing the first free slot with the next generation.
+    pub(super) fn create_id(&mut self, label: String) -> Result<ComponentId> {
+        if self.label_to_id.contains_key(&label) {
+            bail!("Label already registered: {label}");
+        }
+        let id = match self.slots.iter().position(|slot| slot.label.is_none()) {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.generation = slot
+                    .generation
+                    .checked_add(1)
+                    .ok_or_else(|| anyhow!("Component slot was reused too many times: {index}"))?;
+                slot.label = Some(label.clone());
+                ComponentId(index.try_into().unwrap(), slot.generation)
+            }
+            None => {
+                let index: RawComponentId = self.slots.len().try_into()?;
+                self.slots.push(ComponentSlot {
+                    label: Some(label.clone()),
+                    generation: 0,
+                });
+                ComponentId(index, 0)
+            }
+        };
+        self.label_to_id.insert(label, id);
+        Ok(id)
+    }
+
+    /// Frees the component's slot, keeping its generation.
+    pub(super) fn remove(&mut self, id: ComponentId) -> Result<String> {
+        self.label(id)?;
+        let label = self.slots[id.to_index()].label.take().unwrap();
+        self.label_to_id.remove(&label);
+        Ok(label)
+    }
+
+    /// Occupies the slot freed by `remove` again, with the same id.
+    pub(super) fn restore(&mut self, id: ComponentId, label: String) -> Result<()> {
+        let slot = &mut self.slots[id.to_index()];
+        if slot.label.is_some() || slot.generation != id.1 {
+            bail!("Component slot was reused: {}", id.0);
+        }
+        slot.label = Some(label.clone());
+        self.label_to_id.insert(label, id);
+        Ok(())
     }
 }
@@ -1,23 +1,58 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
+use sha2::{Digest, Sha256};
 use unic_langid::langid;
 
 use crate::{
-    params::{COMPONENT_CONFIGS_DIR, COMPONENT_TEXTS_DIR, COMPONENT_TEXTURES_DIR},
+    params::{COMPONENT_CONFIGS_DIR, COMPONENT_TEXTS_DIR, COMPONENT_TEXTURES_DIR, LABEL_SEPARATOR},
+    sim::{Sim, SimSnapshot},
     state::{
         components::{app::AppComponent, shared::SharedComponent, ComponentId},
-        config::{ComponentPreConfigsRef, ConfigRepositoryBuilder, ConfigTypeRegistry},
+        config::{
+            ComponentPreConfigsRef, ConfigChecker, ConfigPatch, ConfigPatchAction, ConfigPatches,
+            ConfigRepositoryBuilder, ConfigTypeRegistry, RawConfig, RawConfigs,
+        },
+        serializable::Serializable,
         text::TextRepository,
         texture::TextureRepository,
     },
+    util::parallel_map,
 };
 
-use super::{app::AppComponents, shared::SharedComponents, ComponentIndexer, ComponentsRef};
+use super::{
+    app::AppComponents, order_components, shared::SharedComponents, ComponentIndexer,
+    ComponentLabel, ComponentManifest, ComponentsRef, DiscoveredComponent,
+};
 
 pub struct ComponentLoader {
     indexer: ComponentIndexer,
     config_type_registry: ConfigTypeRegistry,
+    /// Patches of configs of components not loaded yet, by target component label.
+    patches: HashMap<String, ConfigPatches>,
+    /// Labels of components whose patches were collected.
+    patch_sources: HashSet<String>,
+}
+
+/// Files of a component read from its directory, which don't depend on other components.
+/// They are read for all loaded components at once, while building them is sequential.
+pub struct ComponentFiles {
+    pub texts: TextRepository,
+    pub textures: TextureRepository,
+    pub configs: RawConfigs,
+    /// See [`AppComponent::configs_hash`].
+    pub configs_hash: String,
+}
+
+/// Progress of loading components, shared with the thread displaying it.
+#[derive(Default)]
+pub struct LoadingProgress {
+    done: AtomicUsize,
+    total: AtomicUsize,
 }
 
 #[must_use]
@@ -39,6 +74,67 @@ impl Drop for ComponentsChangedToken {
     }
 }
 
+impl ComponentFiles {
+    /// Reads texts, textures and raw configs from the component's directory subdirectories.
+    /// Texts of packs are loaded by their targets, see [`ComponentManifest::pack_for`].
+    pub fn read(dir: &Path, manifest: &ComponentManifest) -> Result<Self> {
+        let texts_dir = dir.join(COMPONENT_TEXTS_DIR);
+        let texts = if manifest.pack_for.is_none()
+            && std::fs::try_exists(&texts_dir)
+                .context("Checking existence of component's texts directory.")?
+        {
+            TextRepository::from_directory(&texts_dir, langid!("en"))?
+        } else {
+            TextRepository::new()
+        };
+
+        let textures_dir = dir.join(COMPONENT_TEXTURES_DIR);
+        let textures = if std::fs::try_exists(&textures_dir)
+            .context("Checking existence of component's textures directory.")?
+        {
+            TextureRepository::from_directory(&textures_dir)?
+        } else {
+            TextureRepository::new()
+        };
+
+        let configs_dir = dir.join(COMPONENT_CONFIGS_DIR);
+        let mut hasher = Sha256::new();
+        let configs = if std::fs::try_exists(&configs_dir)
+            .context("Checking existence of component's configs directory.")?
+        {
+            hash_directory(&mut hasher, &configs_dir, &configs_dir)?;
+            RawConfigs::read_directory(&configs_dir)?
+        } else {
+            RawConfigs::default()
+        };
+        Ok(ComponentFiles {
+            texts,
+            textures,
+            configs,
+            configs_hash: format!("{:x}", hasher.finalize()),
+        })
+    }
+}
+
+impl LoadingProgress {
+    pub fn add_steps(&self, steps: usize) {
+        self.total.fetch_add(steps, Ordering::Relaxed);
+    }
+
+    pub fn step(&self) {
+        self.done.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Part of steps done, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        let total = self.total.load(Ordering::Relaxed);
+        if total == 0 {
+            return 0.0;
+        }
+        self.done.load(Ordering::Relaxed).min(total) as f32 / total as f32
+    }
+}
+
 impl ComponentLoader {
     pub fn new() -> Result<Self> {
         let config_type_registry = crate::sim::config::register()?;
@@ -46,6 +142,8 @@ impl ComponentLoader {
         Ok(ComponentLoader {
             indexer,
             config_type_registry,
+            patches: HashMap::new(),
+            patch_sources: HashSet::new(),
         })
     }
 
@@ -63,63 +161,315 @@ impl ComponentLoader {
         shared_comps: &mut SharedComponents,
         app_comps: &mut AppComponents,
         label: String,
-        mut dir: PathBuf,
+        dir: PathBuf,
     ) -> Result<ComponentsChangedToken> {
-        // todo: reclaim unloaded component slots (or not? misusing unloaded's ids with replacement's ones may be confusing)
-        let component_id = ComponentId(self.indexer.0.create_id(label)?);
+        let manifest = ComponentManifest::read(&label, &dir)?;
+        self.load_discovered(
+            shared_comps,
+            app_comps,
+            vec![DiscoveredComponent {
+                label,
+                dir,
+                manifest,
+            }],
+            &LoadingProgress::default(),
+        )
+    }
 
-        dir.push(COMPONENT_TEXTS_DIR);
-        let texts = if std::fs::try_exists(&dir)
-            .context("Checking existence of component's texts directory.")?
-        {
-            TextRepository::from_directory(&dir, langid!("en"))?
-        } else {
-            TextRepository::new()
+    /// Takes patches of other components' configs out of the component's raw configs.
+    /// Patches must be collected before their target components are loaded,
+    /// so files of all loaded components are read before building any of them.
+    /// Patches of a component are recorded once, as they stay until their targets are reloaded.
+    fn collect_patches(&mut self, label: &str, raw_cfgs: &mut RawConfigs) -> Result<()> {
+        let record = self.patch_sources.insert(label.to_owned());
+        let mut configs = Vec::with_capacity(raw_cfgs.configs.len());
+        for mut raw in std::mem::take(&mut raw_cfgs.configs) {
+            let action = match raw.take_patch() {
+                Ok(Some(action)) => action,
+                Ok(None) => {
+                    configs.push(raw);
+                    continue;
+                }
+                Err(e) => {
+                    raw_cfgs.errors.push(e);
+                    continue;
+                }
+            };
+            if record {
+                self.collect_patch(label, raw, action)
+                    .with_context(|| format!("Collecting patches of component \"{label}\""))?;
+            }
+        }
+        raw_cfgs.configs = configs;
+        Ok(())
+    }
+
+    /// Records the patch of other component's config, which is applied once the component is loaded.
+    fn collect_patch(
+        &mut self,
+        label: &str,
+        raw: RawConfig,
+        action: ConfigPatchAction,
+    ) -> Result<()> {
+        let Some((target, target_label)) = raw.label.split_once(LABEL_SEPARATOR) else {
+            bail!(
+                "Patch label must contain the target component: {}",
+                raw.label
+            );
         };
-        assert!(dir.pop());
+        if target == label {
+            bail!(
+                "Component \"{label}\" patches its own config {} \"{target_label}\"",
+                raw.tag
+            );
+        }
+        if self.indexer.id(&ComponentLabel(target.to_owned())).is_ok() {
+            bail!(
+                "Component \"{target}\" is already loaded, so \"{label}\" can't patch its config {} \"{target_label}\"",
+                raw.tag
+            );
+        }
+        self.patches.entry(target.to_owned()).or_default().push(
+            raw.tag,
+            target_label.to_owned(),
+            ConfigPatch {
+                source: label.to_owned(),
+                action,
+            },
+        );
+        Ok(())
+    }
 
-        dir.push(COMPONENT_TEXTURES_DIR);
-        let textures = if std::fs::try_exists(&dir)
-            .context("Checking existence of component's textures directory.")?
-        {
-            TextureRepository::from_directory(&dir)?
-        } else {
-            TextureRepository::new()
+    /// Adds texts and textures of the pack to its target, which must be loaded along with it.
+    fn merge_pack(
+        &self,
+        components: &mut [(DiscoveredComponent, ComponentFiles)],
+        pack: usize,
+        target_label: &str,
+    ) -> Result<()> {
+        let (pack_component, pack_files) = &mut components[pack];
+        let label = pack_component.label.clone();
+        if !pack_files.configs.configs.is_empty() {
+            bail!("Component \"{label}\" is a pack for \"{target_label}\", so it can't have configs of its own");
+        }
+        let texts_dir = pack_component.dir.join(COMPONENT_TEXTS_DIR);
+        let has_texts = std::fs::try_exists(&texts_dir)
+            .context("Checking existence of component's texts directory.")?;
+        let textures = std::mem::take(&mut pack_files.textures);
+
+        let Some(target) = components
+            .iter()
+            .position(|(component, _)| component.label == target_label)
+        else {
+            if self
+                .indexer
+                .id(&ComponentLabel(target_label.to_owned()))
+                .is_ok()
+            {
+                bail!("Component \"{target_label}\" is already loaded, so pack \"{label}\" can't add to its texts and textures");
+            }
+            bail!("Component \"{label}\" is a pack for \"{target_label}\", which isn't available");
         };
-        assert!(dir.pop());
+        let (target_component, target_files) = &mut components[target];
+        if target_component.manifest.pack_for.is_some() {
+            bail!("Component \"{label}\" is a pack for \"{target_label}\", which is a pack itself");
+        }
+        if has_texts {
+            target_files
+                .texts
+                .add_directory(&texts_dir, langid!("en"))?;
+        }
+        target_files.textures.extend(textures)
+    }
+
+    /// Manifests of loaded components by their labels.
+    fn loaded_manifests<'a>(
+        &self,
+        app_comps: &'a AppComponents,
+    ) -> Result<HashMap<String, &'a ComponentManifest>> {
+        app_comps
+            .iter_components()
+            .map(|(id, comp)| Ok((self.indexer.label(id)?.0.clone(), &comp.manifest)))
+            .try_collect()
+    }
+
+    fn load_with_manifest(
+        &mut self,
+        shared_comps: &mut SharedComponents,
+        app_comps: &mut AppComponents,
+        label: String,
+        dir: PathBuf,
+        manifest: ComponentManifest,
+        files: ComponentFiles,
+    ) -> Result<ComponentsChangedToken> {
+        for dependency_label in manifest.depends_on.keys() {
+            let dependency = match self.indexer.id(&ComponentLabel(dependency_label.clone())) {
+                Ok(id) => Some(&app_comps.component(id)?.manifest),
+                Err(_) => None,
+            };
+            manifest.check_dependency(&label, dependency_label, dependency)?;
+        }
+        let patches = self.patches.remove(&label).unwrap_or_default();
+
+        let component_id = self.indexer.create_id(label)?;
+        let component = self.build_component(
+            shared_comps,
+            app_comps,
+            component_id,
+            dir,
+            manifest,
+            files,
+            patches,
+        );
+        let (shared_comp, app_comp) = match component {
+            Ok(component) => component,
+            Err(e) => {
+                // free the slot, so the component can be loaded again
+                self.indexer.remove(component_id)?;
+                if app_comps.0.len() == component_id.to_index() {
+                    app_comps.0.push(None);
+                    shared_comps.0.push(None);
+                }
+                return Err(e);
+            }
+        };
+        let component_index = component_id.to_index();
+        if app_comps.0.len() == component_index {
+            app_comps.0.push(Some(app_comp));
+            shared_comps.0.push(Some(shared_comp));
+        } else {
+            assert!(app_comps.0.len() > component_index);
+            app_comps.0[component_index] = Some(app_comp);
+            shared_comps.0[component_index] = Some(shared_comp);
+        }
+        Ok(ComponentsChangedToken(()))
+    }
 
-        dir.push(COMPONENT_CONFIGS_DIR);
+    fn build_component(
+        &self,
+        shared_comps: &SharedComponents,
+        app_comps: &AppComponents,
+        component_id: ComponentId,
+        dir: PathBuf,
+        manifest: ComponentManifest,
+        files: ComponentFiles,
+        patches: ConfigPatches,
+    ) -> Result<(SharedComponent, AppComponent)> {
+        let ComponentFiles {
+            texts,
+            textures,
+            configs,
+            configs_hash,
+        } = files;
         let configs = {
             let mut builder = ConfigRepositoryBuilder::new(&self.config_type_registry)?;
-            if std::fs::try_exists(&dir)
-                .context("Checking existence of component's configs directory.")?
-            {
-                builder.load_directory(&self.config_type_registry, &dir)?;
-            }
+            builder.set_patches(patches);
+            builder.load_raw_configs(&self.config_type_registry, configs);
             builder.build(
                 &self.config_type_registry,
                 ComponentsRef {
                     indexer: &self.indexer,
                     app: app_comps,
                     shared: shared_comps,
+                    dropped: None,
                 },
                 ComponentPreConfigsRef::new(component_id, &textures),
             )?
         };
-        assert!(dir.pop());
 
-        let shared_comp = SharedComponent { configs };
-        let app_comp = AppComponent { texts, textures };
-        let component_index = component_id.0 as usize;
-        if app_comps.0.len() == component_index {
-            app_comps.0.push(Some(app_comp));
-            shared_comps.0.push(Some(shared_comp));
-        } else {
-            assert!(app_comps.0.len() > component_index);
-            app_comps.0[component_index] = Some(app_comp);
-            shared_comps.0[component_index] = Some(shared_comp);
+        let shared_comp = SharedComponent {
+            id: component_id,
+            configs,
+        };
+        let app_comp = AppComponent {
+            id: component_id,
+            dir,
+            manifest,
+            texts,
+            textures,
+            configs_hash,
+        };
+        Ok((shared_comp, app_comp))
+    }
+
+    /// Unloads the component, freeing its slot for reuse.
+    /// The game is remapped to the remaining components the same way it would be saved and loaded,
+    /// so when it or configs of other components still refer to the component's configs, nothing is unloaded.
+    /// Patches the component applied to other components' configs stay until those are reloaded,
+    /// as do texts and textures it added as a pack.
+    /// Components are finalized afterwards.
+    pub fn unload(
+        &mut self,
+        shared_comps: &mut SharedComponents,
+        app_comps: &mut AppComponents,
+        sim: Option<&mut Sim>,
+        id: ComponentId,
+    ) -> Result<()> {
+        if id == ComponentId::core() {
+            bail!("Core component can't be unloaded");
+        }
+        let label = self.indexer.label(id)?.0.clone();
+        let mut dependents = Vec::new();
+        for (dependent_id, dependent) in app_comps.iter_components() {
+            if dependent.manifest.depends_on.contains_key(&label) {
+                dependents.push(format!("\"{}\"", self.indexer.label(dependent_id)?.0));
+            }
+        }
+        if !dependents.is_empty() {
+            bail!(
+                "Component \"{label}\" can't be unloaded, {} depend on it",
+                dependents.join(", ")
+            );
+        }
+        let raw_sim = match &sim {
+            Some(sim) => Some(sim.snapshot().into_serializable(ComponentsRef {
+                indexer: &self.indexer,
+                app: app_comps,
+                shared: shared_comps,
+                dropped: None,
+            })?),
+            None => None,
+        };
+
+        let index = id.to_index();
+        let shared_comp = shared_comps.0[index].take();
+        let app_comp = app_comps.0[index].take();
+        self.indexer.remove(id)?;
+
+        // other components' configs may refer to the unloaded ones, failing finalization
+        let remapped = self
+            .finalize(ComponentsChangedToken::new(), shared_comps)
+            .and_then(|()| match raw_sim {
+                Some(raw_sim) => SimSnapshot::from_serializable(
+                    raw_sim,
+                    ComponentsRef {
+                        indexer: &self.indexer,
+                        app: app_comps,
+                        shared: shared_comps,
+                        dropped: None,
+                    },
+                )
+                .and_then(|snapshot| Sim::restore(shared_comps, snapshot))
+                .map(Some),
+                None => Ok(None),
+            });
+        match remapped {
+            Ok(remapped) => {
+                if let (Some(sim), Some(remapped)) = (sim, remapped) {
+                    *sim = remapped;
+                }
+                Ok(())
+            }
+            Err(e) => {
+                self.indexer.restore(id, label.clone())?;
+                shared_comps.0[index] = shared_comp;
+                app_comps.0[index] = app_comp;
+                self.finalize(ComponentsChangedToken::new(), shared_comps)?;
+                Err(e.context(format!(
+                    "Component \"{label}\" can't be unloaded, other configs or the game refer to its configs"
+                )))
+            }
         }
-        Ok(ComponentsChangedToken(()))
     }
 
     /// Loads specified directory subdirectories as components.
@@ -129,7 +479,94 @@ impl ComponentLoader {
         app_comps: &mut AppComponents,
         top_dir: &Path,
     ) -> Result<ComponentsChangedToken> {
+        let discovered = Self::discover(top_dir)?;
+        self.load_discovered(
+            shared_comps,
+            app_comps,
+            discovered,
+            &LoadingProgress::default(),
+        )
+    }
+
+    /// Loads discovered components after their dependencies, as declared in their manifests.
+    /// Files of all components are read in parallel, then components are built one by one in order,
+    /// so ids they get don't depend on which are read first.
+    pub fn load_discovered(
+        &mut self,
+        shared_comps: &mut SharedComponents,
+        app_comps: &mut AppComponents,
+        discovered: Vec<DiscoveredComponent>,
+        progress: &LoadingProgress,
+    ) -> Result<ComponentsChangedToken> {
+        let discovered = order_components(discovered, &self.loaded_manifests(app_comps)?)?;
+        // reading and building each component
+        progress.add_steps(discovered.len() * 2);
+        let read = parallel_map(discovered, |component| {
+            let files = ComponentFiles::read(&component.dir, &component.manifest)
+                .with_context(|| format!("Reading component \"{}\"", component.label));
+            progress.step();
+            (component, files)
+        });
+        let mut components = Vec::with_capacity(read.len());
+        for (component, files) in read {
+            let mut files = files?;
+            self.collect_patches(&component.label, &mut files.configs)?;
+            components.push((component, files));
+        }
+        // packs are merged before their targets are built, as configs refer to textures
+        for pack in 0..components.len() {
+            let Some(target_label) = components[pack].0.manifest.pack_for.clone() else {
+                continue;
+            };
+            let label = components[pack].0.label.clone();
+            self.merge_pack(&mut components, pack, &target_label)
+                .with_context(|| format!("Loading component \"{label}\""))?;
+        }
+
         let changed_token = ComponentsChangedToken::new();
+        for (component, files) in components {
+            let loaded = self
+                .load_with_manifest(
+                    shared_comps,
+                    app_comps,
+                    component.label.clone(),
+                    component.dir,
+                    component.manifest,
+                    files,
+                )
+                .with_context(|| format!("Loading component \"{}\"", component.label));
+            match loaded {
+                Ok(loaded) => changed_token.consume(loaded),
+                Err(e) => {
+                    // components loaded so far stay, but aren't finalized
+                    std::mem::forget(changed_token);
+                    return Err(e);
+                }
+            }
+            progress.step();
+        }
+        self.warn_unapplied_patches();
+        Ok(changed_token)
+    }
+
+    /// Reports patches whose target components aren't loaded.
+    /// They are kept, in case their targets are loaded later.
+    fn warn_unapplied_patches(&self) {
+        let mut targets: Vec<_> = self.patches.iter().collect();
+        targets.sort_unstable_by_key(|(target, _)| target.as_str());
+        for (target, patches) in targets {
+            for source in patches.sources() {
+                log::warn!(
+                    "Component \"{source}\" patches component \"{target}\" which isn't loaded"
+                );
+            }
+        }
+    }
+
+    /// Finds components in the specified directory subdirectories and reads their manifests.
+    /// Components are ordered by label, to not depend on the filesystem order.
+    pub fn discover(top_dir: &Path) -> Result<Vec<DiscoveredComponent>> {
+        let mut discovered = Vec::new();
         for top_entry in std::fs::read_dir(top_dir)? {
             let top_entry_path = top_entry?.path();
             if top_entry_path.is_dir() {
@@ -143,15 +580,16 @@ impl ComponentLoader {
                     })?
                     .to_string_lossy()
                     .into_owned();
-                changed_token.consume(self.load_single(
-                    shared_comps,
-                    app_comps,
+                let manifest = ComponentManifest::read(&label, &top_entry_path)?;
+                discovered.push(DiscoveredComponent {
                     label,
-                    top_entry_path,
-                )?);
+                    dir: top_entry_path,
+                    manifest,
+                });
             }
         }
-        Ok(changed_token)
+        discovered.sort_by(|a, b| a.label.cmp(&b.label));
+        Ok(discovered)
     }
 
     /// Finalizes initialization of some configs.
@@ -165,6 +603,185 @@ impl ComponentLoader {
         for f in self.config_type_registry.finalizers() {
             f(&self.indexer, shared_comps)?;
         }
+        let mut checker = ConfigChecker::new(&self.indexer, shared_comps);
+        for validate in self.config_type_registry.validators() {
+            validate(&mut checker)?;
+        }
+        let problems = checker.into_problems();
+        if !problems.is_empty() {
+            bail!("Configs are inconsistent:\n{}", problems.join("\n"));
+        }
         Ok(())
     }
 }
+
+/// Hashes paths of files in the directory and its subdirectories relative to `root`, along with their contents.
+/// Entries are sorted, so the hash doesn't depend on the filesystem order.
+fn hash_directory(hasher: &mut Sha256, root: &Path, dir: &Path) -> Result<()> {
+    let mut entry_paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .try_collect()?;
+    entry_paths.sort();
+    for entry_path in entry_paths {
+        if entry_path.is_dir() {
+            hash_directory(hasher, root, &entry_path)?;
+        } else if entry_path.is_file() {
+            let content = std::fs::read(&entry_path)?;
+            // separators differ between platforms
+            let relative_path: Vec<_> = entry_path
+                .strip_prefix(root)?
+                .iter()
+                .map(|part| part.to_string_lossy())
+                .collect();
+            let relative_path = relative_path.join("/");
+            hasher.update((relative_path.len() as u64).to_le_bytes());
+            hasher.update(relative_path.as_bytes());
+            hasher.update((content.len() as u64).to_le_bytes());
+            hasher.update(content);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        sim::config::setting_group::SettingGroup,
+        state::{config::FatConfigId, load_test_core, test_dir, LoadedComponents},
+    };
+
+    use super::*;
+
+    /// Writes the component with the configs and the manifest, when given, to the directory.
+    fn write_component(dir: &Path, label: &str, manifest: Option<&str>, configs: &str) -> PathBuf {
+        let component_dir = dir.join(label);
+        std::fs::create_dir_all(component_dir.join(COMPONENT_CONFIGS_DIR)).unwrap();
+        std::fs::write(
+            component_dir
+                .join(COMPONENT_CONFIGS_DIR)
+                .join("configs.json"),
+            configs,
+        )
+        .unwrap();
+        if let Some(manifest) = manifest {
+            std::fs::write(
+                component_dir.join(crate::params::COMPONENT_MANIFEST_FILE),
+                manifest,
+            )
+            .unwrap();
+        }
+        component_dir
+    }
+
+    fn load(components: &mut LoadedComponents, dir: &Path, label: &str) -> Result<ComponentId> {
+        let changed = components.loader.load_single(
+            &mut components.shared,
+            &mut components.app,
+            label.to_owned(),
+            dir.join(label),
+        )?;
+        components
+            .loader
+            .finalize(changed, &mut components.shared)?;
+        components
+            .loader
+            .indexer()
+            .id(&ComponentLabel(label.to_owned()))
+    }
+
+    const GROUP: &str = r#"[
+        {"tag": "setting-group", "label": "group"},
+        {"tag": "setting", "label": "setting", "payload": {"group": "group"}}
+    ]"#;
+
+    #[test]
+    fn ids_of_unloaded_components_are_invalid_after_slot_reuse() {
+        let dir = test_dir("component-generations");
+        write_component(&dir, "old", None, GROUP);
+        write_component(&dir, "new", None, GROUP);
+        let mut components = load_test_core();
+        let old_id = load(&mut components, &dir, "old").unwrap();
+        let old_group: FatConfigId<SettingGroup> = components
+            .shared
+            .component(old_id)
+            .unwrap()
+            .configs
+            .id_from_raw("group")
+            .unwrap()
+            .in_component(old_id);
+        components
+            .loader
+            .unload(&mut components.shared, &mut components.app, None, old_id)
+            .unwrap();
+
+        let new_id = load(&mut components, &dir, "new").unwrap();
+        assert_eq!(new_id.to_index(), old_id.to_index());
+        assert_ne!(new_id, old_id);
+        assert!(components.shared.component(old_id).is_err());
+        assert!(components.loader.indexer().label(old_id).is_err());
+        assert!(components.shared.config(old_group).is_err());
+        assert!(components.loader.indexer().label(old_group.0).is_err());
+        assert!(components
+            .shared
+            .config(FatConfigId(new_id, old_group.1))
+            .is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn components_others_depend_on_are_not_unloaded() {
+        let dir = test_dir("component-dependents");
+        write_component(&dir, "base", None, "[]");
+        write_component(
+            &dir,
+            "dependent",
+            Some(r#"{"name": "dependent", "version": "1.0.0", "depends_on": {"base": "*"}}"#),
+            "[]",
+        );
+        let mut components = load_test_core();
+        let base_id = load(&mut components, &dir, "base").unwrap();
+        load(&mut components, &dir, "dependent").unwrap();
+        let error = components
+            .loader
+            .unload(&mut components.shared, &mut components.app, None, base_id)
+            .unwrap_err();
+        assert!(error.to_string().contains("\"dependent\" depend on it"));
+        assert!(components.shared.component(base_id).is_ok());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unloading_is_rolled_back_when_finalizing_fails() {
+        let dir = test_dir("component-rollback");
+        write_component(&dir, "base", None, GROUP);
+        // refers to the config without declaring the dependency
+        write_component(
+            &dir,
+            "user",
+            None,
+            r#"[{"tag": "setting", "label": "setting", "payload": {"group": "base/group"}}]"#,
+        );
+        let mut components = load_test_core();
+        let base_id = load(&mut components, &dir, "base").unwrap();
+        load(&mut components, &dir, "user").unwrap();
+        let error = components
+            .loader
+            .unload(&mut components.shared, &mut components.app, None, base_id)
+            .unwrap_err();
+        assert!(error.to_string().contains("refer to its configs"));
+        assert!(components.shared.component(base_id).is_ok());
+        assert_eq!(
+            components.loader.indexer().label(base_id).unwrap().0,
+            "base"
+        );
+        assert_eq!(
+            components
+                .loader
+                .indexer()
+                .id(&ComponentLabel("base".to_owned()))
+                .unwrap(),
+            base_id
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
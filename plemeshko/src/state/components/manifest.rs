@@ -0,0 +1,272 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use crate::params::COMPONENT_MANIFEST_FILE;
+
+/// Metadata of a component, read from the manifest file in its directory.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ComponentManifest {
+    pub name: String,
+    pub version: semver::Version,
+    #[serde(default)]
+    pub authors: Vec<String>,
+    /// Components which must be loaded before this one, with required versions.
+    #[serde(default)]
+    pub depends_on: BTreeMap<String, semver::VersionReq>,
+    /// Components which must be loaded before this one if they are present.
+    #[serde(default)]
+    pub load_after: Vec<String>,
+    /// Component which texts and textures of this one are added to, replacing ones with the same labels,
+    /// like translation and texture packs. Packs have no configs of their own, only patches.
+    #[serde(default)]
+    pub pack_for: Option<String>,
+}
+
+impl ComponentManifest {
+    /// Manifest used for components without a manifest file.
+    pub fn fallback(label: &str) -> Self {
+        ComponentManifest {
+            name: label.to_owned(),
+            version: semver::Version::new(0, 0, 0),
+            authors: Vec::new(),
+            depends_on: BTreeMap::new(),
+            load_after: Vec::new(),
+            pack_for: None,
+        }
+    }
+
+    /// Reads manifest from the component's directory, falling back to the default one when it is absent.
+    pub fn read(label: &str, dir: &Path) -> Result<Self> {
+        let path = dir.join(COMPONENT_MANIFEST_FILE);
+        if !std::fs::try_exists(&path).context("Checking existence of component's manifest.")? {
+            return Ok(Self::fallback(label));
+        }
+        let file = std::fs::File::open(&path)?;
+        let reader = std::io::BufReader::new(file);
+        serde_json::de::from_reader(reader)
+            .with_context(|| format!("Reading component manifest: {}", path.display()))
+    }
+
+    /// Checks that the dependency is compatible, `None` meaning it isn't loaded.
+    pub fn check_dependency(
+        &self,
+        label: &str,
+        dependency_label: &str,
+        dependency: Option<&ComponentManifest>,
+    ) -> Result<()> {
+        let Some(requirement) = self.depends_on.get(dependency_label) else {
+            return Ok(());
+        };
+        let Some(dependency) = dependency else {
+            bail!("Component \"{label}\" depends on \"{dependency_label}\" {requirement}, which isn't available");
+        };
+        if !requirement.matches(&dependency.version) {
+            bail!(
+                "Component \"{label}\" depends on \"{dependency_label}\" {requirement}, but version {} is available",
+                dependency.version
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Component found in the mods directory, not loaded yet.
+#[derive(Clone, Debug)]
+pub struct DiscoveredComponent {
+    pub label: String,
+    pub dir: PathBuf,
+    pub manifest: ComponentManifest,
+}
+
+/// Orders components so each one is preceded by its dependencies and `load_after` components.
+/// Components otherwise keep their relative order.
+/// `loaded` are manifests of components already loaded, which dependencies may refer to.
+pub fn order_components(
+    discovered: Vec<DiscoveredComponent>,
+    loaded: &HashMap<String, &ComponentManifest>,
+) -> Result<Vec<DiscoveredComponent>> {
+    let index: HashMap<&str, usize> = discovered
+        .iter()
+        .enumerate()
+        .map(|(i, component)| (component.label.as_str(), i))
+        .collect();
+
+    let mut errors = Vec::new();
+    for component in &discovered {
+        for dependency_label in component.manifest.depends_on.keys() {
+            let dependency = match index.get(dependency_label.as_str()) {
+                Some(&i) => Some(&discovered[i].manifest),
+                None => loaded.get(dependency_label).copied(),
+            };
+            if let Err(e) =
+                component
+                    .manifest
+                    .check_dependency(&component.label, dependency_label, dependency)
+            {
+                errors.push(e.to_string());
+            }
+        }
+    }
+    if !errors.is_empty() {
+        bail!("Unsatisfied component dependencies:\n{}", errors.join("\n"));
+    }
+
+    #[derive(Clone, Copy, PartialEq, Eq)]
+    enum Mark {
+        None,
+        Visiting,
+        Done,
+    }
+
+    fn visit(
+        i: usize,
+        discovered: &[DiscoveredComponent],
+        index: &HashMap<&str, usize>,
+        marks: &mut [Mark],
+        stack: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<()> {
+        match marks[i] {
+            Mark::Done => return Ok(()),
+            Mark::Visiting => {
+                let cycle_start = stack.iter().position(|&j| j == i).unwrap();
+                let cycle: Vec<_> = stack[cycle_start..]
+                    .iter()
+                    .chain(std::iter::once(&i))
+                    .map(|&j| format!("\"{}\"", discovered[j].label))
+                    .collect();
+                bail!("Component dependency cycle: {}", cycle.join(" -> "));
+            }
+            Mark::None => (),
+        }
+        marks[i] = Mark::Visiting;
+        stack.push(i);
+        let manifest = &discovered[i].manifest;
+        let mut predecessors: Vec<usize> = manifest
+            .depends_on
+            .keys()
+            .chain(manifest.load_after.iter())
+            .filter_map(|label| index.get(label.as_str()).copied())
+            .collect();
+        predecessors.sort_unstable();
+        predecessors.dedup();
+        for j in predecessors {
+            visit(j, discovered, index, marks, stack, order)?;
+        }
+        stack.pop();
+        marks[i] = Mark::Done;
+        order.push(i);
+        Ok(())
+    }
+
+    let mut marks = vec![Mark::None; discovered.len()];
+    let mut order = Vec::with_capacity(discovered.len());
+    for i in 0..discovered.len() {
+        visit(
+            i,
+            &discovered,
+            &index,
+            &mut marks,
+            &mut Vec::new(),
+            &mut order,
+        )?;
+    }
+
+    let mut discovered: Vec<_> = discovered.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .map(|i| {
+            discovered[i]
+                .take()
+                .ok_or_else(|| anyhow!("Component ordered twice"))
+        })
+        .try_collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn component(
+        label: &str,
+        depends_on: &[(&str, &str)],
+        load_after: &[&str],
+    ) -> DiscoveredComponent {
+        let mut manifest = ComponentManifest::fallback(label);
+        manifest.version = semver::Version::new(1, 2, 0);
+        manifest.depends_on = depends_on
+            .iter()
+            .map(|&(label, requirement)| (label.to_owned(), requirement.parse().unwrap()))
+            .collect();
+        manifest.load_after = load_after.iter().map(|&label| label.to_owned()).collect();
+        DiscoveredComponent {
+            label: label.to_owned(),
+            dir: PathBuf::from(label),
+            manifest,
+        }
+    }
+
+    fn labels(components: &[DiscoveredComponent]) -> Vec<&str> {
+        components
+            .iter()
+            .map(|component| component.label.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn dependencies_are_ordered_first() {
+        let discovered = vec![
+            component("a", &[("b", "^1")], &[]),
+            component("c", &[], &[]),
+            component("b", &[], &["c"]),
+        ];
+        let ordered = order_components(discovered, &HashMap::new()).unwrap();
+        assert_eq!(labels(&ordered), ["c", "b", "a"]);
+    }
+
+    #[test]
+    fn absent_load_after_components_are_ignored() {
+        let discovered = vec![component("a", &[], &["missing"]), component("b", &[], &[])];
+        let ordered = order_components(discovered, &HashMap::new()).unwrap();
+        assert_eq!(labels(&ordered), ["a", "b"]);
+    }
+
+    #[test]
+    fn dependencies_are_checked() {
+        let core = ComponentManifest::fallback("core");
+        let loaded = HashMap::from([("core".to_owned(), &core)]);
+        let discovered = vec![component("a", &[("core", "^0")], &[])];
+        assert!(order_components(discovered, &loaded).is_ok());
+
+        let discovered = vec![
+            component("a", &[("b", "^2")], &[]),
+            component("b", &[], &[]),
+            component("c", &[("missing", "*")], &[]),
+        ];
+        let error = order_components(discovered, &loaded)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("\"a\" depends on \"b\" ^2, but version 1.2.0 is available"));
+        assert!(error.contains("\"c\" depends on \"missing\" *, which isn't available"));
+    }
+
+    #[test]
+    fn cycles_are_reported() {
+        let discovered = vec![
+            component("a", &[("b", "*")], &[]),
+            component("b", &[], &["c"]),
+            component("c", &[], &["a"]),
+        ];
+        let error = order_components(discovered, &HashMap::new()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Component dependency cycle: \"a\" -> \"b\" -> \"c\" -> \"a\""
+        );
+    }
+}
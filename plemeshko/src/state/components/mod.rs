@@ -2,20 +2,44 @@ mod app;
 mod id;
 mod indexer;
 mod loader;
+mod manifest;
+mod settings;
 mod shared;
+mod watcher;
 
 pub use app::*;
 pub use id::*;
 pub use indexer::*;
 pub use loader::*;
+pub use manifest::*;
+pub use settings::*;
 pub use shared::*;
+pub use watcher::*;
+
+use std::cell::RefCell;
+
+use anyhow::Result;
 
 #[derive(Clone, Copy)]
 pub struct ComponentsRef<'a> {
     pub indexer: &'a ComponentIndexer,
     pub app: &'a AppComponents,
     pub shared: &'a SharedComponents,
+    /// When present, elements of collections which fail to deserialize, like ones referring to missing configs,
+    /// are dropped instead of failing the whole value, with the errors recorded.
+    pub dropped: Option<&'a RefCell<Vec<String>>>,
 }
 
-// todo: parallelize component loading?
-// todo: partially loaded component? (no App/Shared part?)
+impl<'a> ComponentsRef<'a> {
+    /// Passes the deserialized element through, or drops it when dropping is enabled and it failed.
+    pub fn drop_failed<T>(self, result: Result<T>) -> Result<Option<T>> {
+        match (result, self.dropped) {
+            (Ok(value), _) => Ok(Some(value)),
+            (Err(e), Some(dropped)) => {
+                dropped.borrow_mut().push(format!("{e:#}"));
+                Ok(None)
+            }
+            (Err(e), None) => Err(e),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::BTreeSet;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::params::COMPONENT_SETTINGS_FILE;
+
+use super::DiscoveredComponent;
+
+/// User's choice of components loaded from the mods directory, persisted between runs.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ComponentSettings {
+    /// Labels of components which aren't loaded.
+    #[serde(default)]
+    pub disabled: BTreeSet<String>,
+    /// Preferred load order of components, applied where dependencies don't dictate it.
+    /// Components missing from the list are loaded after the listed ones.
+    #[serde(default)]
+    pub order: Vec<String>,
+}
+
+impl ComponentSettings {
+    /// Reads settings from the file, falling back to the default ones when it is absent.
+    pub fn read() -> Result<Self> {
+        if !std::fs::try_exists(COMPONENT_SETTINGS_FILE)
+            .context("Checking existence of component settings.")?
+        {
+            return Ok(ComponentSettings::default());
+        }
+        let file = std::fs::File::open(COMPONENT_SETTINGS_FILE)?;
+        let reader = std::io::BufReader::new(file);
+        serde_json::de::from_reader(reader)
+            .with_context(|| format!("Reading component settings: {COMPONENT_SETTINGS_FILE}"))
+    }
+
+    pub fn write(&self) -> Result<()> {
+        let mut file = std::fs::File::create(COMPONENT_SETTINGS_FILE)?;
+        serde_json::to_writer_pretty(&mut file, self)?;
+        Ok(())
+    }
+
+    pub fn is_enabled(&self, label: &str) -> bool {
+        !self.disabled.contains(label)
+    }
+
+    /// Sorts components by the preferred order, keeping the relative order of unlisted ones.
+    pub fn sort(&self, discovered: &mut [DiscoveredComponent]) {
+        discovered.sort_by_key(|component| {
+            self.order
+                .iter()
+                .position(|label| *label == component.label)
+                .unwrap_or(usize::MAX)
+        });
+    }
+
+    /// Sorts components by the preferred order and removes disabled ones.
+    pub fn apply(&self, mut discovered: Vec<DiscoveredComponent>) -> Vec<DiscoveredComponent> {
+        discovered.retain(|component| self.is_enabled(&component.label));
+        self.sort(&mut discovered);
+        discovered
+    }
+}
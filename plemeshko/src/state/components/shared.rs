@@ -1,21 +1,33 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use either::Either;
 
-use crate::state::config::{Config, ConfigRepository, FatConfigId};
+use crate::{
+    params::{CORE_RESOURCE_FOOD, CORE_RESOURCE_HUMAN},
+    sim::config::resource::ResourceId,
+    state::config::{Config, ConfigLabel, ConfigRepository, FatConfigId},
+};
 
 use super::{ComponentId, ComponentSlotId};
 
 pub struct SharedComponent {
+    pub(super) id: ComponentId,
     pub configs: ConfigRepository,
 }
 
 #[derive(Default)]
 pub struct SharedComponents(pub(super) Vec<Option<SharedComponent>>);
 
+impl SharedComponent {
+    pub fn id(&self) -> ComponentId {
+        self.id
+    }
+}
+
 impl SharedComponents {
     pub fn component_mut(&mut self, id: ComponentId) -> Result<&mut SharedComponent> {
-        self.0
-            .get_mut(id.0 as usize)
+        let component = self
+            .0
+            .get_mut(id.to_index())
             .ok_or_else(|| anyhow!("Component id out of range: {}", id.0))?
             .as_mut()
             .ok_or_else(|| {
@@ -23,12 +35,17 @@ impl SharedComponents {
                     "Component hasn't finished loading or was unloaded: {}",
                     id.0
                 )
-            })
+            })?;
+        if component.id != id {
+            bail!("Component was unloaded, its slot is reused: {}", id.0);
+        }
+        Ok(component)
     }
 
     pub fn component(&self, id: ComponentId) -> Result<&SharedComponent> {
-        self.0
-            .get(id.0 as usize)
+        let component = self
+            .0
+            .get(id.to_index())
             .ok_or_else(|| anyhow!("Component id out of range: {}", id.0))?
             .as_ref()
             .ok_or_else(|| {
@@ -36,7 +53,11 @@ impl SharedComponents {
                     "Component hasn't finished loading or was unloaded: {}",
                     id.0
                 )
-            })
+            })?;
+        if component.id != id {
+            bail!("Component was unloaded, its slot is reused: {}", id.0);
+        }
+        Ok(component)
     }
 
     pub fn component_slot(&self, id: ComponentSlotId) -> Result<&Option<SharedComponent>> {
@@ -58,26 +79,39 @@ impl SharedComponents {
         self.component(ComponentId::core())
     }
 
+    // Resolved by label, because config ids change when components are reloaded.
+    pub fn human_id(&self) -> Result<ResourceId> {
+        let id = self.core()?.configs.id_from_raw(CORE_RESOURCE_HUMAN)?;
+        Ok(FatConfigId::new_core(id))
+    }
+
+    pub fn food_id(&self) -> Result<ResourceId> {
+        let id = self.core()?.configs.id_from_raw(CORE_RESOURCE_FOOD)?;
+        Ok(FatConfigId::new_core(id))
+    }
+
     pub fn iter_components(&self) -> impl Iterator<Item = (ComponentId, &SharedComponent)> {
-        self.0.iter().enumerate().filter_map(|(i, comp)| {
-            comp.as_ref()
-                .map(|comp| (ComponentId(i.try_into().unwrap()), comp))
-        })
+        self.0
+            .iter()
+            .filter_map(|comp| comp.as_ref().map(|comp| (comp.id, comp)))
     }
 
     pub fn iter_components_mut(
         &mut self,
     ) -> impl Iterator<Item = (ComponentId, &mut SharedComponent)> {
-        self.0.iter_mut().enumerate().filter_map(|(i, comp)| {
-            comp.as_mut()
-                .map(|comp| (ComponentId(i.try_into().unwrap()), comp))
-        })
+        self.0
+            .iter_mut()
+            .filter_map(|comp| comp.as_mut().map(|comp| (comp.id, comp)))
     }
 
     pub fn config<C: Config>(&self, id: FatConfigId<C>) -> Result<&C> {
         self.component(id.0)?.configs.storage()?.get(id.1)
     }
 
+    pub fn config_label<C: Config>(&self, id: FatConfigId<C>) -> Result<&ConfigLabel<C>> {
+        self.component(id.0)?.configs.label(id.1)
+    }
+
     pub fn config_mut<C: Config>(&mut self, id: FatConfigId<C>) -> Result<&mut C> {
         self.component_mut(id.0)?
             .configs
@@ -0,0 +1,108 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::{Instant, SystemTime},
+};
+
+use anyhow::Result;
+
+use crate::params::HOT_RELOAD_POLL_INTERVAL;
+
+use super::{AppComponents, ComponentIndexer};
+
+/// Summary of a directory's files, changing when any file is added, removed or modified.
+#[derive(PartialEq, Eq)]
+struct DirectoryStamp {
+    files: usize,
+    modified: Option<SystemTime>,
+}
+
+impl DirectoryStamp {
+    fn read(dir: &Path) -> Result<Self> {
+        let mut stamp = DirectoryStamp {
+            files: 0,
+            modified: None,
+        };
+        stamp.accumulate(dir)?;
+        Ok(stamp)
+    }
+
+    /// Files removed while the directory is read, like temporary files of editors, are skipped.
+    fn accumulate(&mut self, dir: &Path) -> Result<()> {
+        let Some(entries) = skip_not_found(std::fs::read_dir(dir))? else {
+            return Ok(());
+        };
+        for entry in entries {
+            let Some(entry) = skip_not_found(entry)? else {
+                continue;
+            };
+            let Some(metadata) = skip_not_found(entry.metadata())? else {
+                continue;
+            };
+            if metadata.is_dir() {
+                self.accumulate(&entry.path())?;
+                continue;
+            }
+            self.files += 1;
+            self.modified = self.modified.max(Some(metadata.modified()?));
+        }
+        Ok(())
+    }
+}
+
+fn skip_not_found<T>(result: std::io::Result<T>) -> std::io::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+struct WatchedComponent {
+    label: String,
+    dir: PathBuf,
+    stamp: DirectoryStamp,
+}
+
+/// Detects changes in directories of loaded components by polling their files.
+pub struct ComponentWatcher {
+    components: Vec<WatchedComponent>,
+    last_poll: Instant,
+}
+
+impl ComponentWatcher {
+    pub fn new(indexer: &ComponentIndexer, app_comps: &AppComponents) -> Result<Self> {
+        let components: Vec<_> = app_comps
+            .iter_components()
+            .map(|(id, comp)| {
+                Ok(WatchedComponent {
+                    label: indexer.label(id)?.0.clone(),
+                    dir: comp.dir.clone(),
+                    stamp: DirectoryStamp::read(&comp.dir)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(ComponentWatcher {
+            components,
+            last_poll: Instant::now(),
+        })
+    }
+
+    /// Returns labels of components changed since the last poll.
+    /// Directories are scanned at most once per `HOT_RELOAD_POLL_INTERVAL`.
+    pub fn poll(&mut self) -> Result<Vec<String>> {
+        if self.last_poll.elapsed() < HOT_RELOAD_POLL_INTERVAL {
+            return Ok(Vec::new());
+        }
+        self.last_poll = Instant::now();
+        let mut changed = Vec::new();
+        for component in &mut self.components {
+            let stamp = DirectoryStamp::read(&component.dir)?;
+            if stamp != component.stamp {
+                component.stamp = stamp;
+                changed.push(component.label.clone());
+            }
+        }
+        Ok(changed)
+    }
+}
@@ -0,0 +1,97 @@
+use crate::{
+    params::LABEL_SEPARATOR,
+    state::{
+        components::{ComponentIndexer, SharedComponents},
+        text::FatTextId,
+        texture::FatTextureId,
+    },
+};
+
+use super::{Config, FatConfigId, Info};
+
+/// Context of checking references between loaded configs, collecting found problems.
+/// Texts and textures are only recorded, as they're loaded separately from configs.
+pub struct ConfigChecker<'a> {
+    pub indexer: &'a ComponentIndexer,
+    pub shared_comps: &'a SharedComponents,
+    /// Texts referred to by configs, along with descriptions of the configs.
+    pub texts: Vec<(String, FatTextId)>,
+    /// Icons of configs, along with descriptions of the configs.
+    pub textures: Vec<(String, FatTextureId)>,
+    problems: Vec<String>,
+}
+
+impl<'a> ConfigChecker<'a> {
+    pub fn new(indexer: &'a ComponentIndexer, shared_comps: &'a SharedComponents) -> Self {
+        ConfigChecker {
+            indexer,
+            shared_comps,
+            texts: Vec::new(),
+            textures: Vec::new(),
+            problems: Vec::new(),
+        }
+    }
+
+    /// Formats as the tag and the fat label of the config, falling back to raw ids when it isn't loaded.
+    pub fn describe<C: Config>(&self, id: FatConfigId<C>) -> String {
+        let component_label = self.indexer.label(id.0);
+        let config_label = self.shared_comps.config_label(id);
+        match (component_label, config_label) {
+            (Ok(component_label), Ok(config_label)) => {
+                format!(
+                    "{} \"{component_label}{LABEL_SEPARATOR}{config_label}\"",
+                    C::TAG
+                )
+            }
+            _ => format!("{} {:?}", C::TAG, id),
+        }
+    }
+
+    pub fn report(&mut self, problem: String) {
+        self.problems.push(problem);
+    }
+
+    /// Reports the reference when it doesn't point to a loaded config.
+    pub fn check_ref<C: Config>(&mut self, from: &str, id: FatConfigId<C>) -> bool {
+        if let Err(e) = self.shared_comps.config(id) {
+            self.report(format!(
+                "{from} refers to a missing {}: {e:#}",
+                self.describe(id)
+            ));
+            return false;
+        }
+        true
+    }
+
+    pub fn check_refs<C: Config>(
+        &mut self,
+        from: &str,
+        ids: impl IntoIterator<Item = FatConfigId<C>>,
+    ) {
+        for id in ids {
+            self.check_ref(from, id);
+        }
+    }
+
+    /// Reports the text when its component isn't loaded.
+    pub fn check_text(&mut self, from: &str, id: &FatTextId) {
+        if let Err(e) = self.shared_comps.component(id.0) {
+            self.report(format!(
+                "{from} refers to a text of a missing component: {e:#}"
+            ));
+        }
+        self.texts.push((from.to_owned(), id.clone()));
+    }
+
+    pub fn check_info(&mut self, from: &str, info: &Info) {
+        self.check_text(from, &info.name);
+        self.check_text(from, &info.description);
+        self.textures.push((from.to_owned(), info.icon.texture));
+    }
+
+    /// Found problems, sorted to not depend on the order configs were loaded in.
+    pub fn into_problems(mut self) -> Vec<String> {
+        self.problems.sort();
+        self.problems
+    }
+}
@@ -7,12 +7,20 @@ use std::{
 use anyhow::{Context, Result};
 use bytemuck::TransparentWrapper;
 use educe::Educe;
+use schemars::{
+    gen::SchemaGenerator,
+    schema::{Schema, SchemaObject},
+    JsonSchema,
+};
 use serde::{Deserialize, Serialize};
 
-use crate::state::{
-    components::{ComponentId, ComponentsRef, RawFatLabel},
-    serializable::Serializable,
-    text::TextIdFactory,
+use crate::{
+    params::LABEL_SEPARATOR,
+    state::{
+        components::{ComponentId, ComponentsRef, RawFatLabel},
+        serializable::Serializable,
+        text::TextIdFactory,
+    },
 };
 
 use super::{prepare::Prepare, Config, ConfigIndexerMap, ConfigsLoadingContext};
@@ -138,6 +146,29 @@ impl<C: Config> Prepare for FatConfigLabel<C> {
     }
 }
 
+impl<C: Config> JsonSchema for FatConfigLabel<C> {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        format!("FatConfigLabel_{}", C::TAG)
+    }
+
+    fn json_schema(gen: &mut SchemaGenerator) -> Schema {
+        // the tag lets schema consumers know which configs the label may refer to
+        let mut schema: SchemaObject = RawFatLabel::json_schema(gen).into_object();
+        schema.metadata().description = Some(format!(
+            "Label of a \"{}\" config, prefixed by the label of its component and \"{LABEL_SEPARATOR}\" when it's not local",
+            C::TAG
+        ));
+        schema
+            .extensions
+            .insert("x-config-tag".to_owned(), C::TAG.into());
+        schema.into()
+    }
+}
+
 impl<C: Config> Serializable for FatConfigId<C> {
     type Raw = FatConfigLabel<C>;
 
@@ -4,13 +4,13 @@ use std::{
     collections::HashMap,
 };
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use bytemuck::TransparentWrapper;
 use educe::Educe;
 
 use crate::state::raw_indexer::RawIndexer;
 
-use super::{Config, ConfigId, ConfigLabel, RawConfigId};
+use super::{validation::with_suggestion, Config, ConfigId, ConfigLabel, RawConfigId};
 
 #[derive(Educe)]
 #[educe(Default)]
@@ -32,7 +32,21 @@ impl ConfigIndexer {
     }
 
     pub fn id_from_raw<C: Config>(&self, label: &str) -> Result<ConfigId<C>> {
-        Ok(ConfigId::new(self.0.id(label)?))
+        match self.0.label_to_id.get(label) {
+            Some(&id) => Ok(ConfigId::new(id)),
+            None => bail!(
+                "{}",
+                with_suggestion(
+                    format!("Label not registered: {label}"),
+                    label,
+                    self.labels()
+                )
+            ),
+        }
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.0.id_to_label.iter().map(String::as_str)
     }
 
     pub fn declare_id<C: Config>(&mut self, label: Cow<'_, ConfigLabel<C>>) -> Result<ConfigId<C>> {
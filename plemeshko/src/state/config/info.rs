@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 
 use crate::state::{
@@ -8,7 +9,7 @@ use crate::state::{
 use super::Prepare;
 
 /// Deserialized part of [`Info`]
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema)]
 pub struct RawInfo {
     pub icon: Option<FatTexturePartLabel>,
 }
@@ -1,21 +1,29 @@
+mod check;
 mod id;
 mod indexer;
 mod info;
+mod patch;
 mod repository;
 mod repository_builder;
+mod schema;
 mod type_registry;
+mod validation;
 #[macro_use]
 mod prepare;
 
+pub use check::*;
 pub use id::*;
 pub use indexer::*;
 pub use info::*;
+pub use patch::*;
 pub use prepare::*;
 pub use repository::*;
 pub use repository_builder::*;
 pub use type_registry::*;
+pub use validation::*;
 
 use anyhow::Result;
+use schemars::JsonSchema;
 
 use super::{
     components::{ComponentIndexer, SharedComponents},
@@ -27,8 +35,9 @@ pub type ConfigFinalizationPriority = i32;
 /// A trait for small assets with custom format or schema, unlike, for example, textures or sounds.
 /// Loaded as a part of a component, initially as [`Raw`](Config::Raw) and then "prepared".
 /// [`TAG`](Config::TAG) is used to differentiate between different [`Config`](Config) types.
+/// Payloads of raw configs are validated against the schema of [`Raw`](Config::Raw).
 pub trait Config: Sized + Send + Sync + 'static {
-    type Raw: Prepare<Prepared = Self>;
+    type Raw: Prepare<Prepared = Self> + JsonSchema;
 
     const TAG: &'static str;
     const FINALIZATION_PRIORITY: ConfigFinalizationPriority = 0;
@@ -37,6 +46,12 @@ pub trait Config: Sized + Send + Sync + 'static {
     fn finalize(indexer: &ComponentIndexer, shared_comps: &mut SharedComponents) -> Result<()> {
         Ok(())
     }
+
+    /// Checks references of configs once all components are loaded and finalized, reporting problems to the checker.
+    #[allow(unused_variables)]
+    fn validate(checker: &mut ConfigChecker<'_>) -> Result<()> {
+        Ok(())
+    }
 }
 
 pub fn create_config_text_id_factory<C: Config>(config_label: &ConfigLabel<C>) -> TextIdFactory {
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use serde_json::{value::RawValue, Map, Value};
+
+/// Modification of a config of another component, applied to its raw payload before preparation.
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfigPatchAction {
+    /// Deep-merged into the payload: objects are merged recursively,
+    /// `null` removes the field and any other value replaces it.
+    Patch(Value),
+    /// Replaces the whole payload.
+    Replace(Value),
+}
+
+pub struct ConfigPatch {
+    /// Label of the component the patch comes from.
+    pub source: String,
+    pub action: ConfigPatchAction,
+}
+
+/// Patches targeting configs of a single component, by config tag and label.
+#[derive(Default)]
+pub struct ConfigPatches(HashMap<(String, String), Vec<ConfigPatch>>);
+
+impl ConfigPatches {
+    pub fn new() -> Self {
+        ConfigPatches::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn push(&mut self, tag: String, label: String, patch: ConfigPatch) {
+        self.0.entry((tag, label)).or_default().push(patch);
+    }
+
+    /// Applies patches targeting the config to its payload, in order they were added.
+    /// Returns `None` when the config isn't patched.
+    pub fn apply(
+        &mut self,
+        tag: &str,
+        label: &str,
+        payload: &RawValue,
+    ) -> Result<Option<Box<RawValue>>> {
+        let Some(patches) = self.0.remove(&(tag.to_owned(), label.to_owned())) else {
+            return Ok(None);
+        };
+        let mut payload: Value = serde_json::from_str(payload.get())?;
+        for patch in patches {
+            match patch.action {
+                ConfigPatchAction::Patch(value) => merge(&mut payload, value),
+                ConfigPatchAction::Replace(value) => payload = value,
+            }
+        }
+        Ok(Some(RawValue::from_string(serde_json::to_string(
+            &payload,
+        )?)?))
+    }
+
+    /// Labels of components the patches come from, sorted and deduplicated.
+    pub fn sources(&self) -> Vec<&str> {
+        let mut sources: Vec<&str> = self
+            .0
+            .values()
+            .flatten()
+            .map(|patch| patch.source.as_str())
+            .collect();
+        sources.sort_unstable();
+        sources.dedup();
+        sources
+    }
+
+    /// Fails when some patches weren't applied because their target configs don't exist.
+    pub fn ensure_applied(&self) -> Result<()> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        let mut missing: Vec<_> = self
+            .0
+            .iter()
+            .map(|((tag, label), patches)| {
+                let sources: Vec<_> = patches
+                    .iter()
+                    .map(|patch| format!("\"{}\"", patch.source))
+                    .collect();
+                format!("{tag} \"{label}\" patched by {}", sources.join(", "))
+            })
+            .collect();
+        missing.sort();
+        bail!("Patched configs not found:\n{}", missing.join("\n"))
+    }
+}
+
+/// Deep-merges the patch into the target, see [`ConfigPatchAction::Patch`].
+pub(super) fn merge(target: &mut Value, patch: Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!()
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(&key);
+        } else {
+            merge(target.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
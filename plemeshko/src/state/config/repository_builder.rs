@@ -1,32 +1,90 @@
 use std::{
     any::{Any, TypeId},
     collections::{HashMap, HashSet},
-    fs::File,
-    io,
+    ffi::OsStr,
     path::Path,
+    sync::Arc,
 };
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use serde::Deserialize;
-use serde_json::value::RawValue;
+use serde_json::{
+    value::{to_raw_value, RawValue},
+    Value,
+};
 
 use crate::state::{
     components::{ComponentId, ComponentsRef},
     texture::TextureRepository,
 };
 
-use super::{indexer::ConfigIndexer, type_registry::ConfigTypeRegistry, ConfigRepository};
+use super::{
+    indexer::ConfigIndexer,
+    patch::merge,
+    type_registry::ConfigTypeRegistry,
+    validation::{json_error_message, with_suggestion},
+    ConfigPatchAction, ConfigPatches, ConfigRepository, ConfigSource,
+};
 
+/// Entry of a config file. Files holding a single config contain just its payload, see [`read_file`].
 #[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct RawConfig {
     pub tag: String,
     pub label: String,
-    // todo: something similar to `#[serde(flatten)]` (or custom deserializer?) can remove unnecessary nesting
     #[serde(default = "deser_default_json_object")]
     pub payload: Box<RawValue>,
+    /// Label of a config or a template of the same tag and component, which payload is deep-merged with this one,
+    /// like patches are (see [`ConfigPatchAction::Patch`]).
+    #[serde(default)]
+    pub extends: Option<String>,
+    /// Makes the entry only a base for others to extend, not a config.
+    #[serde(default)]
+    pub template: bool,
+    /// Makes the entry a patch of another component's config, see [`ConfigPatchAction`].
+    #[serde(default)]
+    pub patch: Option<Value>,
+    #[serde(default)]
+    pub replace: Option<Value>,
+    /// Where the entry was read from, `None` for entries not read from files.
+    #[serde(skip)]
+    pub source: Option<ConfigSource>,
+}
+
+/// Formats of config files, chosen by their extensions.
+/// All of them are converted to JSON, which payloads are parsed from.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ConfigFormat {
+    Json,
+    Ron,
+    Toml,
+    Yaml,
+}
+
+/// Part of a config entry borrowed from the file's text, to locate its payload.
+#[derive(Deserialize)]
+struct PayloadSpan<'a> {
+    #[serde(borrow, default)]
+    payload: Option<&'a RawValue>,
+}
+
+/// Config entries read from a component's configs directory, before they are parsed.
+/// Reading doesn't depend on other components, so it's done for all of them at once.
+#[derive(Default)]
+pub struct RawConfigs {
+    pub configs: Vec<RawConfig>,
+    /// Errors of individual files, reported along with errors of configs.
+    pub errors: Vec<anyhow::Error>,
 }
 
-pub struct ConfigRepositoryBuilder(HashMap<TypeId, (Box<dyn Any>, ConfigIndexer)>);
+/// Errors are collected instead of stopping at the first one, and reported together by [`build`](Self::build).
+/// Raw configs are kept until then, as configs may extend ones loaded after them.
+pub struct ConfigRepositoryBuilder(
+    HashMap<TypeId, (Box<dyn Any>, ConfigIndexer)>,
+    ConfigPatches,
+    Vec<anyhow::Error>,
+    Vec<RawConfig>,
+);
 
 #[derive(Clone, Copy)]
 pub struct ComponentPreConfigsRef<'a> {
@@ -59,17 +117,51 @@ impl<'a> ComponentPreConfigsRef<'a> {
     }
 }
 
+impl RawConfig {
+    /// Position of the entry's payload to prefix errors with, empty when it's unknown.
+    fn location(&self) -> String {
+        self.source
+            .as_ref()
+            .map(|source| format!("{}: ", source.describe(0)))
+            .unwrap_or_default()
+    }
+
+    /// Takes patch of the entry, `None` when it's a regular config.
+    pub fn take_patch(&mut self) -> Result<Option<ConfigPatchAction>> {
+        match (self.patch.take(), self.replace.take()) {
+            (None, None) => Ok(None),
+            (Some(patch), None) => Ok(Some(ConfigPatchAction::Patch(patch))),
+            (None, Some(replace)) => Ok(Some(ConfigPatchAction::Replace(replace))),
+            (Some(_), Some(_)) => bail!(
+                "Config entry {} \"{}\" has both `patch` and `replace`",
+                self.tag,
+                self.label
+            ),
+        }
+    }
+}
+
 impl ConfigRepositoryBuilder {
     pub fn new(reg: &ConfigTypeRegistry) -> Result<Self> {
         let mut configs = HashMap::new();
-        for (&type_id, (new_map, _, _, _)) in reg.type_map.iter() {
+        for (&type_id, (new_map, _, _, _, _)) in reg.type_map.iter() {
             let indexer = ConfigIndexer::new();
             let map = new_map();
             configs
                 .try_insert(type_id, (map, indexer))
                 .map_err(|_| anyhow!("Type registered more than once: {:?}", type_id))?;
         }
-        Ok(ConfigRepositoryBuilder(configs))
+        Ok(ConfigRepositoryBuilder(
+            configs,
+            ConfigPatches::new(),
+            Vec::new(),
+            Vec::new(),
+        ))
+    }
+
+    /// Sets patches to apply to loaded configs, all of them must be applied by the time of [`build`](Self::build).
+    pub fn set_patches(&mut self, patches: ConfigPatches) {
+        self.1 = patches;
     }
 
     pub fn build<'a>(
@@ -78,13 +170,18 @@ impl ConfigRepositoryBuilder {
         components: ComponentsRef<'a>,
         pre_cfg: ComponentPreConfigsRef<'a>,
     ) -> Result<ConfigRepository> {
+        self.insert_raw(cfg_ty_reg);
+        let mut errors = std::mem::take(&mut self.2);
+        if let Err(e) = self.1.ensure_applied() {
+            errors.push(e);
+        }
         let mut loaded_cfg_tys = HashSet::with_capacity(cfg_ty_reg.type_map.len());
-        for (type_id, (_, labelmap_to_idmap, _, _)) in cfg_ty_reg.type_map.iter() {
+        for (type_id, (_, labelmap_to_idmap, _, _, _)) in cfg_ty_reg.type_map.iter() {
             let Some(label_to_raw) = self.0.get_mut(type_id) else {
                 continue;
             };
             let label_to_raw = std::mem::replace(&mut label_to_raw.0, Box::new(()));
-            let config_storage = labelmap_to_idmap(
+            let config_storage = match labelmap_to_idmap(
                 &mut ConfigsLoadingContext {
                     other_components: components,
                     this_component: pre_cfg,
@@ -92,15 +189,24 @@ impl ConfigRepositoryBuilder {
                     loaded: &loaded_cfg_tys,
                 },
                 label_to_raw,
-            )?;
+            ) {
+                Ok(config_storage) => config_storage,
+                Err(e) => {
+                    errors.push(e);
+                    continue;
+                }
+            };
             if !loaded_cfg_tys.insert(*type_id) {
                 panic!("Config type loaded multiple times");
             }
             self.0.get_mut(type_id).unwrap().0 = config_storage;
         }
+        if !errors.is_empty() {
+            let errors: Vec<_> = errors.iter().map(|e| format!("{e:#}")).collect();
+            bail!("Loading configs failed:\n{}", errors.join("\n"));
+        }
 
-        // todo: when some cfg param enabled, vaidate that all ids point to existing configs
-        // consider (on the same flag or another one) disabling cfg existence checks when retrieving by id
+        // references between configs of all components are checked by `ComponentLoader::finalize`
 
         Ok(ConfigRepository {
             // SAFETY:
@@ -114,42 +220,323 @@ impl ConfigRepositoryBuilder {
         })
     }
 
-    pub fn load_raw(&mut self, reg: &ConfigTypeRegistry, raw: RawConfig) -> Result<()> {
-        let (type_id, insert_cfg) = reg
-            .tag_map
-            .get(raw.tag.as_str())
-            .ok_or_else(|| anyhow!("Tag not registered: {}", raw.tag))?;
+    /// Loads raw config, skipping patches of other components' configs which are collected separately.
+    /// It's parsed by [`build`](Self::build), once all configs it may extend are loaded.
+    pub fn load_raw(&mut self, reg: &ConfigTypeRegistry, mut raw: RawConfig) -> Result<()> {
+        if raw.take_patch()?.is_some() {
+            return Ok(());
+        }
+        if !reg.tag_map.contains_key(raw.tag.as_str()) {
+            bail!(
+                "{}{}",
+                raw.location(),
+                with_suggestion(
+                    format!("Tag not registered: {}", raw.tag),
+                    &raw.tag,
+                    reg.tags()
+                )
+            );
+        }
+        self.3.push(raw);
+        Ok(())
+    }
+
+    /// Parses loaded raw configs, merging payloads they extend into them and applying patches.
+    fn insert_raw(&mut self, reg: &ConfigTypeRegistry) {
+        let raw_cfgs = std::mem::take(&mut self.3);
+        let mut by_label = HashMap::with_capacity(raw_cfgs.len());
+        for raw in &raw_cfgs {
+            if let Some(other) = by_label.insert((raw.tag.as_str(), raw.label.as_str()), raw) {
+                // duplicate configs are reported when parsed
+                if raw.template || other.template {
+                    self.2.push(anyhow!(
+                        "{}{} \"{}\" is defined more than once",
+                        raw.location(),
+                        raw.tag,
+                        raw.label
+                    ));
+                }
+            }
+        }
+        for raw in &raw_cfgs {
+            if raw.template {
+                continue;
+            }
+            if let Err(e) = self.insert_single_raw(reg, &by_label, raw) {
+                self.2.push(e);
+            }
+        }
+    }
+
+    fn insert_single_raw(
+        &mut self,
+        reg: &ConfigTypeRegistry,
+        by_label: &HashMap<(&str, &str), &RawConfig>,
+        raw: &RawConfig,
+    ) -> Result<()> {
+        let (type_id, insert_cfg, schema) = &reg.tag_map[raw.tag.as_str()];
         let store = self
             .0
             .get_mut(type_id)
             .ok_or_else(|| anyhow!("Storage for requested tag doesn't exist: {}", raw.tag))?;
-        insert_cfg(store.0.as_mut(), raw.label, raw.payload.as_ref())
+        let extended = match raw.extends {
+            Some(_) => Some(extended_payload(by_label, raw)?),
+            None => None,
+        };
+        let payload = extended.as_deref().unwrap_or(&raw.payload);
+        let patched = self.1.apply(&raw.tag, &raw.label, payload)?;
+        let source = match (&extended, &patched) {
+            (None, None) => raw.source.clone(),
+            // positions in the file don't correspond to the merged payload
+            _ => raw.source.as_ref().map(ConfigSource::modified),
+        };
+        insert_cfg(
+            store.0.as_mut(),
+            raw.label.clone(),
+            patched.as_deref().unwrap_or(payload),
+            source,
+            schema,
+        )
     }
 
+    /// Loads configs from the file, collecting errors of individual entries.
     pub fn load_file(&mut self, reg: &ConfigTypeRegistry, path: &Path) -> Result<()> {
-        let file = File::open(path)?;
-        let reader = io::BufReader::new(file);
-        let raw_cfgs = serde_json::from_reader::<_, Vec<RawConfig>>(reader)
-            .with_context(|| format!("Pre-parsing file {} failed", path.display()))?;
-        for raw_cfg in raw_cfgs {
-            self.load_raw(reg, raw_cfg).with_context(|| {
-                format!(
-                    "Failed to parse config payload when loading \"{}\": ",
-                    path.display()
-                )
-            })?;
+        for raw_cfg in read_file(path)? {
+            if let Err(e) = self.load_raw(reg, raw_cfg) {
+                self.2.push(e);
+            }
         }
         Ok(())
     }
 
+    /// Loads read configs, collecting errors of individual entries along with errors of reading.
+    pub fn load_raw_configs(&mut self, reg: &ConfigTypeRegistry, raw_cfgs: RawConfigs) {
+        self.2.extend(raw_cfgs.errors);
+        for raw_cfg in raw_cfgs.configs {
+            if let Err(e) = self.load_raw(reg, raw_cfg) {
+                self.2.push(e);
+            }
+        }
+    }
+
     pub fn load_directory(&mut self, reg: &ConfigTypeRegistry, path: &Path) -> Result<()> {
+        self.load_raw_configs(reg, RawConfigs::read_directory(path)?);
+        Ok(())
+    }
+}
+
+impl ConfigFormat {
+    const EXTENSIONS: [(&'static str, ConfigFormat); 5] = [
+        ("json", ConfigFormat::Json),
+        ("ron", ConfigFormat::Ron),
+        ("toml", ConfigFormat::Toml),
+        ("yaml", ConfigFormat::Yaml),
+        ("yml", ConfigFormat::Yaml),
+    ];
+
+    fn of(path: &Path) -> Result<Self> {
+        let extension = path.extension().and_then(OsStr::to_str).unwrap_or_default();
+        Self::EXTENSIONS
+            .iter()
+            .find(|(format_extension, _)| format_extension.eq_ignore_ascii_case(extension))
+            .map(|(_, format)| *format)
+            .ok_or_else(|| {
+                let extensions: Vec<_> = Self::EXTENSIONS.iter().map(|(e, _)| *e).collect();
+                anyhow!(
+                    "{}: Unsupported config file format, expected one of: {}",
+                    path.display(),
+                    extensions.join(", ")
+                )
+            })
+    }
+
+    /// Parses the text into the JSON it represents.
+    fn parse(self, text: &str) -> Result<Value> {
+        Ok(match self {
+            ConfigFormat::Json => serde_json::from_str(text)?,
+            // structs can't be deserialized directly into JSON, so RON is read as its own value first
+            ConfigFormat::Ron => serde_json::to_value(ron::from_str::<ron::Value>(text)?)?,
+            ConfigFormat::Toml => toml::from_str(text)?,
+            ConfigFormat::Yaml => serde_yaml::from_str(text)?,
+        })
+    }
+}
+
+/// Reads config entries from the file, remembering their sources to report positions of errors.
+/// The file is either an array of entries, or an object which is the payload of a single config
+/// labelled by the file's name, with the tag being the name of its directory (like `resource/food.toml`).
+/// TOML documents are always tables, so TOML files hold single configs.
+pub fn read_file(path: &Path) -> Result<Vec<RawConfig>> {
+    let format = ConfigFormat::of(path)?;
+    let text: Arc<str> = std::fs::read_to_string(path)
+        .with_context(|| format!("Reading file {} failed", path.display()))?
+        .into();
+    if format != ConfigFormat::Json {
+        // positions in converted documents don't correspond to the file
+        let source = ConfigSource::new(path.to_owned(), text.clone(), None);
+        return match format
+            .parse(&text)
+            .map_err(|e| anyhow!("{}: {e:#}", path.display()))?
+        {
+            Value::Array(entries) => entries
+                .into_iter()
+                .map(|entry| {
+                    let mut raw: RawConfig = serde_json::from_value(entry)
+                        .map_err(|e| anyhow!("{}: {}", path.display(), json_error_message(&e)))?;
+                    raw.source = Some(source.clone());
+                    Ok(raw)
+                })
+                .collect(),
+            payload => Ok(vec![single_config(
+                path,
+                serde_json::value::to_raw_value(&payload)?,
+                source,
+            )?]),
+        };
+    }
+    let file_source = ConfigSource::new(path.to_owned(), text.clone(), None);
+    if text.trim_start().starts_with('{') {
+        let payload: Box<RawValue> = serde_json::from_str(&text).map_err(|e| {
+            anyhow!(
+                "{}: {}",
+                file_source.describe_entry(0, e.line(), e.column()),
+                json_error_message(&e)
+            )
+        })?;
+        let payload_offset = text.len() - text.trim_start().len();
+        let source = ConfigSource::new(path.to_owned(), text.clone(), Some(payload_offset));
+        return Ok(vec![single_config(path, payload, source)?]);
+    }
+    let entries = serde_json::from_str::<Vec<&RawValue>>(&text).map_err(|e| {
+        anyhow!(
+            "{}: {}",
+            file_source.describe_entry(0, e.line(), e.column()),
+            json_error_message(&e)
+        )
+    })?;
+    let offset_in_text = |json: &str| json.as_ptr() as usize - text.as_ptr() as usize;
+    entries
+        .into_iter()
+        .map(|entry| {
+            let entry_offset = offset_in_text(entry.get());
+            let parse_error = |e: serde_json::Error| {
+                anyhow!(
+                    "{}: {}",
+                    file_source.describe_entry(entry_offset, e.line(), e.column()),
+                    json_error_message(&e)
+                )
+            };
+            let mut raw: RawConfig = serde_json::from_str(entry.get()).map_err(parse_error)?;
+            let span: PayloadSpan<'_> = serde_json::from_str(entry.get()).map_err(parse_error)?;
+            // errors of entries without payload are reported at the entry
+            raw.source = Some(ConfigSource::new(
+                path.to_owned(),
+                text.clone(),
+                Some(
+                    span.payload
+                        .map_or(entry_offset, |payload| offset_in_text(payload.get())),
+                ),
+            ));
+            Ok(raw)
+        })
+        .collect()
+}
+
+/// Payload of the config merged into payloads of configs it extends, directly or through others.
+fn extended_payload(
+    by_label: &HashMap<(&str, &str), &RawConfig>,
+    raw: &RawConfig,
+) -> Result<Box<RawValue>> {
+    let mut chain = vec![raw];
+    let mut current = raw;
+    while let Some(base_label) = &current.extends {
+        let Some(&base) = by_label.get(&(raw.tag.as_str(), base_label.as_str())) else {
+            let labels = by_label
+                .keys()
+                .filter(|(tag, _)| *tag == raw.tag)
+                .map(|(_, label)| *label);
+            bail!(
+                "{}{}",
+                current.location(),
+                with_suggestion(
+                    format!(
+                        "{} \"{}\" extends \"{base_label}\" which doesn't exist",
+                        raw.tag, current.label
+                    ),
+                    base_label,
+                    labels
+                )
+            );
+        };
+        if chain.iter().any(|&extended| std::ptr::eq(extended, base)) {
+            let labels: Vec<_> = chain
+                .iter()
+                .map(|extended| format!("\"{}\"", extended.label))
+                .collect();
+            bail!(
+                "{}{} \"{}\" extends configs in a cycle: {} -> \"{}\"",
+                raw.location(),
+                raw.tag,
+                raw.label,
+                labels.join(" -> "),
+                base.label
+            );
+        }
+        chain.push(base);
+        current = base;
+    }
+    let mut payload = Value::Null;
+    for extended in chain.into_iter().rev() {
+        let extended_payload = serde_json::from_str(extended.payload.get())?;
+        merge(&mut payload, extended_payload);
+    }
+    Ok(to_raw_value(&payload)?)
+}
+
+/// Config of a file holding just its payload, with the tag and the label taken from the path.
+fn single_config(path: &Path, payload: Box<RawValue>, source: ConfigSource) -> Result<RawConfig> {
+    let tag = path
+        .parent()
+        .and_then(Path::file_name)
+        .and_then(OsStr::to_str);
+    let label = path.file_stem().and_then(OsStr::to_str);
+    let (Some(tag), Some(label)) = (tag, label) else {
+        bail!(
+            "{}: Can't determine the tag and the label of the config from the path",
+            path.display()
+        );
+    };
+    Ok(RawConfig {
+        tag: tag.to_owned(),
+        label: label.to_owned(),
+        payload,
+        extends: None,
+        template: false,
+        patch: None,
+        replace: None,
+        source: Some(source),
+    })
+}
+
+impl RawConfigs {
+    /// Reads raw configs from all files in the directory and its subdirectories.
+    /// Errors of individual files are collected, errors of reading directories are returned.
+    pub fn read_directory(path: &Path) -> Result<Self> {
+        let mut raw_cfgs = RawConfigs::default();
+        raw_cfgs.read_directory_into(path)?;
+        Ok(raw_cfgs)
+    }
+
+    fn read_directory_into(&mut self, path: &Path) -> Result<()> {
         for dir_entry in std::fs::read_dir(path)? {
-            let dir_entry = dir_entry?;
-            let entry_path = dir_entry.path();
+            let entry_path = dir_entry?.path();
             if entry_path.is_file() {
-                self.load_file(reg, &entry_path)?;
+                match read_file(&entry_path) {
+                    Ok(raw_cfgs) => self.configs.extend(raw_cfgs),
+                    Err(e) => self.errors.push(e),
+                }
             } else if entry_path.is_dir() {
-                self.load_directory(reg, &entry_path)?;
+                self.read_directory_into(&entry_path)?;
             }
         }
         Ok(())
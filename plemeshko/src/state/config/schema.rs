@@ -0,0 +1,201 @@
+use std::{fs::File, path::Path};
+
+use anyhow::{Context, Result};
+use schemars::schema::{
+    ArrayValidation, InstanceType, Metadata, ObjectValidation, RootSchema, Schema, SchemaObject,
+    SingleOrVec, SubschemaValidation,
+};
+
+use super::ConfigTypeRegistry;
+
+const CONFIG_FILE_SCHEMA_NAME: &str = "configs";
+
+impl ConfigTypeRegistry {
+    /// Schema of config files: arrays of raw configs, with payloads described by the schemas of their tags.
+    pub fn config_file_schema(&self) -> RootSchema {
+        let mut tags: Vec<_> = self.tags().collect();
+        tags.sort_unstable();
+        let mut root = RootSchema::default();
+        let mut entries = Vec::with_capacity(tags.len());
+        for tag in tags {
+            let schema = self.schema(tag).unwrap();
+            for (name, definition) in &schema.definitions {
+                root.definitions
+                    .entry(name.clone())
+                    .or_insert_with(|| definition.clone());
+            }
+            root.definitions
+                .insert(tag.to_owned(), schema.schema.clone().into());
+            entries.push(raw_config_schema(tag));
+        }
+        root.schema = SchemaObject {
+            metadata: Some(Box::new(Metadata {
+                title: Some("Plemeshko config file".to_owned()),
+                ..Default::default()
+            })),
+            instance_type: Some(InstanceType::Array.into()),
+            array: Some(Box::new(ArrayValidation {
+                items: Some(
+                    Schema::Object(SchemaObject {
+                        subschemas: Some(Box::new(SubschemaValidation {
+                            one_of: Some(entries),
+                            ..Default::default()
+                        })),
+                        ..Default::default()
+                    })
+                    .into(),
+                ),
+                ..Default::default()
+            })),
+            ..Default::default()
+        };
+        root
+    }
+
+    /// Writes schemas of payloads of every registered tag, and the schema of config files, to the directory.
+    pub fn export_schemas(&self, dir: &Path) -> Result<()> {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Creating schema directory {}", dir.display()))?;
+        for tag in self.tags() {
+            let mut schema = self.schema(tag).unwrap().clone();
+            schema.schema.metadata().title = Some(tag.to_owned());
+            deny_unknown_fields(&mut schema);
+            write_schema(dir, tag, &schema)?;
+        }
+        let mut schema = self.config_file_schema();
+        deny_unknown_fields(&mut schema);
+        write_schema(dir, CONFIG_FILE_SCHEMA_NAME, &schema)
+    }
+}
+
+/// Forbids properties not declared by objects, as unknown fields are errors when loading configs.
+/// Objects combined with subschemas are left open, as their properties may be declared by the subschemas.
+fn deny_unknown_fields(root: &mut RootSchema) {
+    fn visit(schema: &mut SchemaObject) {
+        if let Some(object) = &mut schema.object {
+            if object.additional_properties.is_none()
+                && object.pattern_properties.is_empty()
+                && schema.subschemas.is_none()
+            {
+                object.additional_properties = Some(Box::new(false.into()));
+            }
+            for property in object.properties.values_mut() {
+                visit_schema(property);
+            }
+            if let Some(additional) = &mut object.additional_properties {
+                visit_schema(additional);
+            }
+        }
+        if let Some(array) = &mut schema.array {
+            match &mut array.items {
+                Some(SingleOrVec::Single(item)) => visit_schema(item),
+                Some(SingleOrVec::Vec(items)) => items.iter_mut().for_each(visit_schema),
+                None => (),
+            }
+        }
+        if let Some(subschemas) = &mut schema.subschemas {
+            for alternatives in [
+                &mut subschemas.all_of,
+                &mut subschemas.any_of,
+                &mut subschemas.one_of,
+            ] {
+                alternatives.iter_mut().flatten().for_each(visit_schema);
+            }
+        }
+    }
+
+    fn visit_schema(schema: &mut Schema) {
+        if let Schema::Object(schema) = schema {
+            visit(schema);
+        }
+    }
+
+    visit(&mut root.schema);
+    root.definitions.values_mut().for_each(visit_schema);
+}
+
+/// Entry of a config file with the tag, either a config with its payload or a patch of another component's config.
+/// Payloads of templates and configs extending others may be partial, so only complete ones are validated.
+fn raw_config_schema(tag: &str) -> Schema {
+    let mut object = ObjectValidation {
+        additional_properties: Some(Box::new(false.into())),
+        ..Default::default()
+    };
+    object
+        .required
+        .extend(["tag".to_owned(), "label".to_owned()]);
+    object.properties.extend([
+        (
+            "tag".to_owned(),
+            Schema::Object(SchemaObject {
+                const_value: Some(tag.into()),
+                ..Default::default()
+            }),
+        ),
+        (
+            "label".to_owned(),
+            Schema::Object(SchemaObject {
+                instance_type: Some(InstanceType::String.into()),
+                ..Default::default()
+            }),
+        ),
+        ("payload".to_owned(), true.into()),
+        (
+            "extends".to_owned(),
+            Schema::Object(SchemaObject {
+                instance_type: Some(InstanceType::String.into()),
+                ..Default::default()
+            }),
+        ),
+        (
+            "template".to_owned(),
+            Schema::Object(SchemaObject {
+                instance_type: Some(InstanceType::Boolean.into()),
+                ..Default::default()
+            }),
+        ),
+        // patches are merged into payloads of configs, so they are validated only when applied
+        ("patch".to_owned(), true.into()),
+        ("replace".to_owned(), true.into()),
+    ]);
+    let with_property = |name: &str| {
+        let mut object = ObjectValidation::default();
+        object.required.insert(name.to_owned());
+        Schema::Object(SchemaObject {
+            object: Some(Box::new(object)),
+            ..Default::default()
+        })
+    };
+    let mut complete = ObjectValidation::default();
+    complete.properties.insert(
+        "payload".to_owned(),
+        Schema::new_ref(format!("#/definitions/{tag}")),
+    );
+    Schema::Object(SchemaObject {
+        instance_type: Some(InstanceType::Object.into()),
+        object: Some(Box::new(object)),
+        subschemas: Some(Box::new(SubschemaValidation {
+            if_schema: Some(Box::new(Schema::Object(SchemaObject {
+                subschemas: Some(Box::new(SubschemaValidation {
+                    any_of: Some(vec![with_property("extends"), with_property("template")]),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }))),
+            else_schema: Some(Box::new(Schema::Object(SchemaObject {
+                object: Some(Box::new(complete)),
+                ..Default::default()
+            }))),
+            ..Default::default()
+        })),
+        ..Default::default()
+    })
+}
+
+fn write_schema(dir: &Path, name: &str, schema: &RootSchema) -> Result<()> {
+    let path = dir.join(format!("{name}.schema.json"));
+    let mut file =
+        File::create(&path).with_context(|| format!("Creating schema file {}", path.display()))?;
+    serde_json::to_writer_pretty(&mut file, schema)?;
+    Ok(())
+}
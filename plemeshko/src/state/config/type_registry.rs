@@ -1,25 +1,35 @@
 use std::{
     any::{type_name, Any, TypeId},
     borrow::Cow,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
 };
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{anyhow, bail, Result};
+use schemars::{schema::RootSchema, schema_for};
 use serde_json::value::RawValue;
 
 use crate::state::components::{ComponentIndexer, SharedComponents};
 
 use super::{
-    create_config_text_id_factory, AnySendSync, Config, ConfigArray, ConfigFinalizationPriority,
-    ConfigIndexerMap, ConfigLabel, ConfigsLoadingContext, Prepare,
+    create_config_text_id_factory,
+    validation::{parse_payload, with_suggestion},
+    AnySendSync, Config, ConfigArray, ConfigChecker, ConfigFinalizationPriority, ConfigId,
+    ConfigIndexerMap, ConfigLabel, ConfigSource, ConfigsLoadingContext, Prepare, RawConfigId,
 };
 
-pub type LabelRawMap<C> = HashMap<ConfigLabel<C>, <C as Config>::Raw>;
+pub type LabelRawMap<C> = HashMap<ConfigLabel<C>, (<C as Config>::Raw, Option<ConfigSource>)>;
 type LabelMapToIdMap =
     fn(ctx: &mut ConfigsLoadingContext<'_>, Box<dyn Any>) -> Result<Box<AnySendSync>>;
-type ParseAddingToAnyStore = fn(&mut dyn Any, label: String, raw_cfg: &RawValue) -> Result<()>;
+type ParseAddingToAnyStore = fn(
+    &mut dyn Any,
+    label: String,
+    raw_cfg: &RawValue,
+    source: Option<ConfigSource>,
+    schema: &RootSchema,
+) -> Result<()>;
 type CreateAnyBox = fn() -> Box<dyn Any>;
 type Finalize = fn(&ComponentIndexer, &mut SharedComponents) -> Result<()>;
+type Validate = fn(&mut ConfigChecker<'_>) -> Result<()>;
 
 #[derive(Default)]
 pub struct ConfigTypeRegistry {
@@ -30,9 +40,11 @@ pub struct ConfigTypeRegistry {
             LabelMapToIdMap,
             ConfigFinalizationPriority,
             Finalize,
+            Validate,
         ),
     >,
-    pub(super) tag_map: HashMap<&'static str, (TypeId, ParseAddingToAnyStore)>,
+    /// Config types by their tags, along with schemas of their raw payloads.
+    pub(super) tag_map: HashMap<&'static str, (TypeId, ParseAddingToAnyStore, RootSchema)>,
 }
 
 impl ConfigTypeRegistry {
@@ -50,30 +62,50 @@ impl ConfigTypeRegistry {
                     label_map_to_id_map::<C>,
                     C::FINALIZATION_PRIORITY,
                     C::finalize,
+                    C::validate,
                 ),
             )
             .map_err(|_| anyhow!("Type already registered: {}", type_name::<C>()))?;
         self.tag_map
-            .try_insert(C::TAG, (type_id, parse_adding_to_any_store::<C>))
+            .try_insert(
+                C::TAG,
+                (type_id, parse_adding_to_any_store::<C>, schema_for!(C::Raw)),
+            )
             .map_err(|_| anyhow!("Tag already registered: {}", C::TAG))?;
         Ok(())
     }
 
+    pub fn schema(&self, tag: &str) -> Option<&RootSchema> {
+        self.tag_map.get(tag).map(|(_, _, schema)| schema)
+    }
+
+    pub fn tags(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.tag_map.keys().copied()
+    }
+
     pub fn finalizers(&self) -> impl Iterator<Item = Finalize> {
         let mut finalizers: Vec<(_, _)> = self
             .type_map
             .iter()
-            .map(|(_, (_, _, fp, fin))| (*fp, *fin))
+            .map(|(_, (_, _, fp, fin, _))| (*fp, *fin))
             .collect();
         finalizers.sort_unstable_by_key(|(fp, _)| -fp);
         finalizers.into_iter().map(|(_, fin)| fin)
     }
+
+    pub fn validators(&self) -> impl Iterator<Item = Validate> + '_ {
+        self.type_map
+            .values()
+            .map(|(_, _, _, _, validate)| *validate)
+    }
 }
 
 fn parse_adding_to_any_store<C: Config>(
     dst: &mut dyn Any,
     label: String,
     raw_cfg: &RawValue,
+    source: Option<ConfigSource>,
+    schema: &RootSchema,
 ) -> Result<()> {
     dst.downcast_mut::<LabelRawMap<C>>()
         .ok_or_else(|| {
@@ -83,8 +115,8 @@ fn parse_adding_to_any_store<C: Config>(
             )
         })
         .and_then(|store| {
-            let config = serde_json::from_str(raw_cfg.get()).context("Parsing failed")?;
-            match store.try_insert(ConfigLabel::new(label), config) {
+            let config = parse_payload(C::TAG, &label, raw_cfg.get(), source.as_ref(), schema)?;
+            match store.try_insert(ConfigLabel::new(label), (config, source)) {
                 Ok(_) => Ok(()),
                 Err(e) => Err(anyhow!(
                     "Identifier already loaded: {}",
@@ -104,11 +136,25 @@ fn label_map_to_id_map<C: Config + std::fmt::Debug>(
     let mut configs = (0..label_to_cfg.len())
         .map(|_| None)
         .collect::<Vec<Option<C>>>();
-    for (label, raw_config) in label_to_cfg.into_iter() {
+    // configs which failed to prepare are still loaded, they aren't reported as missing
+    let mut loaded = HashSet::with_capacity(label_to_cfg.len());
+    let mut errors = Vec::new();
+    for (label, (raw_config, source)) in label_to_cfg.into_iter() {
         let mut tif = create_config_text_id_factory(&label);
-        let prepared_config = raw_config.prepare(ctx, &mut tif)?;
+        let prepared_config = raw_config.prepare(ctx, &mut tif);
         let id = ctx.st.declare_id(Cow::Borrowed(&label))?;
         let index: usize = id.0.try_into().unwrap();
+        loaded.insert(index);
+        let prepared_config = match prepared_config {
+            Ok(prepared_config) => prepared_config,
+            Err(e) => {
+                let location = source
+                    .map(|source| format!("{}: ", source.describe(0)))
+                    .unwrap_or_default();
+                errors.push(format!("{location}{} \"{label}\": {e:#}", C::TAG));
+                continue;
+            }
+        };
         while index >= configs.len() {
             configs.push(None);
         }
@@ -118,20 +164,29 @@ fn label_map_to_id_map<C: Config + std::fmt::Debug>(
     while configs.len() < indexer.len() {
         configs.push(None);
     }
-    let mut uninit_labels = String::new();
-    for (index, config) in configs.iter().enumerate() {
-        if config.is_some() {
-            continue;
-        }
-        if !uninit_labels.is_empty() {
-            uninit_labels += ", ";
-        }
-        uninit_labels += indexer
-            .label(super::ConfigId::<C>::new(index as super::RawConfigId))
-            .map_or("<???>", |x| &x.0);
-    }
+    let loaded_labels: Vec<_> = loaded
+        .iter()
+        .filter_map(|&index| indexer.label(ConfigId::<C>::new(index as RawConfigId)).ok())
+        .map(|label| label.0.as_str())
+        .collect();
+    let uninit_labels: Vec<_> = (0..configs.len())
+        .filter(|index| !loaded.contains(index))
+        .map(|index| {
+            let label = indexer
+                .label(ConfigId::<C>::new(index as RawConfigId))
+                .map_or("<???>", |x| &x.0);
+            with_suggestion(label.to_owned(), label, loaded_labels.iter().copied())
+        })
+        .collect();
     if !uninit_labels.is_empty() {
-        bail!("Configs referenced but not loaded: {uninit_labels}");
+        errors.push(format!(
+            "{} configs referenced but not loaded: {}",
+            C::TAG,
+            uninit_labels.join(", ")
+        ));
+    }
+    if !errors.is_empty() {
+        bail!("{}", errors.join("\n"));
     }
     let configs = configs.into_iter().try_collect::<Vec<C>>().unwrap();
     Ok(Box::new(ConfigArray(configs)))
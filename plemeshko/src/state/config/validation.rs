@@ -0,0 +1,524 @@
+use std::{path::PathBuf, sync::Arc};
+
+use anyhow::{bail, Result};
+use schemars::schema::{InstanceType, RootSchema, Schema, SchemaObject, SingleOrVec};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// File a config was read from, used to report positions of errors in its payload.
+#[derive(Clone)]
+pub struct ConfigSource {
+    pub path: PathBuf,
+    text: Arc<str>,
+    /// Byte offset of the payload in the file, `None` when the payload doesn't come from the file as is.
+    payload_offset: Option<usize>,
+}
+
+enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl ConfigSource {
+    pub fn new(path: PathBuf, text: Arc<str>, payload_offset: Option<usize>) -> Self {
+        ConfigSource {
+            path,
+            text,
+            payload_offset,
+        }
+    }
+
+    /// Source of the payload modified after reading, positions in which can't be reported.
+    pub fn modified(&self) -> Self {
+        ConfigSource {
+            payload_offset: None,
+            ..self.clone()
+        }
+    }
+
+    /// Formats as `path:line:column` of the byte offset in the payload, or just the path when it's unknown.
+    pub fn describe(&self, offset: usize) -> String {
+        let Some(payload_offset) = self.payload_offset else {
+            return self.path.display().to_string();
+        };
+        let (line, column) = line_column(&self.text, payload_offset + offset);
+        format!("{}:{line}:{column}", self.path.display())
+    }
+
+    /// Formats as `path:line:column` of the position in the entry starting at the byte offset of the file,
+    /// as reported by `serde_json`.
+    pub fn describe_entry(&self, entry_offset: usize, line: usize, column: usize) -> String {
+        let entry = &self.text[entry_offset..];
+        let (line, column) = line_column(
+            &self.text,
+            entry_offset + offset_of_position(entry, line, column),
+        );
+        format!("{}:{line}:{column}", self.path.display())
+    }
+}
+
+/// 1-based line and column of the byte offset.
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// Byte offset of 1-based line and column as reported by `serde_json`.
+fn offset_of_position(text: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+    (line_start + column.saturating_sub(1)).min(text.len())
+}
+
+fn format_path(path: &[PathSegment]) -> String {
+    let mut formatted = String::from("$");
+    for segment in path {
+        match segment {
+            PathSegment::Key(key)
+                if !key.is_empty()
+                    && key
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') =>
+            {
+                formatted.push('.');
+                formatted.push_str(key);
+            }
+            PathSegment::Key(key) => formatted.push_str(&format!("[{key:?}]")),
+            PathSegment::Index(index) => formatted.push_str(&format!("[{index}]")),
+        }
+    }
+    formatted
+}
+
+/// Byte offsets of all values and object keys in valid JSON text, with their JSON paths.
+fn json_paths(text: &str) -> Vec<(String, usize)> {
+    enum Frame {
+        Object(Option<String>),
+        Array(usize),
+    }
+
+    fn path_of(stack: &[Frame]) -> String {
+        let segments: Vec<_> = stack
+            .iter()
+            .filter_map(|frame| match frame {
+                Frame::Object(key) => key.clone().map(PathSegment::Key),
+                Frame::Array(index) => Some(PathSegment::Index(*index)),
+            })
+            .collect();
+        format_path(&segments)
+    }
+
+    let mut paths = Vec::new();
+    let mut stack = Vec::<Frame>::new();
+    let mut expecting_key = false;
+    let mut chars = text.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '"' => {
+                let mut end = text.len();
+                let mut escaped = false;
+                for (c_offset, c) in chars.by_ref() {
+                    match (escaped, c) {
+                        (false, '\\') => escaped = true,
+                        (false, '"') => {
+                            end = c_offset + 1;
+                            break;
+                        }
+                        _ => escaped = false,
+                    }
+                }
+                if expecting_key {
+                    if let Some(Frame::Object(key)) = stack.last_mut() {
+                        // the token is parsed to unescape the key, the text is valid JSON
+                        let token = &text[offset..end];
+                        *key = Some(
+                            serde_json::from_str(token)
+                                .unwrap_or_else(|_| token.trim_matches('"').to_owned()),
+                        );
+                    }
+                    expecting_key = false;
+                }
+                paths.push((path_of(&stack), offset));
+            }
+            '{' => {
+                paths.push((path_of(&stack), offset));
+                stack.push(Frame::Object(None));
+                expecting_key = true;
+            }
+            '[' => {
+                paths.push((path_of(&stack), offset));
+                stack.push(Frame::Array(0));
+            }
+            '}' | ']' => {
+                stack.pop();
+            }
+            ',' => match stack.last_mut() {
+                Some(Frame::Object(key)) => {
+                    *key = None;
+                    expecting_key = true;
+                }
+                Some(Frame::Array(index)) => *index += 1,
+                None => (),
+            },
+            c if c.is_whitespace() || c == ':' => (),
+            _ => {
+                paths.push((path_of(&stack), offset));
+                while chars
+                    .peek()
+                    .is_some_and(|&(_, c)| !matches!(c, ',' | '}' | ']') && !c.is_whitespace())
+                {
+                    chars.next();
+                }
+            }
+        }
+    }
+    paths
+}
+
+/// Message of the error without the position, which is reported separately.
+pub fn json_error_message(e: &serde_json::Error) -> String {
+    let message = e.to_string();
+    let suffix = format!(" at line {} column {}", e.line(), e.column());
+    match message.strip_suffix(&suffix) {
+        Some(message) => message.to_owned(),
+        None => message,
+    }
+}
+
+/// Suggests the most similar candidate for a misspelled label or field.
+pub fn did_you_mean<'a>(
+    label: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> Option<&'a str> {
+    let max_distance = label.chars().count() / 3 + 1;
+    candidates
+        .into_iter()
+        .map(|candidate| (strsim::damerau_levenshtein(label, candidate), candidate))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Formats the error with a suggestion of the most similar candidate, when there is one.
+pub fn with_suggestion<'a>(
+    message: String,
+    label: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+) -> String {
+    match did_you_mean(label, candidates) {
+        Some(suggestion) => format!("{message} (did you mean \"{suggestion}\"?)"),
+        None => message,
+    }
+}
+
+/// Walks a JSON value along with the schema, finding fields which aren't declared by it.
+struct UnknownFields<'s> {
+    root: &'s RootSchema,
+    path: Vec<PathSegment>,
+    found: Vec<(String, String)>,
+}
+
+impl<'s> UnknownFields<'s> {
+    fn resolve(&self, schema: &'s Schema) -> Option<&'s SchemaObject> {
+        let Schema::Object(object) = schema else {
+            return None;
+        };
+        match object
+            .reference
+            .as_ref()
+            .and_then(|reference| reference.strip_prefix("#/definitions/"))
+        {
+            Some(name) => self
+                .root
+                .definitions
+                .get(name)
+                .and_then(|s| self.resolve(s)),
+            None => Some(object),
+        }
+    }
+
+    fn accepts(object: &SchemaObject, value: &Value) -> bool {
+        let Some(instance_type) = &object.instance_type else {
+            return true;
+        };
+        let types = match instance_type {
+            SingleOrVec::Single(single) => std::slice::from_ref(single.as_ref()),
+            SingleOrVec::Vec(types) => types.as_slice(),
+        };
+        types.iter().any(|ty| {
+            matches!(
+                (ty, value),
+                (InstanceType::Null, Value::Null)
+                    | (InstanceType::Boolean, Value::Bool(_))
+                    | (
+                        InstanceType::Number | InstanceType::Integer,
+                        Value::Number(_)
+                    )
+                    | (InstanceType::String, Value::String(_))
+                    | (InstanceType::Array, Value::Array(_))
+                    | (InstanceType::Object, Value::Object(_))
+            )
+        })
+    }
+
+    /// Collects schemas which apply to the value together, choosing the best fitting alternatives.
+    fn gather(&self, schema: &'s Schema, value: &Value, applied: &mut Vec<&'s SchemaObject>) {
+        let Some(object) = self.resolve(schema) else {
+            return;
+        };
+        if !Self::accepts(object, value) {
+            return;
+        }
+        applied.push(object);
+        let Some(subschemas) = &object.subschemas else {
+            return;
+        };
+        for schema in subschemas.all_of.iter().flatten() {
+            self.gather(schema, value, applied);
+        }
+        for alternatives in [&subschemas.any_of, &subschemas.one_of]
+            .into_iter()
+            .flatten()
+        {
+            let best = alternatives
+                .iter()
+                .filter_map(|schema| {
+                    let mut gathered = Vec::new();
+                    self.gather(schema, value, &mut gathered);
+                    let matching = match value {
+                        Value::Object(fields) => fields
+                            .keys()
+                            .filter(|key| {
+                                gathered.iter().any(|object| {
+                                    object
+                                        .object
+                                        .as_ref()
+                                        .is_some_and(|o| o.properties.contains_key(*key))
+                                })
+                            })
+                            .count(),
+                        _ => 0,
+                    };
+                    (!gathered.is_empty()).then_some((matching, gathered))
+                })
+                .max_by_key(|(matching, _)| *matching);
+            match best {
+                // unknown variants are reported by serde
+                Some((0, _)) if value.is_object() && alternatives.len() > 1 => (),
+                Some((_, gathered)) => applied.extend(gathered),
+                None => (),
+            }
+        }
+    }
+
+    fn walk(&mut self, schema: &'s Schema, value: &Value) {
+        let mut applied = Vec::new();
+        self.gather(schema, value, &mut applied);
+        match value {
+            Value::Object(fields) => {
+                let objects: Vec<_> = applied
+                    .iter()
+                    .filter_map(|object| object.object.as_deref())
+                    .collect();
+                if objects.is_empty() {
+                    return;
+                }
+                let additional = objects.iter().find_map(|object| {
+                    object
+                        .additional_properties
+                        .as_deref()
+                        .filter(|schema| !matches!(schema, Schema::Bool(false)))
+                });
+                for (key, value) in fields {
+                    let property = objects
+                        .iter()
+                        .find_map(|object| object.properties.get(key))
+                        .or(additional);
+                    self.path.push(PathSegment::Key(key.clone()));
+                    match property {
+                        Some(property) => self.walk(property, value),
+                        None => {
+                            let message = with_suggestion(
+                                format!("unknown field \"{key}\""),
+                                key,
+                                objects
+                                    .iter()
+                                    .flat_map(|object| object.properties.keys())
+                                    .map(String::as_str),
+                            );
+                            self.found.push((format_path(&self.path), message));
+                        }
+                    }
+                    self.path.pop();
+                }
+            }
+            Value::Array(elements) => {
+                let Some(items) = applied
+                    .iter()
+                    .find_map(|object| object.array.as_ref()?.items.as_ref())
+                else {
+                    return;
+                };
+                for (index, element) in elements.iter().enumerate() {
+                    let item = match items {
+                        SingleOrVec::Single(item) => Some(item.as_ref()),
+                        SingleOrVec::Vec(items) => items.get(index),
+                    };
+                    if let Some(item) = item {
+                        self.path.push(PathSegment::Index(index));
+                        self.walk(item, element);
+                        self.path.pop();
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Parses the payload of the config, validating it against the schema.
+/// All unknown fields are reported along with the parsing error, with positions and JSON paths.
+pub fn parse_payload<T: DeserializeOwned>(
+    tag: &str,
+    label: &str,
+    payload: &str,
+    source: Option<&ConfigSource>,
+    schema: &RootSchema,
+) -> Result<T> {
+    let describe = |offset: usize| match source {
+        Some(source) => format!("{}: ", source.describe(offset)),
+        None => String::new(),
+    };
+    let mut errors = Vec::new();
+    let mut paths = None;
+    let root = Schema::Object(schema.schema.clone());
+    if let Ok(value) = serde_json::from_str::<Value>(payload) {
+        let mut unknown = UnknownFields {
+            root: schema,
+            path: Vec::new(),
+            found: Vec::new(),
+        };
+        unknown.walk(&root, &value);
+        let paths = paths.get_or_insert_with(|| json_paths(payload));
+        for (path, message) in unknown.found {
+            let offset = paths
+                .iter()
+                .find(|(p, _)| *p == path)
+                .map_or(0, |&(_, offset)| offset);
+            errors.push(format!(
+                "{}{tag} \"{label}\" at {path}: {message}",
+                describe(offset)
+            ));
+        }
+    }
+    match serde_json::from_str::<T>(payload) {
+        Ok(parsed) if errors.is_empty() => return Ok(parsed),
+        Ok(_) => (),
+        Err(e) => {
+            let offset = offset_of_position(payload, e.line(), e.column());
+            let paths = paths.get_or_insert_with(|| json_paths(payload));
+            let path = paths
+                .iter()
+                .rfind(|&&(_, value_offset)| value_offset < offset)
+                .map_or_else(|| "$".to_owned(), |(path, _)| path.clone());
+            errors.push(format!(
+                "{}{tag} \"{label}\" at {path}: {}",
+                describe(offset),
+                json_error_message(&e)
+            ));
+        }
+    }
+    bail!("{}", errors.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use schemars::{schema_for, JsonSchema};
+    use serde::Deserialize;
+
+    use super::*;
+
+    /// Text starting at the offset of the first value or key with the path.
+    fn at_path<'t>(text: &'t str, path: &str) -> Option<&'t str> {
+        json_paths(text)
+            .into_iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, offset)| &text[offset..])
+    }
+
+    #[test]
+    fn paths_of_nested_objects_and_arrays() {
+        let text = r#"{"a": {"b": [1, {"c": true}]}, "d": "x", "e": [[], [null]]}"#;
+        assert!(at_path(text, "$").unwrap().starts_with("{\"a\""));
+        assert!(at_path(text, "$.a").unwrap().starts_with("\"a\""));
+        assert!(at_path(text, "$.a.b[0]").unwrap().starts_with("1,"));
+        assert!(at_path(text, "$.a.b[1]").unwrap().starts_with("{\"c\""));
+        assert!(at_path(text, "$.a.b[1].c").unwrap().starts_with("\"c\""));
+        assert!(at_path(text, "$.d").unwrap().starts_with("\"d\""));
+        assert!(at_path(text, "$.e[1][0]").unwrap().starts_with("null"));
+        assert_eq!(at_path(text, "$.a.b[2]"), None);
+    }
+
+    #[test]
+    fn paths_of_escaped_keys() {
+        let text = r#"{"a\"b": {"c\\": 1}, "A": 2, "x y": 3, "\u0042": 4}"#;
+        assert!(at_path(text, r#"$["a\"b"]"#)
+            .unwrap()
+            .starts_with(r#""a\"b""#));
+        assert!(at_path(text, r#"$["a\"b"]["c\\"]"#).is_some());
+        assert!(at_path(text, "$.A").unwrap().starts_with(r#""A""#));
+        assert!(at_path(text, r#"$["x y"]"#).is_some());
+        assert!(at_path(text, "$.B").unwrap().starts_with(r#""\u0042""#));
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    struct Payload {
+        a: Vec<u32>,
+        b: Option<Inner>,
+    }
+
+    #[derive(Deserialize, JsonSchema)]
+    #[allow(dead_code)]
+    struct Inner {
+        c: bool,
+    }
+
+    fn parse_error(text: &str) -> String {
+        let source = ConfigSource::new("test.json".into(), text.into(), Some(0));
+        parse_payload::<Payload>("test", "label", text, Some(&source), &schema_for!(Payload))
+            .err()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn error_positions() {
+        let error = parse_error("{\n    \"a\": [1, \"x\"]\n}");
+        assert!(
+            error.starts_with("test.json:2:16: test \"label\" at $.a[1]: invalid type"),
+            "{error}"
+        );
+        let error = parse_error("{\n    \"a\": [],\n    \"b\": {\"c\": true, \"d\": 1}\n}");
+        assert!(
+            error.starts_with("test.json:3:22: test \"label\" at $.b.d:"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn positions_of_entries() {
+        let text = "[\n  {\n    \"x\": 1\n  }\n]";
+        let source = ConfigSource::new("test.json".into(), text.into(), None);
+        let entry_offset = text.find('{').unwrap();
+        assert_eq!(source.describe_entry(entry_offset, 2, 5), "test.json:3:5");
+        assert_eq!(line_column(text, text.len()), (5, 2));
+    }
+}
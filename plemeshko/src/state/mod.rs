@@ -2,29 +2,41 @@
 pub mod config;
 #[macro_use]
 pub mod serializable;
+pub mod audit;
 pub mod components;
 pub mod has;
 pub mod label_factory;
 pub mod raw_indexer;
+pub mod replay;
 pub mod research;
 pub mod save;
 pub mod sound;
+pub mod template;
 pub mod text;
 pub mod texture;
 
-use std::sync::{Mutex, RwLock};
+use std::{
+    path::Path,
+    sync::{Arc, Mutex, RwLock},
+    thread::JoinHandle,
+};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use egui_extras::RetainedImage;
+use unic_langid::LanguageIdentifier;
 
 use crate::{
-    params::{CORE_DIR, CORE_LABEL, CORE_RESOURCE_FOOD, CORE_RESOURCE_HUMAN, MODS_DIR},
-    sim::{config::resource::ResourceId, Sim},
+    params::{CORE_DIR, CORE_LABEL, HOT_RELOAD_ENV_VAR, MODS_DIR},
+    sim::{Sim, SimSnapshot},
 };
 
 use self::{
-    components::{AppComponents, ComponentId, ComponentLoader, SharedComponents},
-    config::FatConfigId,
+    components::{
+        AppComponents, ComponentId, ComponentLoader, ComponentManifest, ComponentSettings,
+        ComponentWatcher, ComponentsRef, DiscoveredComponent, LoadingProgress, SharedComponents,
+    },
+    save::Session,
+    serializable::Serializable,
     texture::FatTextureId,
 };
 
@@ -35,76 +47,200 @@ use self::{
 pub struct SharedState {
     pub components: RwLock<SharedComponents>,
     pub sim: Mutex<Option<Sim>>,
+    /// Snapshot taken by the sim thread between steps, until it's autosaved by the app.
+    pub autosave: Mutex<Option<SimSnapshot>>,
     pub audio: Option<Audio>,
-    pub human_id: ResourceId,
-    pub food_id: ResourceId,
 }
 
 pub struct AppState {
     pub shared: &'static SharedState,
     pub components: AppComponents,
     pub component_loader: ComponentLoader,
-    pub session: Option<String>,
+    pub session: Option<Session>,
+    /// Translation selected by the user, reapplied when components are reloaded.
+    translation: Option<LanguageIdentifier>,
+    /// Present in the hot-reload mode, enabled by `HOT_RELOAD_ENV_VAR`.
+    component_watcher: Option<ComponentWatcher>,
+    /// Present until components loaded at startup replace the empty ones.
+    loading: Option<ComponentsLoading>,
     fallback_texture: RetainedImage,
 }
 
+/// Components loaded on a separate thread, so the window isn't frozen meanwhile.
+struct ComponentsLoading {
+    progress: Arc<LoadingProgress>,
+    thread: JoinHandle<Result<LoadedComponents>>,
+}
+
+/// Freshly loaded and finalized components, ready to replace the current ones.
+pub struct LoadedComponents {
+    pub loader: ComponentLoader,
+    pub shared: SharedComponents,
+    pub app: AppComponents,
+}
+
 pub struct Audio {
     stream_handle: rodio::OutputStreamHandle,
     sink_sfx: rodio::Sink,
 }
 
-/// Create environments and load core component
-pub fn initialize_state() -> Result<(Option<rodio::OutputStream>, &'static SharedState, AppState)> {
-    let mut shared_comps = SharedComponents::default();
-    let mut app_comps = AppComponents::default();
-    let mut component_loader = ComponentLoader::new()?;
-    let components_changed = component_loader.load_single(
-        &mut shared_comps,
-        &mut app_comps,
-        CORE_LABEL.to_owned(),
-        CORE_DIR.into(),
-    )?;
-    match std::fs::try_exists(MODS_DIR) {
-        Ok(true) => {
-            components_changed.consume(component_loader.load_each(
-                &mut shared_comps,
-                &mut app_comps,
-                std::path::Path::new(MODS_DIR),
-            )?);
-        }
+/// Finds components in the mods directory, none when it's inaccessible.
+pub fn discover_components() -> Result<Vec<DiscoveredComponent>> {
+    discover_components_in(Path::new(MODS_DIR))
+}
+
+fn discover_components_in(mods_dir: &Path) -> Result<Vec<DiscoveredComponent>> {
+    match std::fs::try_exists(mods_dir) {
+        Ok(true) => ComponentLoader::discover(mods_dir),
         Ok(false) => {
-            log::warn!("Skipping loading other components: Directory not found: {MODS_DIR}");
+            log::warn!(
+                "Skipping loading other components: Directory not found: {}",
+                mods_dir.display()
+            );
+            Ok(Vec::new())
         }
         Err(e) => {
             log::warn!("Skipping loading other components: Error checking directory: {e}");
+            Ok(Vec::new())
         }
     }
+}
+
+/// Loads core and components from the mods directory enabled in the settings.
+pub fn load_components(
+    settings: &ComponentSettings,
+    progress: &LoadingProgress,
+) -> Result<LoadedComponents> {
+    load_components_in(Path::new(""), settings, progress)
+}
+
+/// Loads components like [`load_components`], from core and mods directories in the root directory.
+fn load_components_in(
+    root: &Path,
+    settings: &ComponentSettings,
+    progress: &LoadingProgress,
+) -> Result<LoadedComponents> {
+    let mut shared_comps = SharedComponents::default();
+    let mut app_comps = AppComponents::default();
+    let mut component_loader = ComponentLoader::new()?;
+    // core goes first, so it gets its id; other components are loaded along with it,
+    // so their patches can be applied to core
+    let core_dir = root.join(CORE_DIR);
+    let mut discovered = vec![DiscoveredComponent {
+        label: CORE_LABEL.to_owned(),
+        manifest: ComponentManifest::read(CORE_LABEL, &core_dir)?,
+        dir: core_dir,
+    }];
+    discovered.extend(settings.apply(discover_components_in(&root.join(MODS_DIR))?));
+    let components_changed = component_loader.load_discovered(
+        &mut shared_comps,
+        &mut app_comps,
+        discovered,
+        progress,
+    )?;
     component_loader.finalize(components_changed, &mut shared_comps)?;
+    // fail early instead of on the first simulation step
+    shared_comps.human_id()?;
+    shared_comps.food_id()?;
+    Ok(LoadedComponents {
+        loader: component_loader,
+        shared: shared_comps,
+        app: app_comps,
+    })
+}
 
-    let human_id = shared_comps
-        .core()?
-        .configs
-        .id_from_raw(CORE_RESOURCE_HUMAN)?;
+/// Directory of the repository, which tests read core and fixtures from.
+#[cfg(test)]
+pub fn test_root() -> &'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap()
+}
+
+/// Directory of the test, unique so concurrent test runs don't overwrite each other's files.
+#[cfg(test)]
+pub fn test_dir(test: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "plemeshko-{test}-{}-{}",
+        std::process::id(),
+        std::time::SystemTime::UNIX_EPOCH
+            .elapsed()
+            .unwrap()
+            .as_nanos()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Core from the repository, loaded regardless of the working directory.
+#[cfg(test)]
+pub fn load_test_core() -> LoadedComponents {
+    let settings = ComponentSettings::default();
+    load_components_in(test_root(), &settings, &LoadingProgress::default()).unwrap()
+}
 
-    let food_id = shared_comps
-        .core()?
-        .configs
-        .id_from_raw(CORE_RESOURCE_FOOD)?;
+/// Loads components enabled in the settings, falling back to core alone when they fail to load,
+/// so a broken component doesn't prevent the game from starting.
+fn load_startup_components(
+    settings: &ComponentSettings,
+    progress: &LoadingProgress,
+) -> Result<LoadedComponents> {
+    load_components(settings, progress).or_else(|e| {
+        log::error!("Loading components failed, loading core only: {e:#}");
+        let core_only = ComponentSettings {
+            disabled: discover_components()?
+                .into_iter()
+                .map(|component| component.label)
+                .collect(),
+            order: Vec::new(),
+        };
+        load_components(&core_only, progress)
+    })
+}
+
+/// Create environments and start loading components, see [`AppState::poll_loading`].
+pub fn initialize_state() -> Result<(Option<rodio::OutputStream>, &'static SharedState, AppState)> {
+    let settings = ComponentSettings::read().unwrap_or_else(|e| {
+        log::error!("Using default component settings: {e:#}");
+        ComponentSettings::default()
+    });
+    let progress = Arc::new(LoadingProgress::default());
+    let thread = std::thread::spawn({
+        let progress = progress.clone();
+        move || load_startup_components(&settings, &progress)
+    });
+    let components = LoadedComponents {
+        loader: ComponentLoader::new()?,
+        shared: SharedComponents::default(),
+        app: AppComponents::default(),
+    };
+
+    // watches loaded components once they replace the empty ones
+    let component_watcher = match std::env::var_os(HOT_RELOAD_ENV_VAR) {
+        Some(_) => {
+            log::info!("Hot-reload mode enabled");
+            Some(ComponentWatcher::new(
+                components.loader.indexer(),
+                &components.app,
+            )?)
+        }
+        None => None,
+    };
 
     let (audio_stream, audio_handle) = Audio::new();
 
     let shared_st: &SharedState = Box::leak(Box::new(SharedState {
-        components: RwLock::new(shared_comps),
+        components: RwLock::new(components.shared),
         sim: Mutex::new(None),
+        autosave: Mutex::new(None),
         audio: audio_handle,
-        human_id: FatConfigId::new_core(human_id),
-        food_id: FatConfigId::new_core(food_id),
     }));
     let app_st = AppState {
         shared: shared_st,
-        components: app_comps,
-        component_loader,
+        components: components.app,
+        component_loader: components.loader,
         session: None,
+        translation: None,
+        component_watcher,
+        loading: Some(ComponentsLoading { progress, thread }),
         fallback_texture: RetainedImage::from_color_image(
             "<fallback>",
             egui::ColorImage::example(),
@@ -114,6 +250,135 @@ pub fn initialize_state() -> Result<(Option<rodio::OutputStream>, &'static Share
 }
 
 impl AppState {
+    /// Replaces the empty components with ones loaded at startup, once they are ready.
+    /// Returns `true` while they are still loading.
+    pub fn poll_loading(&mut self) -> Result<bool> {
+        match &self.loading {
+            Some(loading) if !loading.thread.is_finished() => return Ok(true),
+            Some(_) => (),
+            None => return Ok(false),
+        }
+        let loading = self.loading.take().unwrap();
+        let components = loading
+            .thread
+            .join()
+            .map_err(|_| anyhow!("Loading components panicked"))?
+            .context("Loading components")?;
+        self.replace_components(components)?;
+        Ok(false)
+    }
+
+    /// Part of components loaded at startup, `None` when loading has finished.
+    pub fn loading_progress(&self) -> Option<f32> {
+        self.loading
+            .as_ref()
+            .map(|loading| loading.progress.fraction())
+    }
+
+    /// Replaces all components.
+    /// The running game refers to configs by ids, so it's remapped to the new components
+    /// the same way it would be saved and loaded. Nothing is replaced when that fails.
+    pub fn replace_components(&mut self, components: LoadedComponents) -> Result<()> {
+        let mut sim_guard = self.shared.sim.lock().unwrap();
+        if let Some(sim) = sim_guard.as_ref() {
+            let raw = sim.snapshot().into_serializable(ComponentsRef {
+                indexer: self.component_loader.indexer(),
+                app: &self.components,
+                shared: &self.shared.components.read().unwrap(),
+                dropped: None,
+            })?;
+            let snapshot = SimSnapshot::from_serializable(
+                raw,
+                ComponentsRef {
+                    indexer: components.loader.indexer(),
+                    app: &components.app,
+                    shared: &components.shared,
+                    dropped: None,
+                },
+            )
+            .context("Remapping the running game to new components")?;
+            *sim_guard = Some(Sim::restore(&components.shared, snapshot)?);
+        }
+        *self.shared.components.write().unwrap() = components.shared;
+        drop(sim_guard);
+        self.components = components.app;
+        self.component_loader = components.loader;
+        if let Some(translation) = self.translation.take() {
+            self.switch_translation(translation)?;
+        }
+        if self.component_watcher.is_some() {
+            self.component_watcher = Some(ComponentWatcher::new(
+                self.component_loader.indexer(),
+                &self.components,
+            )?);
+        }
+        Ok(())
+    }
+
+    pub fn unload_component(&mut self, id: ComponentId) -> Result<()> {
+        let mut sim_guard = self.shared.sim.lock().unwrap();
+        self.component_loader.unload(
+            &mut self.shared.components.write().unwrap(),
+            &mut self.components,
+            sim_guard.as_mut(),
+            id,
+        )?;
+        drop(sim_guard);
+        if self.component_watcher.is_some() {
+            self.component_watcher = Some(ComponentWatcher::new(
+                self.component_loader.indexer(),
+                &self.components,
+            )?);
+        }
+        Ok(())
+    }
+
+    /// Reloads components when files of any of them change, in the hot-reload mode.
+    /// All components are rebuilt, since later ones refer to configs of the changed one.
+    /// Returns `true` when components were replaced.
+    pub fn hot_reload(&mut self) -> Result<bool> {
+        let Some(watcher) = &mut self.component_watcher else {
+            return Ok(false);
+        };
+        // files may be inaccessible while being edited, which shouldn't stop the game
+        let changed = match watcher.poll() {
+            Ok(changed) => changed,
+            Err(e) => {
+                log::error!("Checking component files for changes failed: {e:#}");
+                return Ok(false);
+            }
+        };
+        if changed.is_empty() {
+            return Ok(false);
+        }
+        let changed: Vec<_> = changed.iter().map(|label| format!("\"{label}\"")).collect();
+        log::info!("Reloading components, changed: {}", changed.join(", "));
+        // failures are expected while editing, so they are reported without stopping the game
+        let result = ComponentSettings::read()
+            .and_then(|settings| load_components(&settings, &LoadingProgress::default()))
+            .and_then(|components| self.replace_components(components));
+        match result {
+            Ok(()) => Ok(true),
+            Err(e) => {
+                log::error!("Hot reload failed: {e:#}");
+                Ok(false)
+            }
+        }
+    }
+
+    /// Switches core texts to the exact translation and other components' ones to the most similar.
+    pub fn switch_translation(&mut self, translation: LanguageIdentifier) -> Result<()> {
+        for (id, c) in self.components.iter_components_mut() {
+            if id == ComponentId::core() {
+                c.texts.switch_translation_exact(translation.clone())?;
+                continue;
+            }
+            c.texts.switch_translation(translation.clone())?;
+        }
+        self.translation = Some(translation);
+        Ok(())
+    }
+
     pub fn texture(&self, id: FatTextureId) -> Result<&RetainedImage> {
         Ok(self
             .components
@@ -0,0 +1,306 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, RwLock},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    params::REPLAYS_DIR,
+    sim::{
+        command::{RawSimCommand, SimCommand},
+        units::Ticks,
+        RawSimSnapshot, Sim, SimSnapshot,
+    },
+};
+
+use super::{
+    components::{ComponentsRef, SharedComponents},
+    save::{compare_components, component_fingerprints, ComponentFingerprint, SAVE_FORMAT_VERSION},
+    serializable::Serializable,
+    AppState, LoadedComponents, SharedState,
+};
+
+#[derive(Serialize, Deserialize)]
+struct RawCommandRecord {
+    tick: Ticks,
+    command: RawSimCommand,
+}
+
+/// Game recorded from the state it was started or loaded with, along with commands applied since then.
+/// Snapshots are of the save format, but replays aren't migrated.
+#[derive(Serialize, Deserialize)]
+struct RawReplay {
+    format_version: u64,
+    components: Vec<ComponentFingerprint>,
+    start: RawSimSnapshot,
+    commands: Vec<RawCommandRecord>,
+    /// State the replay is expected to reproduce.
+    end: RawSimSnapshot,
+}
+
+fn record_replay(
+    sim: &Sim,
+    components: Vec<ComponentFingerprint>,
+    ctx: ComponentsRef<'_>,
+) -> Result<RawReplay> {
+    Ok(RawReplay {
+        format_version: SAVE_FORMAT_VERSION,
+        components,
+        start: sim.replay_start().clone().into_serializable(ctx)?,
+        commands: sim
+            .commands()
+            .iter()
+            .map(|(tick, command)| {
+                Ok(RawCommandRecord {
+                    tick: *tick,
+                    command: command.clone().into_serializable(ctx)?,
+                })
+            })
+            .collect::<Result<_>>()?,
+        end: sim.snapshot().into_serializable(ctx)?,
+    })
+}
+
+/// Writes the replay of the current game up to now, returning its path.
+pub fn record(app_st: &AppState, shared_comps: &SharedComponents, sim: &Sim) -> Result<PathBuf> {
+    let Some(session) = app_st.session.as_ref() else {
+        bail!("Current session name unknown");
+    };
+    let components = component_fingerprints(app_st.component_loader.indexer(), &app_st.components)?;
+    let replay = record_replay(
+        sim,
+        components,
+        ComponentsRef {
+            indexer: app_st.component_loader.indexer(),
+            app: &app_st.components,
+            shared: shared_comps,
+            dropped: None,
+        },
+    )?;
+    let mut path = PathBuf::from(REPLAYS_DIR);
+    path.push(format!("{}_{}", session.name, sim.tick));
+    path.set_extension("json");
+    std::fs::create_dir_all(REPLAYS_DIR)?;
+    let mut writer = BufWriter::new(File::create(&path)?);
+    serde_json::to_writer_pretty(&mut writer, &replay)?;
+    writer.flush()?;
+    Ok(path)
+}
+
+/// Sorts arrays of labels, which sets of configs are serialized as in arbitrary order.
+fn sort_label_arrays(value: &mut Value) {
+    match value {
+        Value::Array(values) => {
+            values.iter_mut().for_each(sort_label_arrays);
+            if values.iter().all(Value::is_string) {
+                values.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+            }
+        }
+        Value::Object(map) => map.values_mut().for_each(sort_label_arrays),
+        _ => (),
+    }
+}
+
+/// Replays commands from the start of the replay until its end,
+/// checking that the state the game ended with is reproduced.
+fn run_replay(replay: RawReplay, components: LoadedComponents) -> Result<()> {
+    ensure!(
+        replay.format_version == SAVE_FORMAT_VERSION,
+        "Replay format version {} differs from the supported {SAVE_FORMAT_VERSION}",
+        replay.format_version
+    );
+    let changes = compare_components(
+        &replay.components,
+        &component_fingerprints(components.loader.indexer(), &components.app)?,
+    );
+    if !changes.is_empty() {
+        log::warn!(
+            "{} components changed since the replay was recorded, it may not be reproduced",
+            changes.len()
+        );
+    }
+    let mut expected = serde_json::to_value(&replay.end)?;
+    let shared_st = SharedState {
+        components: RwLock::new(components.shared),
+        sim: Mutex::new(None),
+        autosave: Mutex::new(None),
+        audio: None,
+    };
+    let shared_comps = shared_st.components.read().unwrap();
+    let ctx = ComponentsRef {
+        indexer: components.loader.indexer(),
+        app: &components.app,
+        shared: &shared_comps,
+        dropped: None,
+    };
+    let start = SimSnapshot::from_serializable(replay.start, ctx)?;
+    let end_tick = SimSnapshot::from_serializable(replay.end, ctx)?.tick();
+    let commands: Vec<(Ticks, SimCommand)> = replay
+        .commands
+        .into_iter()
+        .map(|record| {
+            Ok((
+                record.tick,
+                SimCommand::from_serializable(record.command, ctx)?,
+            ))
+        })
+        .collect::<Result<_>>()?;
+    let mut sim = Sim::restore(&shared_comps, start)?;
+    drop(shared_comps);
+    let mut commands = commands.into_iter().peekable();
+    loop {
+        while let Some((_, command)) = commands.next_if(|(tick, _)| *tick == sim.tick) {
+            sim.apply(&shared_st.components.read().unwrap(), command)
+                .with_context(|| format!("Applying command at tick {}", sim.tick))?;
+        }
+        if sim.tick >= end_tick {
+            break;
+        }
+        sim.step(&shared_st)
+            .with_context(|| format!("Replaying tick {}", sim.tick))?;
+    }
+    if let Some((tick, _)) = commands.next() {
+        bail!("Command recorded at tick {tick} isn't reached, the replay ends at tick {end_tick}");
+    }
+    let shared_comps = shared_st.components.read().unwrap();
+    let mut actual = serde_json::to_value(sim.snapshot().into_serializable(ComponentsRef {
+        indexer: components.loader.indexer(),
+        app: &components.app,
+        shared: &shared_comps,
+        dropped: None,
+    })?)?;
+    sort_label_arrays(&mut expected);
+    sort_label_arrays(&mut actual);
+    let (Value::Object(expected), Value::Object(actual)) = (expected, actual) else {
+        bail!("Sim snapshot was not a json object when serialized");
+    };
+    let diverged: Vec<&str> = expected
+        .iter()
+        .filter(|(key, value)| actual.get(*key) != Some(value))
+        .map(|(key, _)| key.as_str())
+        .collect();
+    ensure!(
+        diverged.is_empty(),
+        "Replay diverged, state at tick {end_tick} differs in: {}",
+        diverged.join(", ")
+    );
+    Ok(())
+}
+
+/// Replays the replay file, see [`run_replay`].
+pub fn replay(path: &Path, components: LoadedComponents) -> Result<()> {
+    let replay: RawReplay = serde_json::from_reader(BufReader::new(File::open(path)?))
+        .with_context(|| format!("Reading replay {}", path.display()))?;
+    run_replay(replay, components)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        sim::{
+            config::{production_method::FixedProductionMethod, transport_method::TransportMethod},
+            production::ProductionTemplate,
+            units::ResourceAmount,
+        },
+        state::load_test_core,
+    };
+
+    /// Template of the production method with its first unlocked settings and transport.
+    fn template(shared_comps: &SharedComponents, sim: &Sim, label: &str) -> ProductionTemplate {
+        let method = shared_comps
+            .core()
+            .unwrap()
+            .configs
+            .id_from_raw(label)
+            .unwrap()
+            .in_core();
+        let method = FixedProductionMethod::new_unlocked(shared_comps, &sim.research, method)
+            .unwrap()
+            .unwrap();
+        let mut transport = HashMap::new();
+        for transport_method in shared_comps.iter_configs::<TransportMethod>() {
+            let (id, transport_method) = transport_method.unwrap();
+            if sim.research.is_transport_unlocked(id) {
+                transport.entry(transport_method.group).or_insert(id);
+            }
+        }
+        ProductionTemplate {
+            name: label.to_owned(),
+            selected_methods: vec![method],
+            transport,
+        }
+    }
+
+    #[test]
+    fn replays_reproduce_games() {
+        let components = load_test_core();
+        let shared_st = SharedState {
+            components: RwLock::new(components.shared),
+            sim: Mutex::new(None),
+            autosave: Mutex::new(None),
+            audio: None,
+        };
+        let shared_comps = shared_st.components.read().unwrap();
+        let core_configs = &shared_comps.core().unwrap().configs;
+        let human = core_configs.id_from_raw("human").unwrap().in_core();
+        let tech1 = core_configs.id_from_raw("tech1").unwrap().in_core();
+        let mut sim = Sim::new(&shared_comps).unwrap();
+        drop(shared_comps);
+        for tick in 0..40 {
+            let shared_comps = shared_st.components.read().unwrap();
+            let commands = match tick {
+                0 => vec![
+                    SimCommand::SpawnResource {
+                        resource: human,
+                        amount: ResourceAmount(100),
+                    },
+                    SimCommand::StartResearch(tech1),
+                ],
+                // fishing is unlocked by the technology researched by now
+                20 => {
+                    assert!(sim.research.is_researched(tech1));
+                    let template = template(&shared_comps, &sim, "fishing-fish");
+                    vec![
+                        SimCommand::SaveTemplate(template.clone()),
+                        SimCommand::CreateProduction {
+                            template,
+                            count: 1,
+                            index: 0,
+                        },
+                        SimCommand::ActivateProduction { index: 0, delta: 2 },
+                    ]
+                }
+                _ => Vec::new(),
+            };
+            for command in commands {
+                sim.apply(&shared_comps, command).unwrap();
+            }
+            drop(shared_comps);
+            sim.step(&shared_st).unwrap();
+        }
+        assert_eq!(sim.productions[0].active(), 3);
+        assert!(sim.productions[0].last_activated() > 0);
+        let shared_comps = shared_st.components.read().unwrap();
+        let replay = record_replay(
+            &sim,
+            component_fingerprints(components.loader.indexer(), &components.app).unwrap(),
+            ComponentsRef {
+                indexer: components.loader.indexer(),
+                app: &components.app,
+                shared: &shared_comps,
+                dropped: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(replay.commands.len(), 5);
+        run_replay(replay, load_test_core()).unwrap();
+    }
+}
@@ -1,104 +1,300 @@
-use std::{collections::HashSet, ops::AddAssign, sync::RwLock};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::AddAssign,
+    sync::RwLock,
+};
 
 use serde::{Deserialize, Serialize};
 
-use crate::sim::config::{
-    production_method::{ProductionMethod, ProductionMethodId},
-    technology::{Technology, TechnologyBonus, TechnologyId},
-    transport_method::{TransportMethod, TransportMethodId},
+use crate::{
+    params::TECHNOLOGY_LOSS_REFUND_PERCENT,
+    sim::{
+        config::{
+            production_method::{ProductionMethod, ProductionMethodId},
+            setting::{Setting, SettingId},
+            technology::{Technology, TechnologyBonus, TechnologyId},
+            transport_method::{TransportMethod, TransportMethodId},
+        },
+        units::Ticks,
+    },
+};
+
+use super::{
+    components::{ComponentsRef, SharedComponents},
+    config::FatConfigLabel,
+    serializable::{from_serializable_dropping, Serializable},
 };
 
-use super::{components::SharedComponents, config::FatConfigLabel, serializable::Serializable};
+/// Why a researched technology stopped providing its bonuses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TechnologyRevocation {
+    /// Replaced by another researched technology, still counts as researched.
+    Obsolete,
+    /// Forgotten during a collapse, must be researched again.
+    Lost,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RawResearchRecord {
+    technology: FatConfigLabel<Technology>,
+    completed: Ticks,
+    invested: u64,
+    revoked: Option<(Ticks, TechnologyRevocation)>,
+}
+
+/// Entry of the research history, created when a technology is researched.
+#[derive(Clone)]
+pub struct ResearchRecord {
+    pub technology: TechnologyId,
+    /// Tick at which the research was completed.
+    pub completed: Ticks,
+    /// Research points spent on the technology, including interrupted attempts.
+    pub invested: u64,
+    pub revoked: Option<(Ticks, TechnologyRevocation)>,
+}
 
 #[derive(Serialize, Deserialize)]
 pub struct RawResearch {
     current: Option<(FatConfigLabel<Technology>, u64)>,
     finished: HashSet<FatConfigLabel<Technology>>,
-    unlocked_transport: HashSet<FatConfigLabel<TransportMethod>>,
-    unlocked_production: HashSet<FatConfigLabel<ProductionMethod>>,
+    obsolete: HashSet<FatConfigLabel<Technology>>,
+    paused: HashMap<FatConfigLabel<Technology>, u64>,
+    invested: HashMap<FatConfigLabel<Technology>, u64>,
+    history: Vec<RawResearchRecord>,
 }
 
 #[derive(Clone)]
 pub struct Research {
     current: Option<(TechnologyId, u64)>,
     researched: HashSet<TechnologyId>,
+    /// Unlocks are derived from `researched` and `obsolete`, so they aren't serialized.
     unlocked_transport: HashSet<TransportMethodId>,
     unlocked_production: HashSet<ProductionMethodId>,
+    unlocked_settings: HashSet<SettingId>,
+    /// Researched technologies whose bonuses were revoked by a newer technology.
+    obsolete: HashSet<TechnologyId>,
+    /// Progress of interrupted or lost research, restored when it is started again.
+    paused: HashMap<TechnologyId, u64>,
+    /// Points spent on technologies which aren't researched yet.
+    invested: HashMap<TechnologyId, u64>,
+    history: Vec<ResearchRecord>,
+
+    /// Technologies with all prerequisites researched.
+    /// Derived from `researched`, so it isn't serialized.
+    available: HashSet<TechnologyId>,
 }
 
 impl Research {
-    pub fn new(shared_comps: &mut SharedComponents) -> anyhow::Result<Self> {
-        let mut unlocked_production = HashSet::new();
+    pub fn new(shared_comps: &SharedComponents) -> anyhow::Result<Self> {
+        let mut research = Research {
+            current: None,
+            researched: HashSet::new(),
+            unlocked_transport: HashSet::new(),
+            unlocked_production: HashSet::new(),
+            unlocked_settings: HashSet::new(),
+            obsolete: HashSet::new(),
+            paused: HashMap::new(),
+            invested: HashMap::new(),
+            history: Vec::new(),
+            available: HashSet::new(),
+        };
+        research.unlock_initial(shared_comps)?;
+        research.update_availability(shared_comps)?;
+        Ok(research)
+    }
+
+    /// Unlocks methods and settings marked as initially unlocked.
+    fn unlock_initial(&mut self, shared_comps: &SharedComponents) -> anyhow::Result<()> {
         for production_method in shared_comps.iter_configs::<ProductionMethod>() {
             let (production_method_id, production_method) = production_method?;
             if production_method.initially_unlocked {
-                unlocked_production.insert(production_method_id);
+                self.unlocked_production.insert(production_method_id);
             }
         }
-        let mut unlocked_transport = HashSet::new();
         for transport_method in shared_comps.iter_configs::<TransportMethod>() {
             let (transport_method_id, transport_method) = transport_method?;
             if transport_method.initially_unlocked {
-                unlocked_transport.insert(transport_method_id);
+                self.unlocked_transport.insert(transport_method_id);
             }
         }
-        let research = Research {
-            current: None,
-            researched: HashSet::new(),
-            unlocked_transport,
-            unlocked_production,
-        };
-        research.update_technology_satisfaction(shared_comps)?;
-        Ok(research)
+        for setting in shared_comps.iter_configs::<Setting>() {
+            let (setting_id, setting) = setting?;
+            if setting.initially_unlocked {
+                self.unlocked_settings.insert(setting_id);
+            }
+        }
+        Ok(())
     }
 
-    pub fn update_technology_satisfaction(
-        &self,
-        shared_comps: &mut SharedComponents,
-    ) -> anyhow::Result<()> {
-        for technology in shared_comps.iter_configs_mut::<Technology>() {
-            technology?.1.check_prerequisites(self);
+    fn apply_bonuses(&mut self, technology: &Technology) {
+        for bonus in &technology.bonuses {
+            match *bonus {
+                TechnologyBonus::UnlockTransport(tr_id) => self.unlocked_transport.insert(tr_id),
+                TechnologyBonus::UnlockProduction(pr_id) => self.unlocked_production.insert(pr_id),
+                TechnologyBonus::UnlockSetting(st_id) => self.unlocked_settings.insert(st_id),
+            };
+        }
+    }
+
+    /// Rebuilds unlocks from initially unlocked configs and bonuses of effective technologies.
+    /// Used after technologies are revoked, since several technologies may unlock the same thing,
+    /// and on restore, so bonuses added to researched technologies since the save was made apply.
+    fn rebuild_unlocks(&mut self, shared_comps: &SharedComponents) -> anyhow::Result<()> {
+        self.unlocked_transport.clear();
+        self.unlocked_production.clear();
+        self.unlocked_settings.clear();
+        self.unlock_initial(shared_comps)?;
+        for technology in shared_comps.iter_configs::<Technology>() {
+            let (id, technology) = technology?;
+            if self.researched.contains(&id) && !self.obsolete.contains(&id) {
+                self.apply_bonuses(technology);
+            }
+        }
+        Ok(())
+    }
+
+    /// Rebuilds obsolete technologies from the researched ones,
+    /// so technologies obsoleted only by revoked ones become effective again.
+    fn rebuild_obsolete(&mut self, shared_comps: &SharedComponents) -> anyhow::Result<()> {
+        self.obsolete.clear();
+        for technology in shared_comps.iter_configs::<Technology>() {
+            let (id, technology) = technology?;
+            if self.researched.contains(&id)
+                && technology
+                    .obsoleted_by
+                    .iter()
+                    .any(|other_id| self.researched.contains(other_id))
+            {
+                self.obsolete.insert(id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Recalculates which technologies have their prerequisites satisfied.
+    pub fn update_availability(&mut self, shared_comps: &SharedComponents) -> anyhow::Result<()> {
+        self.available.clear();
+        for technology in shared_comps.iter_configs::<Technology>() {
+            let (id, technology) = technology?;
+            if technology.prerequisites_satisfied(self) {
+                self.available.insert(id);
+            }
         }
         Ok(())
     }
 
-    pub fn step(&mut self, shared_comps: &RwLock<SharedComponents>) -> anyhow::Result<()> {
+    fn revoke_record(&mut self, id: TechnologyId, tick: Ticks, revocation: TechnologyRevocation) {
+        if let Some(record) = self
+            .history
+            .iter_mut()
+            .rev()
+            .find(|record| record.technology == id && record.revoked.is_none())
+        {
+            record.revoked = Some((tick, revocation));
+        }
+    }
+
+    pub fn step(
+        &mut self,
+        shared_comps: &RwLock<SharedComponents>,
+        tick: Ticks,
+    ) -> anyhow::Result<()> {
         if let &mut Some((id, ref mut progress)) = &mut self.current {
             progress.add_assign(1);
-            if *progress >= shared_comps.read().unwrap().config(id).unwrap().cost {
+            self.invested.entry(id).or_default().add_assign(1);
+            let shared_comps = shared_comps.read().unwrap();
+            let technology = shared_comps.config(id)?;
+            if *progress >= technology.cost {
                 self.researched.insert(id);
-                let mut shared_comps = shared_comps.write().unwrap();
-                for bonus in &shared_comps.config(id)?.bonuses {
-                    // todo: log when double-unlock
-                    match *bonus {
-                        TechnologyBonus::UnlockTransport(tr_id) => {
-                            self.unlocked_transport.insert(tr_id)
-                        }
-                        TechnologyBonus::UnlockProduction(pr_id) => {
-                            self.unlocked_production.insert(pr_id)
-                        }
-                    };
-                }
-                self.update_technology_satisfaction(&mut shared_comps)?;
+                self.history.push(ResearchRecord {
+                    technology: id,
+                    completed: tick,
+                    invested: self.invested.remove(&id).unwrap_or_default(),
+                    revoked: None,
+                });
                 self.current = None;
+                self.apply_bonuses(technology);
+
+                let mut obsoleted = Vec::new();
+                for other in shared_comps.iter_configs::<Technology>() {
+                    let (other_id, other) = other?;
+                    if other.obsoleted_by.contains(&id)
+                        && self.researched.contains(&other_id)
+                        && self.obsolete.insert(other_id)
+                    {
+                        obsoleted.push(other_id);
+                    }
+                }
+                if !obsoleted.is_empty() {
+                    for other_id in obsoleted {
+                        self.revoke_record(other_id, tick, TechnologyRevocation::Obsolete);
+                    }
+                    self.rebuild_unlocks(&shared_comps)?;
+                }
+                self.update_availability(&shared_comps)?;
             }
         }
         Ok(())
     }
 
-    /// NOTE: Discards technology being researched currently if it exists.
+    /// Forgets researched technologies marked as lost on collapse, revoking their unlocks.
+    /// Part of a lost technology's cost is kept as progress towards researching it again.
+    pub fn collapse(&mut self, shared_comps: &SharedComponents, tick: Ticks) -> anyhow::Result<()> {
+        let mut lost = Vec::new();
+        for technology in shared_comps.iter_configs::<Technology>() {
+            let (id, technology) = technology?;
+            if technology.lost_on_collapse && self.researched.remove(&id) {
+                self.paused
+                    .insert(id, technology.cost * TECHNOLOGY_LOSS_REFUND_PERCENT / 100);
+                lost.push(id);
+            }
+        }
+        if lost.is_empty() {
+            return Ok(());
+        }
+        for id in lost {
+            log::info!(
+                "Technology lost during collapse: {}",
+                shared_comps.config_label(id)?
+            );
+            self.revoke_record(id, tick, TechnologyRevocation::Lost);
+        }
+        self.rebuild_obsolete(shared_comps)?;
+        self.rebuild_unlocks(shared_comps)?;
+        self.update_availability(shared_comps)
+    }
+
+    /// Interrupted research keeps its progress and continues from it when started again.
     pub fn start(&mut self, id: TechnologyId) {
-        self.current = Some((id, 0));
+        if let Some((current_id, progress)) = self.current.take() {
+            self.paused.insert(current_id, progress);
+        }
+        let progress = self.paused.remove(&id).unwrap_or_default();
+        self.current = Some((id, progress));
     }
 
     pub fn current(&self) -> Option<(TechnologyId, u64)> {
         self.current
     }
 
+    pub fn history(&self) -> &[ResearchRecord] {
+        &self.history
+    }
+
     pub fn is_researched(&self, id: TechnologyId) -> bool {
         self.researched.contains(&id)
     }
 
+    pub fn is_obsolete(&self, id: TechnologyId) -> bool {
+        self.obsolete.contains(&id)
+    }
+
+    /// Whether all prerequisites of the technology are researched.
+    pub fn is_available(&self, id: TechnologyId) -> bool {
+        self.available.contains(&id)
+    }
+
     pub fn is_transport_unlocked(&self, id: TransportMethodId) -> bool {
         self.unlocked_transport.contains(&id)
     }
@@ -106,38 +302,103 @@ impl Research {
     pub fn is_production_unlocked(&self, id: ProductionMethodId) -> bool {
         self.unlocked_production.contains(&id)
     }
+
+    pub fn is_setting_unlocked(&self, id: SettingId) -> bool {
+        self.unlocked_settings.contains(&id)
+    }
+}
+
+impl Serializable for ResearchRecord {
+    type Raw = RawResearchRecord;
+
+    fn from_serializable(raw: Self::Raw, ctx: ComponentsRef<'_>) -> anyhow::Result<Self> {
+        Ok(ResearchRecord {
+            technology: Serializable::from_serializable(raw.technology, ctx)?,
+            completed: raw.completed,
+            invested: raw.invested,
+            revoked: raw.revoked,
+        })
+    }
+
+    fn into_serializable(self, ctx: ComponentsRef<'_>) -> anyhow::Result<Self::Raw> {
+        Ok(RawResearchRecord {
+            technology: self.technology.into_serializable(ctx)?,
+            completed: self.completed,
+            invested: self.invested,
+            revoked: self.revoked,
+        })
+    }
 }
 
 impl Serializable for Research {
     type Raw = RawResearch;
 
-    fn from_serializable(
-        raw: Self::Raw,
-        ctx: super::components::ComponentsRef<'_>,
-    ) -> anyhow::Result<Self> {
-        Ok(Research {
+    fn from_serializable(raw: Self::Raw, ctx: ComponentsRef<'_>) -> anyhow::Result<Self> {
+        let mut research = Research {
             current: match raw.current {
-                Some((id, progress)) => Some((Serializable::from_serializable(id, ctx)?, progress)),
+                Some((id, progress)) => ctx
+                    .drop_failed(Serializable::from_serializable(id, ctx))?
+                    .map(|id| (id, progress)),
                 None => None,
             },
-            researched: Serializable::from_serializable(raw.finished, ctx)?,
-            unlocked_transport: Serializable::from_serializable(raw.unlocked_transport, ctx)?,
-            unlocked_production: Serializable::from_serializable(raw.unlocked_production, ctx)?,
-        })
+            researched: from_serializable_dropping(raw.finished, ctx)?,
+            unlocked_transport: HashSet::new(),
+            unlocked_production: HashSet::new(),
+            unlocked_settings: HashSet::new(),
+            obsolete: from_serializable_dropping(raw.obsolete, ctx)?,
+            paused: from_serializable_dropping(raw.paused, ctx)?,
+            invested: from_serializable_dropping(raw.invested, ctx)?,
+            history: from_serializable_dropping(raw.history, ctx)?,
+            available: HashSet::new(),
+        };
+        research.rebuild_unlocks(ctx.shared)?;
+        research.update_availability(ctx.shared)?;
+        Ok(research)
     }
 
-    fn into_serializable(
-        self,
-        ctx: super::components::ComponentsRef<'_>,
-    ) -> anyhow::Result<Self::Raw> {
+    fn into_serializable(self, ctx: ComponentsRef<'_>) -> anyhow::Result<Self::Raw> {
         Ok(RawResearch {
             current: match self.current {
                 Some((id, progress)) => Some((id.into_serializable(ctx)?, progress)),
                 None => None,
             },
             finished: self.researched.into_serializable(ctx)?,
-            unlocked_transport: self.unlocked_transport.into_serializable(ctx)?,
-            unlocked_production: self.unlocked_production.into_serializable(ctx)?,
+            obsolete: self.obsolete.into_serializable(ctx)?,
+            paused: self.paused.into_serializable(ctx)?,
+            invested: self.invested.into_serializable(ctx)?,
+            history: self.history.into_serializable(ctx)?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::load_test_core;
+
+    #[test]
+    fn collapse_restores_technologies_obsoleted_by_lost_ones() {
+        let mut shared_comps = load_test_core().shared;
+        let core_configs = &shared_comps.core().unwrap().configs;
+        let [old, new]: [TechnologyId; 2] =
+            ["tech1", "tech2"].map(|label| core_configs.id_from_raw(label).unwrap().in_core());
+        let fishing: ProductionMethodId =
+            core_configs.id_from_raw("fishing-fish").unwrap().in_core();
+        shared_comps.config_mut(old).unwrap().obsoleted_by.push(new);
+        let shared = RwLock::new(shared_comps);
+        let mut research = Research::new(&shared.read().unwrap()).unwrap();
+        for id in [old, new] {
+            research.start(id);
+            while !research.is_researched(id) {
+                research.step(&shared, Ticks(0)).unwrap();
+            }
+        }
+        assert!(!research.is_production_unlocked(fishing));
+
+        research
+            .collapse(&shared.read().unwrap(), Ticks(0))
+            .unwrap();
+        assert!(!research.is_researched(new));
+        assert!(research.is_production_unlocked(fishing));
+    }
+}
@@ -1,62 +1,655 @@
-use std::{ffi::OsStr, path::PathBuf, time::Duration};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
 
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, ensure, Context, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Map, Value};
 use serde_with::serde_as;
 use time::OffsetDateTime;
 
 use crate::{
-    params::SAVES_DIR,
-    sim::{RawSimSnapshot, Sim, SimSnapshot},
+    params::{AUTOSAVE_INTERVAL_TICKS, AUTOSAVE_SLOTS, SAVES_DIR},
+    sim::{
+        units::{ResourceAmount, Ticks},
+        RawSimSnapshot, Sim, SimSnapshot,
+    },
+    util::parallel_map,
+};
+
+use super::{
+    components::{AppComponents, ComponentIndexer, ComponentsRef},
+    serializable::Serializable,
+    AppState,
 };
 
-use super::{components::ComponentsRef, serializable::Serializable, AppState};
+/// Version of the save format, increased with every change of saves which needs a migration.
+pub const SAVE_FORMAT_VERSION: u64 = 3;
+
+/// Upgrades a save of the format version equal to the migration's index to the next version.
+type Migration = fn(&mut Map<String, Value>) -> Result<()>;
+
+const MIGRATIONS: [Migration; SAVE_FORMAT_VERSION as usize] = [
+    migrate_unversioned,
+    migrate_v1_add_rng,
+    migrate_v2_derive_unlocks,
+];
+
+/// Upgrades metadata read apart from the rest of the save, from compact save headers and metadata files,
+/// like [`MIGRATIONS`] upgrade whole saves.
+const METADATA_MIGRATIONS: [Migration; SAVE_FORMAT_VERSION as usize] =
+    [keep_metadata, keep_metadata, keep_metadata];
+
+/// Start of compact saves, followed by the length of the metadata header.
+const COMPACT_SAVE_MAGIC: &[u8; 8] = b"PLEMSAVE";
+
+/// Limit on the length of metadata headers of compact saves, so corrupt saves don't allocate gigabytes.
+const MAX_COMPACT_HEADER_LEN: u32 = 1 << 20;
+
+/// Separates names of autosaves from numbers of their slots.
+const AUTOSAVE_SUFFIX: &str = "_autosave";
+
+/// Extension of files with metadata of json saves, written next to them to list saves quickly.
+const SAVE_METADATA_EXTENSION: &str = "meta";
+
+/// How a save is stored, selected by the extension of its file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SaveFormat {
+    /// Pretty-printed json with metadata alongside the snapshot, easy to read and edit by hand.
+    #[default]
+    Json,
+    /// Uncompressed json metadata header followed by the gzip-compressed snapshot,
+    /// so saves can be listed without decompressing them.
+    Compact,
+}
+
+impl SaveFormat {
+    /// Formats in the order saves of the same name are looked up in.
+    pub const ALL: [SaveFormat; 2] = [SaveFormat::Json, SaveFormat::Compact];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            SaveFormat::Json => "json",
+            SaveFormat::Compact => "sav",
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<SaveFormat> {
+        let extension = path.extension()?;
+        SaveFormat::ALL
+            .into_iter()
+            .find(|format| extension == format.extension())
+    }
+}
+
+/// Game being played, saved under its name in its format.
+pub struct Session {
+    pub name: String,
+    pub format: SaveFormat,
+}
 
 #[serde_as]
 #[derive(Serialize, Deserialize)]
 pub struct SaveMetadata {
+    pub format_version: u64,
     #[serde(with = "time::serde::iso8601")]
     pub saved_date: OffsetDateTime,
     #[serde_as(as = "serde_with::DurationSeconds<f64>")]
     pub play_time: Duration,
+    /// Components the game was saved with, `None` for saves made before they were recorded.
+    #[serde(default)]
+    pub components: Option<Vec<ComponentFingerprint>>,
+    /// `None` for saves made before summaries were recorded.
+    #[serde(default)]
+    pub summary: Option<SaveSummary>,
+    /// Name of the game the autosave was made for, `None` for other saves.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autosave_of: Option<String>,
+}
+
+/// Overview of the game shown when choosing a save to load.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SaveSummary {
+    pub tick: Ticks,
+    pub population: ResourceAmount,
+}
+
+/// Loaded component, recorded in saves to detect components changed since.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ComponentFingerprint {
+    pub label: String,
+    pub name: String,
+    pub version: semver::Version,
+    /// See [`AppComponent::configs_hash`](super::components::AppComponent::configs_hash).
+    pub configs_hash: String,
+}
+
+/// Difference between components a game was saved with and the loaded ones.
+pub enum ComponentChange {
+    Added(ComponentFingerprint),
+    Removed(ComponentFingerprint),
+    /// Configs of the component changed, so configs the save refers to may be missing.
+    Changed {
+        saved: ComponentFingerprint,
+        loaded: ComponentFingerprint,
+    },
 }
 
+/// Fingerprints of loaded components, ordered by label.
+pub fn component_fingerprints(
+    indexer: &ComponentIndexer,
+    components: &AppComponents,
+) -> Result<Vec<ComponentFingerprint>> {
+    let mut fingerprints: Vec<ComponentFingerprint> = components
+        .iter_components()
+        .map(|(id, component)| {
+            Ok(ComponentFingerprint {
+                label: indexer.label(id)?.to_string(),
+                name: component.manifest.name.clone(),
+                version: component.manifest.version.clone(),
+                configs_hash: component.configs_hash.clone(),
+            })
+        })
+        .collect::<Result<_>>()?;
+    fingerprints.sort_by(|a, b| a.label.cmp(&b.label));
+    Ok(fingerprints)
+}
+
+/// Finds components added, removed or changed since the game was saved, ordered by label.
+/// Components with changed versions but same configs don't affect the save, so they aren't reported.
+pub fn compare_components(
+    saved: &[ComponentFingerprint],
+    loaded: &[ComponentFingerprint],
+) -> Vec<ComponentChange> {
+    let mut pairs =
+        BTreeMap::<&str, (Option<&ComponentFingerprint>, Option<&ComponentFingerprint>)>::new();
+    for fingerprint in saved {
+        pairs.entry(&fingerprint.label).or_default().0 = Some(fingerprint);
+    }
+    for fingerprint in loaded {
+        pairs.entry(&fingerprint.label).or_default().1 = Some(fingerprint);
+    }
+    pairs
+        .into_values()
+        .filter_map(|pair| match pair {
+            (Some(saved), None) => Some(ComponentChange::Removed(saved.clone())),
+            (None, Some(loaded)) => Some(ComponentChange::Added(loaded.clone())),
+            (Some(saved), Some(loaded)) if saved.configs_hash != loaded.configs_hash => {
+                Some(ComponentChange::Changed {
+                    saved: saved.clone(),
+                    loaded: loaded.clone(),
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Saves made before the format was versioned. Fields added since then are set to their defaults.
+fn migrate_unversioned(save: &mut Map<String, Value>) -> Result<()> {
+    save.entry("tick").or_insert(json!(0));
+    save.entry("templates").or_insert(json!([]));
+    let Some(Value::Object(research)) = save.get_mut("research") else {
+        bail!("Save has no research");
+    };
+    research.entry("unlocked_settings").or_insert(json!([]));
+    research.entry("obsolete").or_insert(json!([]));
+    research.entry("paused").or_insert(json!({}));
+    research.entry("invested").or_insert(json!({}));
+    let Value::Array(history) = research.entry("history").or_insert(json!([])) else {
+        bail!("Save's research history isn't an array");
+    };
+    for record in history {
+        let Value::Object(record) = record else {
+            bail!("Save's research history record isn't an object");
+        };
+        record.entry("revoked").or_insert(Value::Null);
+    }
+    Ok(())
+}
+
+/// The sim got its own random number generator, saved for replays to be deterministic.
+fn migrate_v1_add_rng(save: &mut Map<String, Value>) -> Result<()> {
+    save.entry("rng")
+        .or_insert_with(|| json!(rand::random::<u64>()));
+    Ok(())
+}
+
+/// Unlocks are derived from researched technologies on load instead of being saved.
+fn migrate_v2_derive_unlocks(save: &mut Map<String, Value>) -> Result<()> {
+    let Some(Value::Object(research)) = save.get_mut("research") else {
+        bail!("Save has no research");
+    };
+    for unlocks in [
+        "unlocked_transport",
+        "unlocked_production",
+        "unlocked_settings",
+    ] {
+        research.remove(unlocks);
+    }
+    Ok(())
+}
+
+/// Metadata didn't change in the version, fields added to it have defaults.
+fn keep_metadata(_metadata: &mut Map<String, Value>) -> Result<()> {
+    Ok(())
+}
+
+/// Upgrades the save to the current format version, applying migrations from its version onwards.
+pub fn migrate(save: Value) -> Result<Value> {
+    apply_migrations(save, &MIGRATIONS)
+}
+
+/// Upgrades the metadata of a save to the current format version, see [`METADATA_MIGRATIONS`].
+fn migrate_metadata(metadata: Value) -> Result<Value> {
+    apply_migrations(metadata, &METADATA_MIGRATIONS)
+}
+
+fn apply_migrations(save: Value, migrations: &[Migration]) -> Result<Value> {
+    let Value::Object(mut save) = save else {
+        bail!("Save isn't a json object");
+    };
+    let format_version = match save.get("format_version") {
+        Some(format_version) => format_version
+            .as_u64()
+            .ok_or_else(|| anyhow!("Save format version isn't a number: {format_version}"))?,
+        None => 0,
+    };
+    if format_version > SAVE_FORMAT_VERSION {
+        bail!("Save format version {format_version} is newer than supported {SAVE_FORMAT_VERSION}, the save was made by a newer version of the game");
+    }
+    for (version, migration) in migrations.iter().enumerate().skip(format_version as usize) {
+        migration(&mut save)
+            .with_context(|| format!("Migrating save from format version {version}"))?;
+    }
+    save.insert("format_version".to_owned(), SAVE_FORMAT_VERSION.into());
+    Ok(Value::Object(save))
+}
+
+/// Reads the metadata header of a compact save, leaving the reader at the compressed snapshot.
+fn read_compact_header(reader: &mut impl Read) -> Result<Value> {
+    let mut magic = [0; COMPACT_SAVE_MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    ensure!(&magic == COMPACT_SAVE_MAGIC, "Not a compact save");
+    let mut header_len = [0; 4];
+    reader.read_exact(&mut header_len)?;
+    let header_len = u32::from_le_bytes(header_len);
+    ensure!(
+        header_len <= MAX_COMPACT_HEADER_LEN,
+        "Save metadata is {header_len} bytes long, more than {MAX_COMPACT_HEADER_LEN} allowed"
+    );
+    let mut header = vec![0; header_len as usize];
+    reader.read_exact(&mut header)?;
+    Ok(serde_json::from_slice(&header)?)
+}
+
+/// Reads the save, upgrading it to the current format version.
+fn read_save(path: &Path, format: SaveFormat) -> Result<Value> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let save = match format {
+        SaveFormat::Json => serde_json::from_reader(reader)?,
+        SaveFormat::Compact => {
+            let Value::Object(metadata) = read_compact_header(&mut reader)? else {
+                bail!("Save metadata isn't a json object");
+            };
+            let Value::Object(mut save) = serde_json::from_reader(GzDecoder::new(reader))? else {
+                bail!("Save isn't a json object");
+            };
+            save.extend(metadata);
+            Value::Object(save)
+        }
+    };
+    migrate(save).with_context(|| format!("Reading save {}", path.display()))
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Reads only the metadata of the save, from its header or metadata file when there is one.
+fn read_save_metadata(path: &Path, format: SaveFormat) -> Result<SaveMetadata> {
+    let metadata = match format {
+        SaveFormat::Json => {
+            let metadata_path = path.with_extension(SAVE_METADATA_EXTENSION);
+            // metadata files are written after saves, an older one is left from a previous save
+            match (modified_time(&metadata_path), modified_time(path)) {
+                (Some(metadata_modified), Some(save_modified))
+                    if metadata_modified >= save_modified =>
+                {
+                    serde_json::from_reader(BufReader::new(File::open(&metadata_path)?))
+                        .map_err(anyhow::Error::from)
+                        .and_then(migrate_metadata)
+                        .with_context(|| format!("Reading {}", metadata_path.display()))?
+                }
+                _ => read_save(path, format)?,
+            }
+        }
+        SaveFormat::Compact => read_compact_header(&mut BufReader::new(File::open(path)?))
+            .and_then(migrate_metadata)
+            .with_context(|| format!("Reading save {}", path.display()))?,
+    };
+    Ok(serde_json::from_value(metadata)?)
+}
+
+/// Saves with their metadata, the most recent first. Saves which can't be read are logged and skipped.
 pub fn saves() -> Result<Vec<(String, SaveMetadata)>> {
-    let mut saves = Vec::new();
+    let mut entries = Vec::new();
     std::fs::create_dir_all(SAVES_DIR)?;
     for dir_entry in std::fs::read_dir(SAVES_DIR)? {
-        let dir_entry = dir_entry?;
-        let entry_path = dir_entry.path();
-        if entry_path.is_file() && entry_path.extension() == Some(OsStr::new("json")) {
-            let file = std::fs::File::open(&entry_path)?;
-            let reader = std::io::BufReader::new(file);
-            let metadata = serde_json::de::from_reader(reader)?;
-            let name = entry_path
-                .file_stem()
-                .unwrap()
-                .to_string_lossy()
-                .into_owned();
-            saves.push((name, metadata));
+        let entry_path = match dir_entry {
+            Ok(dir_entry) => dir_entry.path(),
+            Err(e) => {
+                log::error!("Listing saves: {e:#}");
+                continue;
+            }
+        };
+        if !entry_path.is_file() {
+            continue;
         }
+        let Some(format) = SaveFormat::from_path(&entry_path) else {
+            continue;
+        };
+        let name = entry_path
+            .file_stem()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        if !is_valid_file_name(&name) {
+            log::warn!(
+                "Save {} is skipped, its name isn't valid",
+                entry_path.display()
+            );
+            continue;
+        }
+        let found = match find_save(&name) {
+            Ok((_, found)) => found,
+            Err(e) => {
+                log::error!("Save {} is skipped: {e:#}", entry_path.display());
+                continue;
+            }
+        };
+        if found != format {
+            log::warn!(
+                "Save {} is shadowed by a save of the same name in another format",
+                entry_path.display()
+            );
+            continue;
+        }
+        entries.push((name, entry_path, format));
     }
+    let mut saves: Vec<_> =
+        parallel_map(entries, |(name, path, format)| {
+            match read_save_metadata(&path, format) {
+                Ok(metadata) => Some((name, metadata)),
+                Err(e) => {
+                    log::error!("Save {} is skipped: {e:#}", path.display());
+                    None
+                }
+            }
+        })
+        .into_iter()
+        .flatten()
+        .collect();
+    saves.sort_by_key(|(_, metadata)| std::cmp::Reverse(metadata.saved_date));
     Ok(saves)
 }
 
-fn save_path_from_name(name: &str) -> PathBuf {
+/// Names consist of ascii letters, digits and underscores, so they can't refer to files outside of saves.
+fn is_valid_file_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Whether the name ends like names of autosaves, see [`autosave_name`].
+fn has_autosave_suffix(name: &str) -> bool {
+    match name.rsplit_once(AUTOSAVE_SUFFIX) {
+        Some((_, slot)) => !slot.is_empty() && slot.chars().all(|c| c.is_ascii_digit()),
+        None => false,
+    }
+}
+
+/// Names which saves can be given, see [`is_valid_file_name`].
+/// The suffix of autosaves is reserved, so that saves aren't taken for autosaves of other games.
+pub fn is_valid_save_name(name: &str) -> bool {
+    is_valid_file_name(name) && !has_autosave_suffix(name)
+}
+
+fn save_path_from_name(name: &str, format: SaveFormat) -> Result<PathBuf> {
+    ensure!(is_valid_file_name(name), "Invalid save name \"{name}\"");
     let mut path = PathBuf::from(SAVES_DIR);
     path.push(name);
-    path.set_extension("json");
-    path
+    path.set_extension(format.extension());
+    Ok(path)
 }
 
-pub fn save(app_st: &AppState) -> Result<()> {
-    let Some(name) = app_st.session.as_ref() else {
-        bail!("Current session name unknown");
+/// Path and format of the existing save with the name, if there is one.
+fn try_find_save(name: &str) -> Result<Option<(PathBuf, SaveFormat)>> {
+    for format in SaveFormat::ALL {
+        let path = save_path_from_name(name, format)?;
+        if path.is_file() {
+            return Ok(Some((path, format)));
+        }
+    }
+    Ok(None)
+}
+
+/// Path and format of the existing save with the name.
+fn find_save(name: &str) -> Result<(PathBuf, SaveFormat)> {
+    try_find_save(name)?.ok_or_else(|| anyhow!("Save \"{name}\" not found"))
+}
+
+fn ensure_save_missing(name: &str) -> Result<()> {
+    ensure!(
+        try_find_save(name)?.is_none(),
+        "Save \"{name}\" already exists"
+    );
+    Ok(())
+}
+
+/// Files of the existing save, along with its metadata file when there is one.
+fn save_files(name: &str) -> Result<(SaveFormat, Vec<PathBuf>)> {
+    let (path, format) = find_save(name)?;
+    let metadata_path = path.with_extension(SAVE_METADATA_EXTENSION);
+    let mut files = vec![path];
+    if format == SaveFormat::Json && metadata_path.is_file() {
+        files.push(metadata_path);
+    }
+    Ok((format, files))
+}
+
+/// Paths the files of the save are moved or copied to, to become the save with the new name.
+fn renamed_save_files(new_name: &str, files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    ensure_save_missing(new_name)?;
+    files
+        .iter()
+        .map(|file| {
+            let mut path = PathBuf::from(SAVES_DIR);
+            path.push(new_name);
+            path.set_extension(file.extension().unwrap());
+            Ok(path)
+        })
+        .collect()
+}
+
+pub fn delete(name: &str) -> Result<()> {
+    for file in save_files(name)?.1 {
+        std::fs::remove_file(&file).with_context(|| format!("Removing {}", file.display()))?;
+    }
+    Ok(())
+}
+
+pub fn rename(name: &str, new_name: &str) -> Result<()> {
+    let (_, files) = save_files(name)?;
+    for (file, new_file) in files.iter().zip(renamed_save_files(new_name, &files)?) {
+        std::fs::rename(file, &new_file)
+            .with_context(|| format!("Renaming {} to {}", file.display(), new_file.display()))?;
+    }
+    Ok(())
+}
+
+pub fn duplicate(name: &str, new_name: &str) -> Result<()> {
+    let (_, files) = save_files(name)?;
+    for (file, new_file) in files.iter().zip(renamed_save_files(new_name, &files)?) {
+        std::fs::copy(file, &new_file)
+            .with_context(|| format!("Copying {} to {}", file.display(), new_file.display()))?;
+    }
+    Ok(())
+}
+
+/// Copies the save out of the saves directory, adding the extension of its format to the path if it has none.
+pub fn export(name: &str, path: &Path) -> Result<()> {
+    let (save_path, format) = find_save(name)?;
+    let mut path = path.to_owned();
+    if path.extension().is_none() {
+        path.set_extension(format.extension());
+    }
+    std::fs::copy(&save_path, &path)
+        .with_context(|| format!("Exporting save \"{name}\" to {}", path.display()))?;
+    Ok(())
+}
+
+/// Copies the save file into the saves directory, naming the save after the file.
+pub fn import(path: &Path) -> Result<String> {
+    let format = SaveFormat::from_path(path).ok_or_else(|| {
+        anyhow!(
+            "Unknown save format of {}, expected one of the extensions: {}",
+            path.display(),
+            SaveFormat::ALL.map(SaveFormat::extension).join(", ")
+        )
+    })?;
+    let name = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .into_owned();
+    ensure!(
+        is_valid_save_name(&name),
+        "File name of {} isn't a valid save name",
+        path.display()
+    );
+    ensure_save_missing(&name)?;
+    // the save is checked to be readable before it's listed
+    read_save_metadata(path, format)?;
+    std::fs::create_dir_all(SAVES_DIR)?;
+    std::fs::copy(path, save_path_from_name(&name, format)?)
+        .with_context(|| format!("Importing save {}", path.display()))?;
+    Ok(name)
+}
+
+/// Save as written, metadata alongside the snapshot.
+#[derive(Serialize)]
+struct SaveRef<'a> {
+    #[serde(flatten)]
+    metadata: &'a SaveMetadata,
+    #[serde(flatten)]
+    sim: &'a RawSimSnapshot,
+}
+
+fn write_save(
+    mut writer: impl Write,
+    format: SaveFormat,
+    metadata: &SaveMetadata,
+    sim: &RawSimSnapshot,
+) -> Result<()> {
+    match format {
+        SaveFormat::Json => {
+            serde_json::to_writer_pretty(writer, &SaveRef { metadata, sim })?;
+        }
+        SaveFormat::Compact => {
+            let header = serde_json::to_vec(metadata)?;
+            writer.write_all(COMPACT_SAVE_MAGIC)?;
+            writer.write_all(&u32::try_from(header.len())?.to_le_bytes())?;
+            writer.write_all(&header)?;
+            let mut encoder = GzEncoder::new(writer, Compression::default());
+            serde_json::to_writer(&mut encoder, sim)?;
+            encoder.finish()?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the file next to its destination first and moves it into place,
+/// so that the previous file is kept intact if writing fails midway.
+fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<()>,
+) -> Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let temp_path = PathBuf::from(temp_path);
+    let mut writer = BufWriter::new(File::create(&temp_path)?);
+    write(&mut writer)?;
+    writer.into_inner()?.sync_all()?;
+    std::fs::rename(&temp_path, path)
+        .with_context(|| format!("Replacing {} with {}", path.display(), temp_path.display()))
+}
+
+fn write_snapshot(
+    app_st: &AppState,
+    name: &str,
+    format: SaveFormat,
+    sim: SimSnapshot,
+    autosave_of: Option<&str>,
+) -> Result<()> {
+    let shared_comps = app_st.shared.components.read().unwrap();
+    let summary = SaveSummary {
+        tick: sim.tick(),
+        population: sim
+            .depot()
+            .get(&shared_comps.human_id()?)
+            .copied()
+            .unwrap_or_default(),
     };
     let metadata = SaveMetadata {
+        format_version: SAVE_FORMAT_VERSION,
         saved_date: time::OffsetDateTime::now_utc(),
         play_time: Duration::ZERO,
+        components: Some(component_fingerprints(
+            app_st.component_loader.indexer(),
+            &app_st.components,
+        )?),
+        summary: Some(summary),
+        autosave_of: autosave_of.map(str::to_owned),
+    };
+    let sim = sim.into_serializable(ComponentsRef {
+        indexer: app_st.component_loader.indexer(),
+        app: &app_st.components,
+        shared: &shared_comps,
+        dropped: None,
+    })?;
+    std::fs::create_dir_all(SAVES_DIR)?;
+    let path = save_path_from_name(name, format)?;
+    write_atomically(&path, |writer| write_save(writer, format, &metadata, &sim))?;
+    if format == SaveFormat::Json {
+        write_atomically(&path.with_extension(SAVE_METADATA_EXTENSION), |writer| {
+            Ok(serde_json::to_writer(writer, &metadata)?)
+        })?;
+    }
+    Ok(())
+}
+
+/// Saves the snapshot of the current game under the session's name.
+pub fn save_snapshot(app_st: &AppState, sim: SimSnapshot) -> Result<()> {
+    let Some(session) = app_st.session.as_ref() else {
+        bail!("Current session name unknown");
+    };
+    write_snapshot(app_st, &session.name, session.format, sim, None)
+}
+
+pub fn save_exists(name: &str) -> Result<bool> {
+    Ok(try_find_save(name)?.is_some())
+}
+
+/// Saves the current game under the new name, which it's saved under from then on.
+pub fn save_as(app_st: &mut AppState, name: &str) -> Result<()> {
+    ensure_save_missing(name)?;
+    let Some(session) = app_st.session.as_ref() else {
+        bail!("Current session name unknown");
     };
     let sim = app_st
         .shared
@@ -65,46 +658,348 @@ pub fn save(app_st: &AppState) -> Result<()> {
         .unwrap()
         .as_ref()
         .unwrap()
-        .snapshot()
-        .into_serializable(ComponentsRef {
-            indexer: app_st.component_loader.indexer(),
-            app: &app_st.components,
-            shared: &app_st.shared.components.read().unwrap(),
-        })?;
-    let Value::Object(metadata) = serde_json::to_value(metadata)? else {
-        bail!("Save metadata was not a json object when serialized");
-    };
-    let Value::Object(mut save) = serde_json::to_value(sim)? else {
-        bail!("Sim snapshot was not a json object when serialized");
-    };
-    save.extend(metadata);
-    let save = Value::Object(save);
-    std::fs::create_dir_all(SAVES_DIR)?;
-    let path = save_path_from_name(name);
-    let mut file = std::fs::File::create(path)?;
-    serde_json::to_writer_pretty(&mut file, &save)?;
+        .snapshot();
+    write_snapshot(app_st, name, session.format, sim, None)?;
+    app_st.session.as_mut().unwrap().name = name.to_owned();
     Ok(())
 }
 
-pub fn load(name: &str, app_st: &mut AppState) -> Result<()> {
+pub fn save(app_st: &AppState) -> Result<()> {
+    let sim = app_st
+        .shared
+        .sim
+        .lock()
+        .unwrap()
+        .as_ref()
+        .unwrap()
+        .snapshot();
+    save_snapshot(app_st, sim)
+}
+
+fn autosave_name(name: &str, slot: i64) -> String {
+    format!("{name}{AUTOSAVE_SUFFIX}{slot}")
+}
+
+/// Name of the game the save belongs to, which is the save's own name unless it's an autosave.
+/// Autosaves are told by their metadata, renamed ones belong to games of their own.
+fn game_name<'a>(save_name: &'a str, metadata: &'a SaveMetadata) -> &'a str {
+    match &metadata.autosave_of {
+        Some(game)
+            if save_name
+                .strip_prefix(game.as_str())
+                .is_some_and(has_autosave_suffix) =>
+        {
+            game
+        }
+        _ => save_name,
+    }
+}
+
+/// Saves the snapshot taken by the sim thread to the autosave slot of its tick,
+/// so that slots are overwritten in turn.
+pub fn autosave(app_st: &AppState, sim: SimSnapshot) -> Result<()> {
+    let Some(session) = app_st.session.as_ref() else {
+        bail!("Current session name unknown");
+    };
+    let slot = sim.tick().0 / AUTOSAVE_INTERVAL_TICKS % AUTOSAVE_SLOTS;
+    write_snapshot(
+        app_st,
+        &autosave_name(&session.name, slot),
+        session.format,
+        sim,
+        Some(&session.name),
+    )
+}
+
+/// Loads the save, dropping parts of the game referring to missing configs when `drop_missing` is set,
+/// like productions using removed production methods.
+pub fn load(name: &str, app_st: &mut AppState, drop_missing: bool) -> Result<()> {
     std::fs::create_dir_all(SAVES_DIR)?;
-    let path = save_path_from_name(name);
-    let file = std::fs::File::open(path)?;
-    let reader = std::io::BufReader::new(file);
-    let sim: RawSimSnapshot = serde_json::de::from_reader(reader)?;
+    let (path, format) = find_save(name)?;
+    let save = read_save(&path, format)?;
+    let metadata = SaveMetadata::deserialize(&save)?;
+    let sim: RawSimSnapshot = serde_json::from_value(save)?;
+    let dropped = RefCell::new(Vec::new());
     let sim: SimSnapshot = Serializable::from_serializable(
         sim,
         ComponentsRef {
             indexer: app_st.component_loader.indexer(),
             app: &app_st.components,
             shared: &app_st.shared.components.read().unwrap(),
+            dropped: drop_missing.then_some(&dropped),
         },
     )?;
-    let mut shared_comps = app_st.shared.components.write().unwrap();
-    let sim = Sim::restore(&shared_comps, sim)?;
-    sim.research
-        .update_technology_satisfaction(&mut shared_comps)?;
-    app_st.session = Some(name.to_owned());
+    let dropped = dropped.into_inner();
+    if !dropped.is_empty() {
+        log::warn!(
+            "Dropped parts of save \"{name}\" referring to missing configs:\n{}",
+            dropped.join("\n")
+        );
+    }
+    let sim = Sim::restore(&app_st.shared.components.read().unwrap(), sim)?;
+    // games continued from autosaves are saved to their own saves
+    app_st.session = Some(Session {
+        name: game_name(name, &metadata).to_owned(),
+        format,
+    });
     *app_st.shared.sim.lock().unwrap() = Some(sim);
+    // snapshot of the replaced game
+    *app_st.shared.autosave.lock().unwrap() = None;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::RwLock};
+
+    use super::*;
+    use crate::{
+        sim::{
+            command::SimCommand,
+            config::{production_method::FixedProductionMethod, transport_method::TransportMethod},
+            production::Production,
+        },
+        state::{load_test_core, test_dir, test_root, LoadedComponents},
+    };
+
+    /// Saves made before the format was versioned.
+    fn fixtures() -> [PathBuf; 2] {
+        ["saves/test.json", "saves/test2.json"].map(|fixture| test_root().join(fixture))
+    }
+
+    fn components_ref(components: &LoadedComponents) -> ComponentsRef<'_> {
+        ComponentsRef {
+            indexer: components.loader.indexer(),
+            app: &components.app,
+            shared: &components.shared,
+            dropped: None,
+        }
+    }
+
+    #[test]
+    fn fixture_saves_load() {
+        let components = load_test_core();
+        for fixture in fixtures() {
+            let save = read_save(&fixture, SaveFormat::Json).unwrap();
+            let metadata: SaveMetadata = serde_json::from_value(save.clone()).unwrap();
+            assert_eq!(metadata.format_version, SAVE_FORMAT_VERSION);
+            let raw: RawSimSnapshot = serde_json::from_value(save).unwrap();
+            let snapshot =
+                SimSnapshot::from_serializable(raw, components_ref(&components)).unwrap();
+            Sim::restore(&components.shared, snapshot).unwrap();
+        }
+    }
+
+    #[test]
+    fn fixture_saves_round_trip() {
+        let components = load_test_core();
+        for fixture in fixtures() {
+            let raw: RawSimSnapshot =
+                serde_json::from_value(read_save(&fixture, SaveFormat::Json).unwrap()).unwrap();
+            let snapshot =
+                SimSnapshot::from_serializable(raw, components_ref(&components)).unwrap();
+            let raw = snapshot
+                .into_serializable(components_ref(&components))
+                .unwrap();
+            let mut save = serde_json::to_value(raw).unwrap();
+            save["format_version"] = SAVE_FORMAT_VERSION.into();
+            // saves of the current version aren't changed by migrations
+            assert_eq!(migrate(save.clone()).unwrap(), save);
+        }
+    }
+
+    #[test]
+    fn unversioned_saves_are_migrated() {
+        let [fixture, _] = fixtures();
+        let save: Value = serde_json::from_str(&std::fs::read_to_string(fixture).unwrap()).unwrap();
+        assert!(save.get("format_version").is_none());
+        let save = migrate(save).unwrap();
+        assert_eq!(save["format_version"], json!(SAVE_FORMAT_VERSION));
+        assert_eq!(save["tick"], json!(0));
+        assert_eq!(save["templates"], json!([]));
+        assert_eq!(save["research"]["history"], json!([]));
+    }
+
+    #[test]
+    fn compact_saves_match_json() {
+        let components = load_test_core();
+        let metadata = SaveMetadata {
+            format_version: SAVE_FORMAT_VERSION,
+            saved_date: OffsetDateTime::UNIX_EPOCH,
+            play_time: Duration::ZERO,
+            components: Some(Vec::new()),
+            summary: None,
+            autosave_of: None,
+        };
+        let dir = test_dir("compact-saves");
+        for fixture in fixtures() {
+            let raw: RawSimSnapshot =
+                serde_json::from_value(read_save(&fixture, SaveFormat::Json).unwrap()).unwrap();
+            let saves = SaveFormat::ALL.map(|format| {
+                let path = dir.join("save").with_extension(format.extension());
+                write_save(File::create(&path).unwrap(), format, &metadata, &raw).unwrap();
+                let save_metadata = read_save_metadata(&path, format).unwrap();
+                assert_eq!(save_metadata.components, metadata.components);
+                read_save(&path, format).unwrap()
+            });
+            assert_eq!(saves[0], saves[1]);
+            let raw: RawSimSnapshot = serde_json::from_value(saves[1].clone()).unwrap();
+            let snapshot =
+                SimSnapshot::from_serializable(raw, components_ref(&components)).unwrap();
+            Sim::restore(&components.shared, snapshot).unwrap();
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn compact_save_headers_are_migrated() {
+        let dir = test_dir("compact-headers");
+        let path = dir
+            .join("save")
+            .with_extension(SaveFormat::Compact.extension());
+        let raw: RawSimSnapshot =
+            serde_json::from_value(read_save(&fixtures()[0], SaveFormat::Json).unwrap()).unwrap();
+        for (format_version, migrated) in [
+            (SAVE_FORMAT_VERSION - 1, true),
+            (SAVE_FORMAT_VERSION + 1, false),
+        ] {
+            let metadata = SaveMetadata {
+                format_version,
+                saved_date: OffsetDateTime::UNIX_EPOCH,
+                play_time: Duration::ZERO,
+                components: None,
+                summary: None,
+                autosave_of: None,
+            };
+            write_save(
+                File::create(&path).unwrap(),
+                SaveFormat::Compact,
+                &metadata,
+                &raw,
+            )
+            .unwrap();
+            let read = read_save_metadata(&path, SaveFormat::Compact);
+            assert_eq!(read.is_ok(), migrated);
+            if let Ok(read) = read {
+                assert_eq!(read.format_version, SAVE_FORMAT_VERSION);
+            }
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn oversized_compact_save_headers_are_rejected() {
+        let mut save = COMPACT_SAVE_MAGIC.to_vec();
+        save.extend_from_slice(&u32::MAX.to_le_bytes());
+        let error = read_compact_header(&mut save.as_slice()).unwrap_err();
+        assert!(error.to_string().contains("more than"));
+    }
+
+    #[test]
+    fn newer_saves_are_rejected() {
+        let save = json!({ "format_version": SAVE_FORMAT_VERSION + 1 });
+        assert!(migrate(save).is_err());
+    }
+
+    #[test]
+    fn productions_with_missing_configs_are_dropped_whole() {
+        let components = load_test_core();
+        let mut save = read_save(&fixtures()[0], SaveFormat::Json).unwrap();
+        save["productions"][0]["transport"]["/solid"] = json!("/missing");
+        let raw = || serde_json::from_value::<RawSimSnapshot>(save.clone()).unwrap();
+        assert!(SimSnapshot::from_serializable(raw(), components_ref(&components)).is_err());
+        let dropped = RefCell::new(Vec::new());
+        let snapshot = SimSnapshot::from_serializable(
+            raw(),
+            ComponentsRef {
+                dropped: Some(&dropped),
+                ..components_ref(&components)
+            },
+        )
+        .unwrap();
+        assert_eq!(dropped.into_inner().len(), 1);
+        let sim = Sim::restore(&components.shared, snapshot).unwrap();
+        assert!(sim.productions.is_empty());
+    }
+
+    #[test]
+    fn saves_load_after_collapse() {
+        let components = load_test_core();
+        let shared = RwLock::new(components.shared);
+        let shared_comps = shared.read().unwrap();
+        let core_configs = &shared_comps.core().unwrap().configs;
+        let mut sim = Sim::new(&shared_comps).unwrap();
+        for technology in ["tech1", "tech2"] {
+            let id = core_configs.id_from_raw(technology).unwrap().in_core();
+            sim.apply(&shared_comps, SimCommand::StartResearch(id))
+                .unwrap();
+            while !sim.research.is_researched(id) {
+                sim.research.step(&shared, sim.tick).unwrap();
+            }
+        }
+        let method = FixedProductionMethod::new_unlocked(
+            &shared_comps,
+            &sim.research,
+            core_configs
+                .id_from_raw("handicraft-production")
+                .unwrap()
+                .in_core(),
+        )
+        .unwrap()
+        .unwrap();
+        let mut transport = HashMap::new();
+        for transport_method in shared_comps.iter_configs::<TransportMethod>() {
+            let (id, transport_method) = transport_method.unwrap();
+            if sim.research.is_transport_unlocked(id) {
+                transport.entry(transport_method.group).or_insert(id);
+            }
+        }
+        let production = Production::new(
+            &shared_comps,
+            &sim.research,
+            "instruments".to_owned(),
+            vec![method],
+            transport,
+        )
+        .unwrap();
+        sim.productions.push(production);
+        sim.research.collapse(&shared_comps, sim.tick).unwrap();
+        assert!(sim.productions[0].is_locked(&shared_comps, &sim.research));
+
+        let ctx = ComponentsRef {
+            indexer: components.loader.indexer(),
+            app: &components.app,
+            shared: &shared_comps,
+            dropped: None,
+        };
+        let raw = sim.snapshot().into_serializable(ctx).unwrap();
+        let raw: RawSimSnapshot =
+            serde_json::from_value(serde_json::to_value(raw).unwrap()).unwrap();
+        let snapshot = SimSnapshot::from_serializable(raw, ctx).unwrap();
+        let sim = Sim::restore(&shared_comps, snapshot).unwrap();
+        assert!(sim.productions[0].is_locked(&shared_comps, &sim.research));
+    }
+
+    #[test]
+    fn autosaves_are_told_by_metadata() {
+        let metadata = |autosave_of: Option<&str>| SaveMetadata {
+            format_version: SAVE_FORMAT_VERSION,
+            saved_date: OffsetDateTime::UNIX_EPOCH,
+            play_time: Duration::ZERO,
+            components: None,
+            summary: None,
+            autosave_of: autosave_of.map(str::to_owned),
+        };
+        assert!(!is_valid_save_name(&autosave_name("foo", 1)));
+        assert!(is_valid_save_name("foo_autosave"));
+        assert!(is_valid_save_name("foo_autosave_1"));
+        assert_eq!(game_name("foo_autosave1", &metadata(None)), "foo_autosave1");
+        assert_eq!(game_name("foo_autosave1", &metadata(Some("foo"))), "foo");
+        // renamed autosaves belong to games of their own
+        assert_eq!(game_name("bar", &metadata(Some("foo"))), "bar");
+        assert_eq!(
+            game_name("bar_autosave1", &metadata(Some("foo"))),
+            "bar_autosave1"
+        );
+    }
+}
@@ -35,12 +35,45 @@ macro_rules! trivially_serializable {
     };
 }
 
+trivially_serializable!(u64);
+
+/// Deserializes elements of the collection one by one, dropping failed ones when dropping is enabled,
+/// see [`ComponentsRef::drop_failed`].
+/// Meant for elements which don't depend on each other, like productions of the game.
+/// Collections are otherwise deserialized as a whole, so a failed entry fails the value it's part of.
+pub fn from_serializable_dropping<T: Serializable, C: FromIterator<T>>(
+    raw: impl IntoIterator<Item = T::Raw>,
+    ctx: ComponentsRef<'_>,
+) -> Result<C> {
+    raw.into_iter()
+        .filter_map(|r| ctx.drop_failed(T::from_serializable(r, ctx)).transpose())
+        .try_collect()
+}
+
+impl<A: Serializable, B: Serializable> Serializable for (A, B) {
+    type Raw = (A::Raw, B::Raw);
+
+    fn from_serializable(raw: Self::Raw, ctx: ComponentsRef<'_>) -> Result<Self> {
+        Ok((
+            A::from_serializable(raw.0, ctx)?,
+            B::from_serializable(raw.1, ctx)?,
+        ))
+    }
+
+    fn into_serializable(self, ctx: ComponentsRef<'_>) -> Result<Self::Raw> {
+        Ok((
+            self.0.into_serializable(ctx)?,
+            self.1.into_serializable(ctx)?,
+        ))
+    }
+}
+
 impl<T: Serializable> Serializable for Vec<T> {
     type Raw = Vec<T::Raw>;
 
     fn from_serializable(raw: Self::Raw, ctx: ComponentsRef<'_>) -> Result<Self> {
         raw.into_iter()
-            .map(|r| Serializable::from_serializable(r, ctx))
+            .map(|r| T::from_serializable(r, ctx))
             .try_collect()
     }
 
@@ -0,0 +1,134 @@
+use std::{
+    ffi::OsStr,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{ensure, Result};
+
+use crate::{
+    params::TEMPLATES_DIR,
+    sim::production::{ProductionTemplate, RawProductionTemplate},
+};
+
+use super::{components::ComponentsRef, serializable::Serializable};
+
+/// Name of the file a template is exported to, made of the template's name.
+/// Characters which may not be allowed in file names are replaced, so names of templates are free text.
+fn template_file_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, ' ' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// File names templates are exported to, see [`template_file_name`].
+fn is_valid_template_file_name(file_name: &str) -> bool {
+    !file_name.is_empty() && template_file_name(file_name) == file_name
+}
+
+fn templates_in(dir: &Path) -> Result<Vec<String>> {
+    let mut templates = Vec::new();
+    let dir_entries = match std::fs::read_dir(dir) {
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(templates),
+        dir_entries => dir_entries?,
+    };
+    for dir_entry in dir_entries {
+        let entry_path = dir_entry?.path();
+        if entry_path.is_file() && entry_path.extension() == Some(OsStr::new("json")) {
+            let name = entry_path
+                .file_stem()
+                .unwrap()
+                .to_string_lossy()
+                .into_owned();
+            if is_valid_template_file_name(&name) {
+                templates.push(name);
+            }
+        }
+    }
+    templates.sort();
+    Ok(templates)
+}
+
+/// Names of files of production templates exported before.
+/// Files with names templates aren't exported to are skipped, since they couldn't be imported.
+pub fn templates() -> Result<Vec<String>> {
+    templates_in(Path::new(TEMPLATES_DIR))
+}
+
+fn template_path(dir: &Path, file_name: &str) -> Result<PathBuf> {
+    ensure!(
+        is_valid_template_file_name(file_name),
+        "Invalid template file name \"{file_name}\""
+    );
+    let mut path = dir.join(file_name);
+    path.set_extension("json");
+    Ok(path)
+}
+
+fn export_to(dir: &Path, template: ProductionTemplate, ctx: ComponentsRef<'_>) -> Result<()> {
+    let path = template_path(dir, &template_file_name(&template.name))?;
+    let template = template.into_serializable(ctx)?;
+    std::fs::create_dir_all(dir)?;
+    let mut file = std::fs::File::create(path)?;
+    serde_json::to_writer_pretty(&mut file, &template)?;
+    Ok(())
+}
+
+/// Writes template to a file, so it can be imported into other saves.
+pub fn export(template: ProductionTemplate, ctx: ComponentsRef<'_>) -> Result<()> {
+    export_to(Path::new(TEMPLATES_DIR), template, ctx)
+}
+
+fn import_from(dir: &Path, file_name: &str, ctx: ComponentsRef<'_>) -> Result<ProductionTemplate> {
+    let file = std::fs::File::open(template_path(dir, file_name)?)?;
+    let reader = std::io::BufReader::new(file);
+    let template: RawProductionTemplate = serde_json::de::from_reader(reader)?;
+    Serializable::from_serializable(template, ctx)
+}
+
+/// Reads the template from the file listed by [`templates`].
+pub fn import(file_name: &str, ctx: ComponentsRef<'_>) -> Result<ProductionTemplate> {
+    import_from(Path::new(TEMPLATES_DIR), file_name, ctx)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::state::{load_test_core, test_dir};
+
+    #[test]
+    fn templates_named_with_spaces_are_imported_back() {
+        let components = load_test_core();
+        let ctx = ComponentsRef {
+            indexer: components.loader.indexer(),
+            app: &components.app,
+            shared: &components.shared,
+            dropped: None,
+        };
+        let dir = test_dir("templates");
+        let templates_dir = dir.join("templates");
+        assert!(templates_in(&templates_dir).unwrap().is_empty());
+        for name in ["Hunting House", "Охотничий дом", "a/../b"] {
+            let template = ProductionTemplate {
+                name: name.to_owned(),
+                selected_methods: Vec::new(),
+                transport: HashMap::new(),
+            };
+            export_to(&templates_dir, template, ctx).unwrap();
+        }
+        let file_names = templates_in(&templates_dir).unwrap();
+        assert_eq!(file_names, ["Hunting House", "a____b", "Охотничий дом"]);
+        let template = import_from(&templates_dir, &file_names[0], ctx).unwrap();
+        assert_eq!(template.name, "Hunting House");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
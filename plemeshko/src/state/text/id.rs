@@ -27,6 +27,10 @@ pub struct TextIdFactory(LabelFactory);
 unsafe impl TransparentWrapper<LabelFactory> for TextIdFactory {}
 
 impl TextId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
     pub fn in_component(self, component_id: ComponentId) -> FatTextId {
         FatTextId(component_id, self)
     }
@@ -12,14 +12,12 @@ use std::{
     str::FromStr,
 };
 
-use fluent::*;
+use fluent::{concurrent::FluentBundle, *};
 use fluent_syntax::parser::ParserError;
 use unic_langid::{LanguageIdentifier, LanguageIdentifierError};
 
-use crate::params::{COMPONENT_TEXTS_DIR, CORE_DIR};
-
 pub struct TextRepository {
-    directory: PathBuf,
+    directories: Vec<PathBuf>,
     bundle: FluentBundle<FluentResource>,
 }
 
@@ -80,9 +78,11 @@ fn calc_langid_similarity(x: &LanguageIdentifier, y: &LanguageIdentifier) -> Opt
     Some(score)
 }
 
+/// Loads all files in the directory and its subdirectories, optionally overriding messages already in the bundle.
 fn load_directory(
     bundle: &mut FluentBundle<FluentResource>,
     path: PathBuf,
+    overriding: bool,
 ) -> Result<(), LoadTranslationError> {
     let dir_iter = std::fs::read_dir(path).map_err(LoadTranslationError::Io)?;
     for dir_entry in dir_iter {
@@ -92,21 +92,47 @@ fn load_directory(
             let source = std::fs::read_to_string(entry_path).map_err(LoadTranslationError::Io)?;
             let resource = FluentResource::try_new(source)
                 .map_err(|(r, e)| LoadTranslationError::Parsing(r, e))?;
-            bundle
-                .add_resource(resource)
-                .map_err(LoadTranslationError::ResourceRegistration)?;
+            if overriding {
+                bundle.add_resource_overriding(resource);
+            } else {
+                bundle
+                    .add_resource(resource)
+                    .map_err(LoadTranslationError::ResourceRegistration)?;
+            }
         } else if entry_path.is_dir() {
-            load_directory(bundle, entry_path)?;
+            load_directory(bundle, entry_path, overriding)?;
         }
     }
     Ok(())
 }
 
+/// Lists subdirectories' names parsed as language identifiers along with their full paths.
+fn read_translations(
+    dir: &Path,
+) -> Result<Vec<(LanguageIdentifier, PathBuf)>, TextRepositoryCreationError> {
+    let mut translations = Vec::new();
+    for dir_entry in std::fs::read_dir(dir).map_err(TextRepositoryCreationError::Io)? {
+        let dir_entry = dir_entry.map_err(TextRepositoryCreationError::Io)?;
+        let entry_path = dir_entry.path();
+        if !entry_path.is_dir() {
+            continue;
+        }
+        let entry_name = dir_entry.file_name();
+        let entry_name = entry_name.to_str().ok_or_else(|| {
+            TextRepositoryCreationError::BadTranslationDirectoryName(entry_name.to_owned())
+        })?;
+        let entry_langid = LanguageIdentifier::from_str(entry_name)
+            .map_err(TextRepositoryCreationError::InvalidTranslationDirectoryName)?;
+        translations.push((entry_langid, entry_path));
+    }
+    Ok(translations)
+}
+
 impl TextRepository {
     pub fn new() -> Self {
         TextRepository {
-            directory: PathBuf::new(),
-            bundle: FluentBundle::new(vec![unic_langid::langid!("en")]),
+            directories: Vec::new(),
+            bundle: FluentBundle::new_concurrent(vec![unic_langid::langid!("en")]),
         }
     }
 
@@ -114,104 +140,105 @@ impl TextRepository {
         path: &Path,
         langid: LanguageIdentifier,
     ) -> Result<Self, TextRepositoryCreationError> {
-        let mut dir_iter = std::fs::read_dir(path).map_err(TextRepositoryCreationError::Io)?;
-        let mut max_similar_path = match dir_iter.next() {
-            Some(Ok(dir_entry)) => dir_entry.path(),
-            Some(Err(e)) => return Err(TextRepositoryCreationError::Io(e)),
-            None => return Err(TextRepositoryCreationError::ZeroTranslationDirectories),
-        };
-        let mut max_similar_langid = langid.clone();
-        let mut max_similarity = {
-            let dir_name = max_similar_path.file_name().unwrap();
-            let dir_name = dir_name.to_str().ok_or_else(|| {
-                TextRepositoryCreationError::BadTranslationDirectoryName(dir_name.to_owned())
-            })?;
-            let entry_langid = LanguageIdentifier::from_str(dir_name)
-                .map_err(TextRepositoryCreationError::InvalidTranslationDirectoryName)?;
-            calc_langid_similarity(&langid, &entry_langid)
-        };
-        for dir_entry in dir_iter {
-            let dir_entry = dir_entry.map_err(TextRepositoryCreationError::Io)?;
-            let entry_path = dir_entry.path();
-            if !entry_path.is_dir() {
-                continue;
-            }
-            let entry_name = dir_entry.file_name();
-            let entry_name = entry_name.to_str().ok_or_else(|| {
-                TextRepositoryCreationError::BadTranslationDirectoryName(entry_name.to_owned())
-            })?;
-            let entry_langid = LanguageIdentifier::from_str(entry_name)
-                .map_err(TextRepositoryCreationError::InvalidTranslationDirectoryName)?;
-            let entry_similarity = calc_langid_similarity(&langid, &entry_langid);
-            if entry_similarity > max_similarity {
-                max_similarity = entry_similarity;
-                max_similar_path = dir_entry.path();
-                max_similar_langid = entry_langid;
-            }
+        if read_translations(path)?.is_empty() {
+            return Err(TextRepositoryCreationError::ZeroTranslationDirectories);
         }
-        if max_similar_langid != langid {
-            log::warn!(
-                "Loaded unideal translation, requested '{langid}', selected '{max_similar_langid}'"
-            );
-        }
-        let mut bundle = FluentBundle::new(vec![max_similar_langid]);
-        load_directory(&mut bundle, max_similar_path).map_err(|e| match e {
-            LoadTranslationError::ResourceRegistration(es) => {
-                TextRepositoryCreationError::ResourceRegistration(es)
-            }
-            LoadTranslationError::Io(e) => TextRepositoryCreationError::Io(e),
-            LoadTranslationError::Parsing(r, es) => TextRepositoryCreationError::Parsing(r, es),
-        })?;
-        Ok(TextRepository {
-            directory: path.to_owned(),
-            bundle,
-        })
+        let mut repo = TextRepository {
+            directories: vec![path.to_owned()],
+            bundle: FluentBundle::new_concurrent(vec![langid.clone()]),
+        };
+        repo.switch_translation(langid)?;
+        Ok(repo)
+    }
+
+    /// Adds translations from the directory of a pack, which override messages with the same ids.
+    /// The translation most similar to the specified one is reloaded, as the pack may add languages.
+    pub fn add_directory(
+        &mut self,
+        path: &Path,
+        langid: LanguageIdentifier,
+    ) -> Result<(), TextRepositoryCreationError> {
+        self.directories.push(path.to_owned());
+        self.switch_translation(langid)
+    }
+
+    /// Translation directories of the component followed by ones of packs adding to it.
+    pub fn directories(&self) -> &[PathBuf] {
+        &self.directories
     }
 
+    /// Loads the exact translation from all directories having it.
     pub fn switch_translation_exact(
         &mut self,
         langid: LanguageIdentifier,
-        path: PathBuf,
-    ) -> Result<(), LoadTranslationError> {
-        self.bundle = FluentBundle::new(vec![langid]);
-        load_directory(&mut self.bundle, path)
+    ) -> Result<(), TextRepositoryCreationError> {
+        let mut bundle = FluentBundle::new_concurrent(vec![langid.clone()]);
+        for (i, directory) in self.directories.iter().enumerate() {
+            for (entry_langid, path) in read_translations(directory)? {
+                if entry_langid != langid {
+                    continue;
+                }
+                load_directory(&mut bundle, path, i > 0).map_err(|e| match e {
+                    LoadTranslationError::ResourceRegistration(es) => {
+                        TextRepositoryCreationError::ResourceRegistration(es)
+                    }
+                    LoadTranslationError::Io(e) => TextRepositoryCreationError::Io(e),
+                    LoadTranslationError::Parsing(r, es) => {
+                        TextRepositoryCreationError::Parsing(r, es)
+                    }
+                })?;
+            }
+        }
+        self.bundle = bundle;
+        Ok(())
     }
 
+    /// Loads the translation most similar to the specified one among translations of all directories.
+    /// Nothing is loaded when there are no translations.
     pub fn switch_translation(
         &mut self,
         langid: LanguageIdentifier,
     ) -> Result<(), TextRepositoryCreationError> {
-        let switched = Self::from_directory(&self.directory, langid)?;
-        self.bundle = switched.bundle;
-        Ok(())
+        let mut max_similar: Option<(Option<i32>, LanguageIdentifier)> = None;
+        for entry_langid in self.translations()? {
+            let entry_similarity = calc_langid_similarity(&langid, &entry_langid);
+            let more_similar = match &max_similar {
+                Some((max_similarity, _)) => entry_similarity > *max_similarity,
+                None => true,
+            };
+            if more_similar {
+                max_similar = Some((entry_similarity, entry_langid));
+            }
+        }
+        let Some((_, max_similar_langid)) = max_similar else {
+            return Ok(());
+        };
+        if max_similar_langid != langid {
+            log::warn!(
+                "Loaded unideal translation, requested '{langid}', selected '{max_similar_langid}'"
+            );
+        }
+        self.switch_translation_exact(max_similar_langid)
     }
 
-    /// Lists subdirectories' names parsed as language identifiers along with their full paths.
-    pub fn available_translations(
-        dir: &Path,
-    ) -> anyhow::Result<Vec<(LanguageIdentifier, PathBuf)>> {
+    /// Languages of translations in any of the directories, in the order they are found.
+    pub fn translations(&self) -> Result<Vec<LanguageIdentifier>, TextRepositoryCreationError> {
         let mut translations = Vec::new();
-        for dir_entry in std::fs::read_dir(dir)? {
-            let dir_entry = dir_entry.map_err(TextRepositoryCreationError::Io)?;
-            let entry_path = dir_entry.path();
-            if !entry_path.is_dir() {
-                continue;
+        for directory in &self.directories {
+            for (langid, _) in read_translations(directory)? {
+                if !translations.contains(&langid) {
+                    translations.push(langid);
+                }
             }
-            let entry_name = dir_entry.file_name();
-            let entry_name = entry_name.to_str().ok_or_else(|| {
-                TextRepositoryCreationError::BadTranslationDirectoryName(entry_name.to_owned())
-            })?;
-            let entry_langid = LanguageIdentifier::from_str(entry_name)
-                .map_err(TextRepositoryCreationError::InvalidTranslationDirectoryName)?;
-            translations.push((entry_langid, entry_path));
         }
         Ok(translations)
     }
 
-    pub fn available_translations_core() -> anyhow::Result<Vec<(LanguageIdentifier, PathBuf)>> {
-        let mut path = Path::new(CORE_DIR).to_path_buf();
-        path.push(Path::new(COMPONENT_TEXTS_DIR));
-        Self::available_translations(&path)
+    /// Lists subdirectories' names parsed as language identifiers along with their full paths.
+    pub fn available_translations(
+        dir: &Path,
+    ) -> anyhow::Result<Vec<(LanguageIdentifier, PathBuf)>> {
+        Ok(read_translations(dir)?)
     }
 
     pub fn get<'a>(
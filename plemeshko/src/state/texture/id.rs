@@ -1,7 +1,9 @@
-use anyhow::Result;
+use anyhow::{anyhow, bail, ensure, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    params::TEXTURE_CELL_SIZE,
     state::{
         components::{ComponentId, RawFatLabel},
         config::Prepare,
@@ -17,7 +19,7 @@ pub(super) type RawTextureId = u32;
 #[serde(transparent)]
 pub struct TextureLabel(pub(super) String);
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 #[repr(transparent)]
 pub struct FatTextureLabel(RawFatLabel);
@@ -28,11 +30,17 @@ pub struct TextureId(pub(super) RawTextureId);
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct FatTextureId(pub ComponentId, pub TextureId);
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct FatTexturePartLabel {
     pub texture: FatTextureLabel,
     #[serde(flatten)]
     pub uv: Option<Rect<f32>>,
+    /// Column and row of the part in the atlas with square cells of `cell_size`, shorthand for `uv`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell: Option<[u32; 2]>,
+    /// Size of atlas cells in pixels, [`TEXTURE_CELL_SIZE`] by default.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cell_size: Option<u32>,
 }
 
 #[derive(Debug)]
@@ -126,9 +134,43 @@ impl Prepare for FatTexturePartLabel {
         tif: &mut crate::state::text::TextIdFactory,
     ) -> Result<Self::Prepared> {
         let texture = self.texture.prepare(ctx, tif)?;
-        Ok(FatTexturePartId {
-            texture,
-            uv: self.uv,
-        })
+        ensure!(
+            self.cell_size.is_none() || self.cell.is_some(),
+            "Texture part has `cell_size` without `cell`"
+        );
+        let uv = match (self.uv, self.cell) {
+            (uv, None) => uv,
+            (None, Some([column, row])) => {
+                let textures = if texture.0 == ctx.this_component.id() {
+                    ctx.this_component.textures
+                } else {
+                    &ctx.other_components.app.component(texture.0)?.textures
+                };
+                let [width, height] = textures
+                    .get(texture.1)
+                    .ok_or_else(|| anyhow!("Texture not loaded: {texture:?}"))?
+                    .size();
+                let cell_size = self.cell_size.unwrap_or(TEXTURE_CELL_SIZE);
+                ensure!(cell_size > 0, "Texture cell size must be positive");
+                // cells come from configs of any component, so their bounds may overflow
+                let cell_end = |index: u32| index.checked_add(1)?.checked_mul(cell_size);
+                let inside = cell_end(column).is_some_and(|right| right <= width as u32)
+                    && cell_end(row).is_some_and(|bottom| bottom <= height as u32);
+                ensure!(
+                    inside,
+                    "Cell [{column}, {row}] of size {cell_size} is outside of the texture of size {width}x{height}"
+                );
+                let cell_width = cell_size as f32 / width as f32;
+                let cell_height = cell_size as f32 / height as f32;
+                Some(Rect::new(
+                    column as f32 * cell_width,
+                    row as f32 * cell_height,
+                    cell_width,
+                    cell_height,
+                ))
+            }
+            (Some(_), Some(_)) => bail!("Texture part has both `cell` and uv coordinates"),
+        };
+        Ok(FatTexturePartId { texture, uv })
     }
 }
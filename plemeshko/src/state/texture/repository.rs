@@ -1,9 +1,12 @@
-use std::{io::Read, path::Path};
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Context, Result};
 use egui_extras::RetainedImage;
 
-use crate::state::{label_factory::LabelFactory, raw_indexer::RawIndexer};
+use crate::{
+    state::{label_factory::LabelFactory, raw_indexer::RawIndexer},
+    util::parallel_map,
+};
 
 use super::{RawTextureId, TextureId, TextureLabel};
 
@@ -18,13 +21,51 @@ impl TextureRepository {
         Self::default()
     }
 
+    /// Loads textures from the directory and its subdirectories, decoding them in parallel.
+    /// Ids are assigned in the order files are listed, regardless of which are decoded first.
     pub fn from_directory<P: AsRef<Path>>(directory: P) -> Result<Self> {
+        let mut files = Vec::new();
+        list_directory(&mut files, &mut LabelFactory::new(), directory.as_ref())?;
+        let decoded = parallel_map(files, |(label, file)| {
+            let texture = std::fs::read(&file)
+                .with_context(|| format!("Reading texture file: {}", file.display()))
+                .map(|bytes| RetainedImage::from_image_bytes(label.clone(), &bytes));
+            (label, file, texture)
+        });
         let mut repo = Self::new();
-        let mut lf = LabelFactory::new();
-        repo.load_directory(&mut Vec::new(), &mut lf, directory)?;
+        for (label, file, texture) in decoded {
+            match texture? {
+                Ok(texture) => {
+                    let index: usize = repo.indexer.create_id(label)?.try_into().unwrap();
+                    assert!(
+                        index == repo.textures.len(),
+                        "Indexer created index corresponds to the next element in the storage."
+                    );
+                    repo.textures.push(texture);
+                }
+                Err(e) => log::warn!("Texture file '{}' loading failed: {e}", file.display()),
+            }
+        }
         Ok(repo)
     }
 
+    /// Adds textures of a pack, replacing ones with the same labels.
+    pub fn extend(&mut self, pack: TextureRepository) -> Result<()> {
+        for (label, texture) in pack.indexer.id_to_label.into_iter().zip(pack.textures) {
+            match self.indexer.id(&label) {
+                Ok(id) => {
+                    let index: usize = id.try_into().unwrap();
+                    self.textures[index] = texture;
+                }
+                Err(_) => {
+                    self.indexer.create_id(label)?;
+                    self.textures.push(texture);
+                }
+            }
+        }
+        Ok(())
+    }
+
     pub fn get(&self, id: TextureId) -> Option<&RetainedImage> {
         let index: usize = id.0.try_into().unwrap();
         self.textures.get(index)
@@ -37,60 +78,35 @@ impl TextureRepository {
     pub fn id_from_raw(&self, label: &str) -> Result<TextureId> {
         self.indexer.id(label).map(TextureId)
     }
+}
 
-    fn load_directory<P: AsRef<Path>>(
-        &mut self,
-        buffer: &mut Vec<u8>,
-        lf: &mut LabelFactory,
-        directory: P,
-    ) -> Result<()> {
-        let dir_entries = std::fs::read_dir(&directory).with_context(|| {
-            format!(
-                "Loading textures from directory: {}",
-                directory.as_ref().display()
-            )
-        })?;
-        for dir_entry in dir_entries {
-            let dir_entry_path = dir_entry?.path();
-            let dir_entry_name = dir_entry_path
-                .file_stem()
-                .ok_or_else(|| {
-                    anyhow!(
-                        "Can't get stem of the textures dir '{}' entry: {}",
-                        directory.as_ref().display(),
-                        dir_entry_path.display()
-                    )
-                })?
-                .to_string_lossy();
-            if dir_entry_path.is_dir() {
-                lf.with_branch(&dir_entry_name, |lf| {
-                    self.load_directory(buffer, lf, &dir_entry_path)
-                })?;
-            } else if dir_entry_path.is_file() {
-                let label = lf.create(&dir_entry_name);
-                self.load_file(buffer, label, &dir_entry_path)?;
-            }
-        }
-        Ok(())
-    }
-
-    fn load_file(&mut self, buffer: &mut Vec<u8>, label: String, file: &Path) -> Result<()> {
-        let index: usize = self.indexer.create_id(label.clone())?.try_into().unwrap();
-        assert!(
-            index == self.textures.len(),
-            "Indexer created index corresponds to the next element in the storage."
-        );
-        buffer.clear();
-        std::fs::File::open(file)?.read_to_end(buffer)?;
-        match RetainedImage::from_image_bytes(label, buffer.as_mut()) {
-            Ok(texture) => self.textures.push(texture),
-            Err(e) => {
-                log::warn!("Texture file '{}' loading failed: {e}", file.display());
-                self.indexer
-                    .pop()
-                    .expect("Indexer was empty when trying to remove entry which wasn't fulfilled");
-            }
+/// Lists texture files in the directory and its subdirectories along with their labels.
+fn list_directory(
+    files: &mut Vec<(String, PathBuf)>,
+    lf: &mut LabelFactory,
+    directory: &Path,
+) -> Result<()> {
+    let dir_entries = std::fs::read_dir(directory)
+        .with_context(|| format!("Loading textures from directory: {}", directory.display()))?;
+    for dir_entry in dir_entries {
+        let dir_entry_path = dir_entry?.path();
+        let dir_entry_name = dir_entry_path
+            .file_stem()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Can't get stem of the textures dir '{}' entry: {}",
+                    directory.display(),
+                    dir_entry_path.display()
+                )
+            })?
+            .to_string_lossy();
+        if dir_entry_path.is_dir() {
+            lf.with_branch(&dir_entry_name, |lf| {
+                list_directory(files, lf, &dir_entry_path)
+            })?;
+        } else if dir_entry_path.is_file() {
+            files.push((lf.create(&dir_entry_name), dir_entry_path));
         }
-        Ok(())
     }
+    Ok(())
 }
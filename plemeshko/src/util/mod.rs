@@ -1,7 +1,9 @@
 pub mod cor;
 
+mod parallel;
 mod rect;
 
+pub use parallel::*;
 pub use rect::*;
 
 pub fn display_each<E: std::fmt::Display>(